@@ -0,0 +1,127 @@
+//! anonymize.rs
+//! `--anonymize`: replaces file paths with stable pseudonyms (`dir1/file3.rs`) and masks deny-listed terms,
+//! so a project's structure and names stay private when the bundle is shared.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::{Regex, RegexBuilder};
+
+/// Pseudonyms for the paths of one run and the terms to mask
+pub struct Anonymizer {
+    paths: HashMap<PathBuf, String>,
+    /// Nested relative paths to rewrite where contents mention them, with their pseudonyms
+    mentions: Option<(Regex, HashMap<String, String>)>,
+    terms: Option<(Regex, HashMap<String, String>)>,
+}
+
+impl Anonymizer {
+    /// Number the directories and files of `relative` in path order; the same tree always gets the same names
+    pub fn new(relative: &[PathBuf], terms: Vec<String>) -> Result<Self, String> {
+        let files: BTreeSet<&Path> = relative.iter().map(PathBuf::as_path).collect();
+        let dirs: BTreeSet<&Path> = files
+            .iter()
+            .flat_map(|f| f.ancestors().skip(1))
+            .filter(|d| !d.as_os_str().is_empty())
+            .collect();
+        let dir_names: HashMap<&Path, usize> = dirs.iter().enumerate().map(|(i, d)| (*d, i + 1)).collect();
+
+        let mut paths = HashMap::new();
+        for (i, file) in files.iter().enumerate() {
+            // Every ancestor directory keeps its own name, so nesting survives: `dir1/dir2/file3.rs`
+            let mut parts: Vec<String> = file.ancestors().skip(1).filter_map(|d| dir_names.get(d)).map(|n| format!("dir{}", n)).collect();
+            parts.reverse();
+            parts.push(format!("file{}", i + 1));
+            let mut pseudonym = parts.join("/");
+            if let Some(ext) = file.extension() {
+                pseudonym.push('.');
+                pseudonym.push_str(&ext.to_string_lossy());
+            }
+            paths.insert(file.to_path_buf(), pseudonym);
+        }
+
+        // Only nested paths are rewritten in contents; a bare file name like `a` would hit unrelated text
+        let mentions: HashMap<String, String> = paths
+            .iter()
+            .filter(|(path, _)| path.components().count() > 1)
+            .map(|(path, pseudonym)| (path.to_string_lossy().replace('\\', "/"), pseudonym.clone()))
+            .collect();
+        let mentions = alternation(mentions.keys(), false)?.map(|re| (re, mentions));
+
+        let term_names: HashMap<String, String> =
+            terms.iter().enumerate().map(|(i, term)| (term.to_lowercase(), format!("term{}", i + 1))).collect();
+        let terms = alternation(terms.iter(), true)?.map(|re| (re, term_names));
+        Ok(Anonymizer { paths, mentions, terms })
+    }
+
+    /// Number of paths with a pseudonym
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// The pseudonym of a relative path
+    pub fn path(&self, relative: &Path) -> String {
+        self.paths.get(relative).cloned().unwrap_or_else(|| "file".to_string())
+    }
+
+    /// Rewrite mentions of the run's paths and mask deny-listed terms, returning the new text and the replacements made
+    pub fn text(&self, content: &str) -> (String, usize) {
+        let mut count = 0;
+        let mut out = content.to_string();
+        for (re, names, fold) in [(&self.mentions, false), (&self.terms, true)]
+            .into_iter()
+            .filter_map(|(pair, fold)| pair.as_ref().map(|(re, names)| (re, names, fold)))
+        {
+            if !re.is_match(&out) {
+                continue;
+            }
+            out = re
+                .replace_all(&out, |caps: &regex::Captures| {
+                    count += 1;
+                    let found = &caps[0];
+                    let key = if fold { found.to_lowercase() } else { found.to_string() };
+                    names.get(&key).cloned().unwrap_or_else(|| found.to_string())
+                })
+                .into_owned();
+        }
+        (out, count)
+    }
+}
+
+/// Read a deny-list: one term per line, ignoring blank lines and `#` comments
+pub fn read_terms(path: &Path) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// A regex matching any of `literals`, longest first so a term never shadows a longer one it starts.
+/// Terms (`fold`) match case-insensitively anywhere; paths only where they don't continue a longer word.
+fn alternation<'a>(literals: impl Iterator<Item = &'a String>, fold: bool) -> Result<Option<Regex>, String> {
+    let mut literals: Vec<&String> = literals.filter(|l| !l.is_empty()).collect();
+    if literals.is_empty() {
+        return Ok(None);
+    }
+    literals.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let bounded = |l: &str| match fold {
+        true => regex::escape(l),
+        false => {
+            let start = if word(l.chars().next()) { r"\b" } else { "" };
+            let end = if word(l.chars().last()) { r"\b" } else { "" };
+            format!("{}{}{}", start, regex::escape(l), end)
+        }
+    };
+    let pattern = literals.iter().map(|l| bounded(l)).collect::<Vec<_>>().join("|");
+    RegexBuilder::new(&pattern)
+        .case_insensitive(fold)
+        .size_limit(1 << 26)
+        .build()
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,138 @@
+//! api.rs
+//! Reduces Rust files to their public surface: `pub` items with doc comments and signatures.
+
+use tree_sitter::Node;
+
+use crate::skeleton::{body_elisions, parse, splice};
+
+/// Keep only `pub` items of a Rust file; `None` if it fails to parse
+pub fn public_api(content: &str) -> Option<String> {
+    let tree = parse(content, "rs")?;
+    let mut out = emit_items(tree.root_node(), content);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// Public items of a file, module, or impl body, separated by blank lines
+fn emit_items(list: Node, src: &str) -> String {
+    let mut out = String::new();
+    // Start of the doc comments and attributes that belong to the next item
+    let mut leading: Option<usize> = None;
+    let mut in_inner_docs = false;
+    let mut cursor = list.walk();
+    for child in list.named_children(&mut cursor) {
+        let text = &src[child.byte_range()];
+        match child.kind() {
+            "line_comment" | "block_comment" if text.starts_with("//!") || text.starts_with("/*!") => {
+                if !in_inner_docs && !out.is_empty() {
+                    out.push_str("\n\n");
+                } else if in_inner_docs {
+                    out.push('\n');
+                }
+                out.push_str(src[line_start(src, child.start_byte())..child.end_byte()].trim_end());
+                in_inner_docs = true;
+            }
+            "line_comment" | "block_comment" | "attribute_item" => {
+                leading.get_or_insert(line_start(src, child.start_byte()));
+            }
+            "inner_attribute_item" => {}
+            _ => {
+                let start = leading.take().unwrap_or_else(|| line_start(src, child.start_byte()));
+                if let Some(item) = item_api(child, src, start) {
+                    if !out.is_empty() {
+                        out.push_str("\n\n");
+                    }
+                    out.push_str(item.trim_end());
+                    in_inner_docs = false;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// The public form of one item (from `start`, which includes its docs), or `None` if it is private
+fn item_api(node: Node, src: &str, start: usize) -> Option<String> {
+    let whole = start..node.end_byte();
+    match node.kind() {
+        "function_item" | "function_signature_item" | "enum_item" | "trait_item" | "type_item"
+        | "const_item" | "static_item" => {
+            is_pub(node, src).then(|| splice(src, whole, &body_elisions(node, "rs")))
+        }
+        "use_declaration" | "extern_crate_declaration" => is_pub(node, src).then(|| src[whole].to_string()),
+        "struct_item" | "union_item" => is_pub(node, src).then(|| splice(src, whole, &private_fields(node, src))),
+        "macro_definition" => src[start..node.start_byte()]
+            .contains("macro_export")
+            .then(|| src[whole].to_string()),
+        "mod_item" => {
+            if !is_pub(node, src) {
+                return None;
+            }
+            match node.child_by_field_name("body") {
+                Some(body) => Some(nested(src, start, body, emit_items(body, src))),
+                None => Some(src[whole].to_string()),
+            }
+        }
+        "impl_item" => {
+            let body = node.child_by_field_name("body")?;
+            if node.child_by_field_name("trait").is_some() {
+                // Trait impls are part of the surface, but their bodies are not
+                return Some(format!("{}{{ ... }}", &src[start..body.start_byte()]));
+            }
+            let items = emit_items(body, src);
+            (!items.is_empty()).then(|| nested(src, start, body, items))
+        }
+        _ => None,
+    }
+}
+
+/// An item header followed by a rebuilt `{ ... }` body
+fn nested(src: &str, start: usize, body: Node, items: String) -> String {
+    let indent: String = src[start..].chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+    if items.is_empty() {
+        format!("{}{{}}", &src[start..body.start_byte()])
+    } else {
+        format!("{}{{\n{}\n{}}}", &src[start..body.start_byte()], items, indent)
+    }
+}
+
+/// Removals for non-`pub` named fields, including their docs and trailing comma
+fn private_fields(node: Node, src: &str) -> Vec<(usize, usize, String)> {
+    let mut edits = Vec::new();
+    let Some(body) = node.child_by_field_name("body").filter(|b| b.kind() == "field_declaration_list") else {
+        return edits;
+    };
+    let mut boundary = body.start_byte() + 1;
+    let mut cursor = body.walk();
+    let children: Vec<Node> = body.children(&mut cursor).collect();
+    for (i, child) in children.iter().enumerate() {
+        match child.kind() {
+            "{" | "," => boundary = child.end_byte(),
+            "field_declaration" if !is_pub(*child, src) => {
+                let end = match children.get(i + 1) {
+                    Some(next) if next.kind() == "," => next.end_byte(),
+                    _ => child.end_byte(),
+                };
+                edits.push((boundary, end, String::new()));
+            }
+            _ => {}
+        }
+    }
+    edits
+}
+
+/// Whether the item is plain `pub` (restricted visibility like `pub(crate)` is not public API)
+fn is_pub(node: Node, src: &str) -> bool {
+    let mut cursor = node.walk();
+    let public = node
+        .children(&mut cursor)
+        .any(|c| c.kind() == "visibility_modifier" && &src[c.byte_range()] == "pub");
+    public
+}
+
+/// Byte offset of the start of the line containing `i`
+fn line_start(src: &str, i: usize) -> usize {
+    src[..i].rfind('\n').map_or(0, |p| p + 1)
+}
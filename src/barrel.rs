@@ -0,0 +1,57 @@
+//! barrel.rs
+//! `--flatten-barrels`: follows imports through `index.ts`-style files that only re-export other modules,
+//! so `{ext}` resolution packs the implementations instead of the barrel.
+
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{deps, encoding, lang, tsconfig};
+
+static COMMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap());
+
+/// `export * from './x'`, `export * as ns from './x'`, `export { a, type B } from './x'`, `export type { C } from './x'`
+static REEXPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"export\s+(?:type\s+)?(?:\*(?:\s+as\s+\w+)?|\{[^}]*\})\s*from\s*['"]([^'"]+)['"]\s*;?"#).unwrap()
+});
+
+/// The specifiers `path` re-exports, when it is a JS/TS index file whose every statement is a relative re-export
+fn reexports(path: &Path) -> Option<Vec<String>> {
+    let is_index = path.file_stem().is_some_and(|s| s == "index");
+    if !is_index || !matches!(lang::language(path), Some("javascript" | "typescript")) {
+        return None;
+    }
+    let content = encoding::read_to_string(path).ok()?;
+    let code = COMMENT.replace_all(&content, "");
+    let specs: Vec<String> = REEXPORT.captures_iter(&code).map(|cap| cap[1].to_string()).collect();
+    let rest = REEXPORT.replace_all(&code, "");
+    let pure = !specs.is_empty() && rest.trim().is_empty() && specs.iter().all(|s| s.starts_with('.'));
+    pure.then_some(specs)
+}
+
+/// The modules behind `path`, through nested barrels, or `None` when `path` is not a barrel.
+/// Re-exports that resolve to no file under `root` are dropped.
+pub fn flatten(path: &Path, root: &Path) -> Option<Vec<PathBuf>> {
+    let specs = reexports(path)?;
+    let mut modules = Vec::new();
+    let mut seen = HashSet::from([path.to_path_buf()]);
+    let mut queue = VecDeque::from([(path.to_path_buf(), specs)]);
+    while let Some((barrel, specs)) = queue.pop_front() {
+        for spec in specs {
+            let target = deps::resolve(&barrel, &spec, root).and_then(|p| tsconfig::probe(&p));
+            let Some(target) = target.filter(|p| p.starts_with(root)) else {
+                continue;
+            };
+            if !seen.insert(target.clone()) {
+                continue;
+            }
+            match reexports(&target) {
+                Some(nested) => queue.push_back((target, nested)),
+                None => modules.push(target),
+            }
+        }
+    }
+    Some(modules)
+}
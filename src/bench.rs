@@ -0,0 +1,207 @@
+//! bench.rs
+//! `bound bench`: generates a synthetic tree and times the full pipeline over it, reporting files/s, MB/s, and tokens/s.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::logging::Logger;
+use crate::progress::human_bytes;
+
+/// Files per generated directory
+const FILES_PER_DIR: usize = 50;
+
+/// Shape of the generated tree and how often to run over it
+pub struct BenchConfig {
+    pub files: usize,
+    pub file_size: usize,
+    pub runs: usize,
+    /// Where to generate the tree and keep it; a temporary directory otherwise
+    pub dir: Option<PathBuf>,
+    /// Extra options for each run (a filter, `--strip-comments`, ...)
+    pub options: Vec<String>,
+}
+
+/// Totals of one timed run
+struct Sample {
+    files: usize,
+    bytes: usize,
+    tokens: usize,
+    secs: f64,
+}
+
+impl Sample {
+    fn line(&self) -> String {
+        format!(
+            "{:>8.3}s  {:>10.0} files/s  {:>8.1} MB/s  {:>12.0} tokens/s",
+            self.secs,
+            self.files as f64 / self.secs,
+            self.bytes as f64 / self.secs / 1e6,
+            self.tokens as f64 / self.secs
+        )
+    }
+}
+
+/// Generate the tree, run the pipeline `runs` times, and print the throughput of each run, the best, and the median
+pub fn run(config: &BenchConfig, logger: &Logger) -> Result<(), String> {
+    if config.files == 0 || config.runs == 0 {
+        return Err("bound bench needs at least one file and one run".to_string());
+    }
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    let scratch = std::env::temp_dir().join(format!("bound-bench-{}-{}", std::process::id(), nanos));
+    fs::create_dir_all(&scratch).map_err(|e| format!("Cannot create {}: {}", scratch.display(), e))?;
+    let tree = config.dir.clone().unwrap_or_else(|| scratch.join("tree"));
+
+    let result = generate(&tree, config.files, config.file_size).and_then(|bytes| {
+        logger.info(&format!("Generated {} files ({}) in {}", config.files, human_bytes(bytes as f64), tree.display()));
+        let bundle = scratch.join("bundle.txt");
+        let mut samples = Vec::new();
+        for n in 1..=config.runs {
+            let sample = time_run(&tree, &bundle, &config.options)?;
+            println!("run {:>2}  {}", n, sample.line());
+            samples.push(sample);
+        }
+        samples.sort_by(|a, b| a.secs.total_cmp(&b.secs));
+        let first = &samples[0];
+        println!(
+            "{} files, {} read, {} tokens per run",
+            first.files,
+            human_bytes(first.bytes as f64),
+            first.tokens
+        );
+        println!("best    {}", first.line());
+        println!("median  {}", samples[samples.len() / 2].line());
+        Ok(())
+    });
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+/// Pack `tree` into `bundle` once, with the token cache off so every run tokenizes
+fn time_run(tree: &Path, bundle: &Path, options: &[String]) -> Result<Sample, String> {
+    let mut args: Vec<String> = ["--progress", "json", "--no-cache", "--force", "--out"].map(String::from).to_vec();
+    args.push(bundle.to_string_lossy().into_owned());
+    args.extend(options.iter().cloned());
+    args.push(tree.to_string_lossy().into_owned());
+
+    let started = Instant::now();
+    let (_, stderr) = run_bound(&args)?;
+    let secs = started.elapsed().as_secs_f64().max(f64::EPSILON);
+    let stats = stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|event| event["event"] == "run_complete")
+        .ok_or("the run reported no totals")?;
+    let count = |key: &str| stats[key].as_u64().unwrap_or(0) as usize;
+    Ok(Sample { files: count("files"), bytes: count("bytes"), tokens: count("tokens"), secs })
+}
+
+/// Run this executable with `args`, returning stdout and stderr, or the error it printed
+fn run_bound(args: &[String]) -> Result<(String, String), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let output = Command::new(exe)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    if output.status.success() {
+        return Ok((String::from_utf8_lossy(&output.stdout).into_owned(), stderr));
+    }
+    // `main` prints errors as `Error: "message"` after any log lines; clap prints usage errors as they are
+    match stderr.lines().rev().find_map(|line| line.strip_prefix("Error: ")) {
+        Some(message) => Err(serde_json::from_str::<String>(message).unwrap_or_else(|_| message.to_string())),
+        None => Err(stderr.trim().to_string()),
+    }
+}
+
+/// Write `files` source files of about `file_size` bytes under `root`, returning the bytes written.
+/// Languages rotate through Rust, Python, JavaScript, and Markdown, and each file imports its neighbours,
+/// so traversal, reference parsing, and tokenization all get exercised.
+fn generate(root: &Path, files: usize, file_size: usize) -> Result<usize, String> {
+    let mut total = 0;
+    for i in 0..files {
+        let dir = root.join(format!("mod{:03}", i / FILES_PER_DIR));
+        let (ext, content) = match i % 4 {
+            0 => ("rs", rust_file(i, files, file_size)),
+            1 => ("py", python_file(i, files, file_size)),
+            2 => ("js", js_file(i, files, file_size)),
+            _ => ("md", markdown_file(i, file_size)),
+        };
+        let path = dir.join(format!("file{:05}.{}", i, ext));
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, &content))
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        total += content.len();
+    }
+    Ok(total)
+}
+
+/// A file of the same language as `i`, a few files further on
+fn neighbour(i: usize, step: usize, files: usize) -> usize {
+    (i + 4 * step) % files.max(1)
+}
+
+/// Repeat `unit(n)` until the text is about `size` bytes long
+fn fill(mut text: String, size: usize, unit: impl Fn(usize) -> String) -> String {
+    let mut n = 0;
+    while text.len() < size {
+        text.push_str(&unit(n));
+        n += 1;
+    }
+    text
+}
+
+fn rust_file(i: usize, files: usize, size: usize) -> String {
+    let mut head = String::from("//! Generated by bound bench\n\n");
+    for step in 1..=2 {
+        let j = neighbour(i, step, files);
+        head.push_str(&format!("use crate::mod{:03}::file{:05};\n", j / FILES_PER_DIR, j));
+    }
+    fill(head, size, |n| {
+        format!(
+            "\n/// Computes value {0} from its inputs\npub fn compute_{0}(input: &[u64], scale: u64) -> u64 {{\n    // Sum, then scale\n    input.iter().map(|x| x.wrapping_mul({0} + scale)).sum::<u64>() ^ 0x{0:x}\n}}\n",
+            n
+        )
+    })
+}
+
+fn python_file(i: usize, files: usize, size: usize) -> String {
+    let mut head = String::from("\"\"\"Generated by bound bench\"\"\"\n\n");
+    for step in 1..=2 {
+        let j = neighbour(i, step, files);
+        head.push_str(&format!("from mod{:03}.file{:05} import helper_0\n", j / FILES_PER_DIR, j));
+    }
+    fill(head, size, |n| {
+        format!(
+            "\n\ndef helper_{0}(values, scale=1):\n    # Scale and sum the values\n    return sum(v * scale for v in values) + {0}\n",
+            n
+        )
+    })
+}
+
+fn js_file(i: usize, files: usize, size: usize) -> String {
+    let mut head = String::from("// Generated by bound bench\n\n");
+    for step in 1..=2 {
+        let j = neighbour(i, step, files);
+        head.push_str(&format!("const dep{} = require('../mod{:03}/file{:05}');\n", step, j / FILES_PER_DIR, j));
+    }
+    fill(head, size, |n| {
+        format!(
+            "\n/** Handler {0} */\nexport function handle{0}(event) {{\n  const total = event.items.reduce((a, b) => a + b, {0});\n  return {{ id: '{0}', total }};\n}}\n",
+            n
+        )
+    })
+}
+
+fn markdown_file(i: usize, size: usize) -> String {
+    fill(format!("# Notes {}\n", i), size, |n| {
+        format!(
+            "\n## Section {0}\n\nThe quick brown fox jumps over the lazy dog while the build system recompiles module {0}. See `compute_{0}` for details.\n",
+            n
+        )
+    })
+}
@@ -0,0 +1,196 @@
+//! cache.rs
+//! Persists per-file token counts keyed by path, mtime, and size so warm runs skip recounting.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::documents;
+use crate::model::Tokenizer;
+use crate::notebook::{extract, is_notebook, NotebookMode};
+use crate::images;
+use crate::tabular;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    mtime_ns: u64,
+    size: u64,
+    tokens: usize,
+    /// How the content was transformed before counting (the notebook mode for `.ipynb` files)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    variant: String,
+}
+
+/// Entries by cache file, kept between runs of one process once `share_across_runs` is called (`bound serve`)
+static SHARED: Mutex<Option<HashMap<PathBuf, HashMap<PathBuf, CacheEntry>>>> = Mutex::new(None);
+
+/// Keep loaded caches in memory so later runs in this process skip reading and parsing them
+pub fn share_across_runs() {
+    let mut shared = SHARED.lock().unwrap_or_else(PoisonError::into_inner);
+    shared.get_or_insert_with(HashMap::new);
+}
+
+/// Apply `f` to the shared caches, if runs share them
+fn shared<T>(f: impl FnOnce(&mut HashMap<PathBuf, HashMap<PathBuf, CacheEntry>>) -> T) -> Option<T> {
+    SHARED.lock().unwrap_or_else(PoisonError::into_inner).as_mut().map(f)
+}
+
+/// Token counts for the files under one root, stored in the user cache directory
+pub struct TokenCache {
+    tokenizer: Tokenizer,
+    notebook: NotebookMode,
+    /// Rows kept of CSV and TSV files (`--csv-rows`)
+    csv_rows: Option<usize>,
+    /// Count images by their placeholder line rather than their bytes
+    image_placeholders: bool,
+    /// Files over this many tokens are summarized, so they cost at most this much in a budget
+    summary_cap: Option<usize>,
+    file: Option<PathBuf>,
+    entries: HashMap<PathBuf, CacheEntry>,
+    dirty: bool,
+}
+
+impl TokenCache {
+    /// Load the cache for `root` and `tokenizer`; a missing or corrupt cache starts empty
+    pub fn load(root: &Path, tokenizer: Tokenizer) -> Self {
+        let file = cache_dir().map(|dir| dir.join(format!("{}-{}.json", root_key(root), tokenizer.name())));
+        let kept = file.as_ref().and_then(|f| shared(|loaded| loaded.get(f).cloned()).flatten());
+        let entries = kept.unwrap_or_else(|| {
+            let entries: HashMap<PathBuf, CacheEntry> = file
+                .as_ref()
+                .and_then(|f| fs::read(f).ok())
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .unwrap_or_default();
+            if let Some(f) = &file {
+                shared(|loaded| loaded.insert(f.clone(), entries.clone()));
+            }
+            entries
+        });
+        TokenCache { tokenizer, notebook: NotebookMode::Code, csv_rows: None, image_placeholders: false, summary_cap: None, file, entries, dirty: false }
+    }
+
+    /// A cache that never reads or writes disk (for `--no-cache`)
+    pub fn disabled(tokenizer: Tokenizer) -> Self {
+        TokenCache { tokenizer, notebook: NotebookMode::Code, csv_rows: None, image_placeholders: false, summary_cap: None, file: None, entries: HashMap::new(), dirty: false }
+    }
+
+    /// Count notebooks by the cells `mode` keeps rather than their raw JSON
+    pub fn with_notebook(mut self, mode: NotebookMode) -> Self {
+        self.notebook = mode;
+        self
+    }
+
+    /// Count CSV and TSV files by the rows `--csv-rows` keeps
+    pub fn with_csv_rows(mut self, rows: Option<usize>) -> Self {
+        self.csv_rows = rows;
+        self
+    }
+
+    /// Count images as the placeholder line that stands in for them
+    pub fn with_image_placeholders(mut self, enabled: bool) -> Self {
+        self.image_placeholders = enabled;
+        self
+    }
+
+    /// Budget files of more than `cap` tokens as `cap`, since `--summarize-over` will shrink them
+    pub fn with_summary_cap(mut self, cap: Option<usize>) -> Self {
+        self.summary_cap = cap;
+        self
+    }
+
+    /// The tokenizer counts are computed with
+    pub fn tokenizer(&self) -> Tokenizer {
+        self.tokenizer
+    }
+
+    /// Token count for `path`, read from the cache when its mtime and size are unchanged
+    pub fn tokens(&mut self, path: &Path) -> io::Result<usize> {
+        let meta = fs::metadata(path)?;
+        let mtime_ns = mtime_ns(&meta);
+        let size = meta.len();
+        let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+        let notebook = is_notebook(ext).then_some(self.notebook);
+        let preview = self.csv_rows.zip(tabular::delimiter(ext));
+        let variant = match (notebook, preview) {
+            (Some(mode), _) => mode.as_str().to_string(),
+            (None, Some((rows, _))) => format!("rows:{}", rows),
+            (None, None) => String::new(),
+        };
+
+        if self.image_placeholders && images::is_image(path) {
+            // The placeholder is cheaper to build than a cache lookup; its path is approximated by the file name
+            let name = path.file_name().map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+            return Ok(self.tokenizer.count(&images::placeholder(path, &name)?));
+        }
+
+        if let Some(entry) = self.entries.get(path) {
+            if entry.mtime_ns == mtime_ns && entry.size == size && entry.variant == variant {
+                return Ok(entry.tokens);
+            }
+        }
+
+        let content = documents::read_to_string(path)?;
+        let content = match (notebook, preview) {
+            (Some(mode), _) => extract(&content, mode).unwrap_or(content),
+            (None, Some((rows, delimiter))) => tabular::preview(&content, delimiter, rows),
+            (None, None) => content,
+        };
+        let tokens = self.tokenizer.count(&content);
+        self.entries.insert(path.to_path_buf(), CacheEntry { mtime_ns, size, tokens, variant });
+        self.dirty = true;
+        Ok(tokens)
+    }
+
+    /// Tokens `path` is expected to take in the bundle: its count, capped where it will be summarized
+    pub fn budget_tokens(&mut self, path: &Path) -> io::Result<usize> {
+        let tokens = self.tokens(path)?;
+        Ok(self.summary_cap.map_or(tokens, |cap| tokens.min(cap)))
+    }
+
+    /// Write the cache back if anything changed, dropping entries for deleted files
+    pub fn save(&mut self) -> io::Result<()> {
+        let Some(file) = &self.file else { return Ok(()) };
+        if !self.dirty {
+            return Ok(());
+        }
+        self.entries.retain(|path, _| path.exists());
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec(&self.entries)?;
+        fs::write(file, json)?;
+        shared(|loaded| loaded.insert(file.clone(), self.entries.clone()));
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Modification time in nanoseconds since the epoch (0 if unavailable)
+pub fn mtime_ns(meta: &fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// `$XDG_CACHE_HOME/bound`, falling back to `~/.cache/bound`
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))?;
+    Some(base.join("bound"))
+}
+
+/// Short stable file name for a root directory
+fn root_key(root: &Path) -> String {
+    let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
@@ -0,0 +1,50 @@
+//! chunk.rs
+//! Splits file contents into overlapping, line-aligned chunks for `--chunk-tokens`.
+
+use crate::model::Tokenizer;
+
+/// A run of whole lines from one file
+pub struct Chunk {
+    /// 1-based first line
+    pub start_line: usize,
+    /// 1-based last line (inclusive)
+    pub end_line: usize,
+    pub tokens: usize,
+    pub content: String,
+}
+
+/// Split `content` into chunks of at most `max_tokens`, each repeating up to `overlap` tokens of
+/// trailing lines from the previous one. A single line over the limit becomes its own chunk.
+pub fn chunk(content: &str, tokenizer: &Tokenizer, max_tokens: usize, overlap: usize) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let counts: Vec<usize> = lines.iter().map(|l| tokenizer.count(l)).collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        while end < lines.len() && (end == start || tokens + counts[end] <= max_tokens) {
+            tokens += counts[end];
+            end += 1;
+        }
+        let text = lines[start..end].concat();
+        chunks.push(Chunk {
+            start_line: start + 1,
+            end_line: end,
+            tokens: tokenizer.count(&text),
+            content: text,
+        });
+        if end == lines.len() {
+            break;
+        }
+        // Back up over trailing lines that fit the overlap, always advancing at least one line
+        let mut next = end;
+        let mut carried = 0;
+        while next > start + 1 && carried + counts[next - 1] <= overlap {
+            carried += counts[next - 1];
+            next -= 1;
+        }
+        start = next;
+    }
+    chunks
+}
@@ -0,0 +1,79 @@
+//! classify.rs
+//! Recognizes lock files, vendored code, and generated or minified files, which are skipped by default.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read from the start of a file when looking for markers and long lines
+const SAMPLE_BYTES: u64 = 8 * 1024;
+
+/// A line this long in JS/CSS means the file is minified
+const MINIFIED_LINE: usize = 1000;
+
+/// File name suffixes of generated outputs (minified bundles, source maps, protobuf stubs)
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".min.js", ".min.mjs", ".min.css", ".map", ".pb.go", ".pb.cc", ".pb.h", "_pb2.py", "_pb2_grpc.py", "_pb2.pyi",
+];
+
+/// Lines at the top of a file searched for generator markers
+const HEADER_LINES: usize = 10;
+
+/// Header markers tools put in files they generate
+const GENERATED_MARKERS: &[&str] = &["@generated", "DO NOT EDIT", "Code generated by", "amalgamation"];
+
+/// Package-manager lock files
+const LOCK_FILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+    "pubspec.lock",
+];
+
+/// Directory names that hold third-party code
+const VENDOR_DIRS: &[&str] = &["vendor", "vendored", "third_party", "third-party", "thirdparty", "node_modules"];
+
+/// Whether `path` is a package-manager lock file
+pub fn is_lock(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| LOCK_FILES.contains(&n))
+}
+
+/// Whether `path` sits under a vendored directory below `root`
+pub fn is_vendored(path: &Path, root: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let dirs = relative.parent().into_iter().flat_map(|p| p.components());
+    dirs.filter_map(|c| c.as_os_str().to_str()).any(|c| VENDOR_DIRS.contains(&c))
+}
+
+/// Whether `path` is a generated or minified file, judged by name and its first few KiB
+pub fn is_generated(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    if GENERATED_SUFFIXES.iter().any(|s| name.ends_with(s)) {
+        return true;
+    }
+    let Some(sample) = read_sample(path) else {
+        return false;
+    };
+    if sample.lines().take(HEADER_LINES).any(|l| GENERATED_MARKERS.iter().any(|m| l.contains(m))) {
+        return true;
+    }
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    matches!(ext, "js" | "mjs" | "cjs" | "css") && sample.lines().any(|l| l.len() >= MINIFIED_LINE)
+}
+
+fn read_sample(path: &Path) -> Option<String> {
+    let mut bytes = Vec::new();
+    File::open(path).ok()?.take(SAMPLE_BYTES).read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -0,0 +1,664 @@
+//! cli.rs
+//! Command-line interface: subcommands and the shared aggregation options.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+
+use crate::clipboard::ClipboardMode;
+use crate::error::ErrorFormat;
+use crate::lines::{split_target, Target};
+use crate::model::Model;
+use crate::notebook::NotebookMode;
+use crate::order::FileOrder;
+use crate::plugins::FilterCmd;
+use crate::output::Compression;
+use crate::pack::{DirBudget, PackStrategy};
+use crate::progress::ProgressMode;
+use crate::template::Template;
+use crate::truncate::TruncateStrategy;
+
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub pack: PackArgs,
+
+    /// How a failed run reports its error on stderr: `Error: "message"` or one JSON object with a kind
+    #[arg(long, value_enum, default_value = "text", global = true)]
+    pub error_format: ErrorFormat,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Aggregate only files changed in a git revision range (e.g. HEAD~3..HEAD)
+    Diff {
+        /// Revision range passed to `git diff`
+        range: String,
+
+        /// Include only the changed hunks of each file instead of the whole file
+        #[arg(long)]
+        hunks_only: bool,
+
+        /// Unchanged lines kept around each hunk with --hunks-only
+        #[arg(short = 'C', long, value_name = "N", default_value_t = 3, requires = "hunks_only")]
+        context: usize,
+
+        #[command(flatten)]
+        pack: PackArgs,
+    },
+    /// Print token totals per directory and extension without building a bundle
+    Count {
+        #[command(flatten)]
+        pack: PackArgs,
+    },
+    /// Pack the files most relevant to a keyword query, most relevant first
+    Query {
+        /// Keywords to score files against (e.g. "websocket reconnect logic")
+        query: String,
+
+        /// Number of files to keep
+        #[arg(short = 'k', long = "top", default_value_t = 20)]
+        top_k: usize,
+
+        #[command(flatten)]
+        pack: PackArgs,
+    },
+    /// Pack a file with every file that imports or includes it, directly or transitively
+    Rdeps {
+        /// File whose dependents to collect
+        file: PathBuf,
+
+        #[command(flatten)]
+        pack: PackArgs,
+    },
+    /// Pack unified diffs of the files added, deleted, or changed between two snapshots
+    Compare {
+        /// The old snapshot: a directory or an archive (.tar, .tar.gz, .zip, ...)
+        old: PathBuf,
+
+        /// The new snapshot: a directory or an archive
+        new: PathBuf,
+
+        #[command(flatten)]
+        pack: PackArgs,
+    },
+    /// Answer requests for packed context from other programs
+    Serve {
+        /// Speak the Model Context Protocol on stdin/stdout, offering pack_directory, count_tokens, and list_files tools
+        #[arg(long, conflicts_with = "http", required_unless_present = "http")]
+        mcp: bool,
+
+        /// Listen on ADDR (e.g. 127.0.0.1:7878) for POST /pack requests with a JSON body of root, filter, budget, model, and format
+        #[arg(long, value_name = "ADDR")]
+        http: Option<String>,
+    },
+    /// Time the pipeline over a generated tree and report files/s, MB/s, and tokens/s
+    Bench {
+        /// Number of files to generate
+        #[arg(long, default_value_t = 1000)]
+        files: usize,
+
+        /// Approximate size of each generated file
+        #[arg(long, value_name = "BYTES", default_value_t = 4096)]
+        file_size: usize,
+
+        /// Number of timed runs; the best and the median are reported
+        #[arg(long, default_value_t = 3)]
+        runs: usize,
+
+        /// Generate the tree in DIR and keep it, instead of a temporary directory
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+
+        /// Options for each run, after `--` (e.g. `-- '{rs}' --strip-comments`)
+        #[arg(last = true, value_name = "OPTIONS")]
+        options: Vec<String>,
+    },
+    /// Print a shell completion script for subcommands and options
+    Completions {
+        /// Shell to generate the script for
+        shell: clap_complete::Shell,
+    },
+    /// Serve clipboard text from stdin until another program takes the selection (started by `--clipboard-mode`)
+    #[command(hide = true)]
+    ClipboardServe {
+        #[arg(long)]
+        primary: bool,
+    },
+    /// Split an aggregated bundle back into files
+    Unpack {
+        /// Bundle written by bound (`-` for stdin)
+        bundle: PathBuf,
+
+        /// Directory to recreate the files in
+        #[arg(long, default_value = ".")]
+        dest: PathBuf,
+
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PackArgs {
+    /// Optional language filter ([.ext] or {.ext}) followed by directories, files (optionally FILE:START-END), or git URLs
+    #[arg(value_name = "[FILTER] PATHS")]
+    pub targets: Vec<String>,
+
+    /// Exclude files with these extensions (comma-separated, e.g. lock,min.js)
+    #[arg(long, value_name = "EXTS", value_delimiter = ',')]
+    pub exclude_ext: Vec<String>,
+
+    /// With a `{ext}` filter, write unresolved references and reference cycles to FILE as JSON
+    #[arg(long, value_name = "FILE")]
+    pub deps_report: Option<PathBuf>,
+
+    /// With a `{ext}` filter, let a reference resolve to a file of another language with the same name
+    #[arg(long)]
+    pub deps_cross_lang: bool,
+
+    /// With a `{ext}` filter, resolve C/C++ `#include`s through the include directories and defines in FILE
+    #[arg(long, value_name = "FILE")]
+    pub compile_commands: Option<PathBuf>,
+
+    /// With a `{ext}` filter, follow imports of an index.ts that only re-exports through to the modules behind it
+    #[arg(long)]
+    pub flatten_barrels: bool,
+
+    /// Let extension filters match extensionless files by shebang or well-known name (`bin/deploy`, Makefile)
+    #[arg(long)]
+    pub detect_lang: bool,
+
+    /// Read project defaults from FILE instead of the nearest bound.toml or .bound.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Apply the [profiles.NAME] section of the config on top of its defaults
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Token limit per file
+    #[arg(short = 't', long)]
+    pub token_limit: Option<usize>,
+
+    /// Size limit per file (bytes)
+    #[arg(short = 's', long)]
+    pub size_limit: Option<usize>,
+
+    /// Target model: selects the tokenizer and a default --budget of its context window minus a reserve
+    #[arg(long, value_enum)]
+    pub model: Option<Model>,
+
+    /// Total token budget for the aggregation; overflowing it is reported loudly
+    #[arg(long)]
+    pub budget: Option<usize>,
+
+    /// How to choose files when they don't all fit in the budget
+    #[arg(long, value_enum)]
+    pub pack: Option<PackStrategy>,
+
+    /// Aggregate at most N files, keeping the first N in bundle order
+    #[arg(long, value_name = "N")]
+    pub max_files: Option<usize>,
+
+    /// Don't ask for confirmation before aggregating a very large tree
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// Estimate the total size from a sample of directories instead of sizing every file (faster start, rougher EBT)
+    #[arg(long)]
+    pub estimate: bool,
+
+    /// Token budget for the files under a directory, as DIR=TOKENS (repeatable; the most specific directory applies)
+    #[arg(long = "dir-budget", value_name = "DIR=TOKENS", value_parser = DirBudget::parse)]
+    pub dir_budgets: Vec<DirBudget>,
+
+    /// Order of files in the bundle (default: alphabetical, or list order with --files-from)
+    #[arg(long, value_enum)]
+    pub order: Option<FileOrder>,
+
+    /// Put FILE at the top of the bundle, whatever the order (repeatable, in the order given)
+    #[arg(long, value_name = "FILE")]
+    pub first: Vec<PathBuf>,
+
+    /// Put FILE at the bottom of the bundle, whatever the order (repeatable, in the order given)
+    #[arg(long, value_name = "FILE")]
+    pub last: Vec<PathBuf>,
+
+    /// Exit with code 2 instead of writing output when the budget is exceeded
+    #[arg(long)]
+    pub fail_on_overflow: bool,
+
+    /// Skip files larger than this instead of truncating them (e.g. 500K, 10M)
+    #[arg(long, value_parser = parse_size)]
+    pub max_file_size: Option<u64>,
+
+    /// Skip files whose read or transform (--filter-cmd, --plugins, --skeleton, ...) takes longer than this (e.g. 10s, 500ms)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub file_timeout: Option<Duration>,
+
+    /// Truncation strategy when a token/size limit is hit
+    #[arg(long, value_enum, default_value = "end")]
+    pub truncate: TruncateStrategy,
+
+    /// Include only files whose contents match this regex (e.g. "TODO|unsafe"); blocks note the match count
+    #[arg(long, value_name = "REGEX", value_parser = regex::Regex::new)]
+    pub grep: Option<regex::Regex>,
+
+    /// Jupyter notebooks: keep code cells, code and markdown cells, or skip notebooks
+    #[arg(long, value_enum, default_value = "code")]
+    pub notebook: NotebookMode,
+
+    /// Shorten CSV and TSV files to their header and first N rows, plus a row and column count
+    #[arg(long, value_name = "N")]
+    pub csv_rows: Option<usize>,
+
+    /// Convert CRLF to LF, strip trailing whitespace and BOMs, and collapse runs of blank lines
+    #[arg(long)]
+    pub normalize: bool,
+
+    /// Replace files whose (normalized) content repeats an earlier file with an "identical to" stub
+    #[arg(long)]
+    pub dedup: bool,
+
+    /// Keep every path to the same file (hard links, overlapping bind mounts) instead of only the first
+    #[arg(long)]
+    pub no_inode_dedup: bool,
+
+    /// Prefix each line with its 1-based line number (per file)
+    #[arg(long)]
+    pub line_numbers: bool,
+
+    /// Keep generated and minified files (`@generated`/`DO NOT EDIT` headers, *.min.js, source maps, protobuf stubs)
+    #[arg(long)]
+    pub include_generated: bool,
+
+    /// Keep package-manager lock files (Cargo.lock, package-lock.json, yarn.lock, poetry.lock, ...)
+    #[arg(long)]
+    pub include_locks: bool,
+
+    /// Keep files under vendored directories (vendor/, third_party/, node_modules/)
+    #[arg(long)]
+    pub include_vendor: bool,
+
+    /// Strip line and block comments before counting and aggregation
+    #[arg(long)]
+    pub strip_comments: bool,
+
+    /// Reduce Rust, Python, JS/TS, and Go files to imports, types, and signatures (bodies become `...`)
+    #[arg(long)]
+    pub skeleton: bool,
+
+    /// Keep only module docs, doc comments, and docstrings, for a documentation digest
+    #[arg(long, conflicts_with_all = ["strip_comments", "skeleton"])]
+    pub docs_only: bool,
+
+    /// Reduce Rust files to their public API: `pub` items with docs and signatures
+    #[arg(long, conflicts_with_all = ["skeleton", "docs_only"])]
+    pub api_only: bool,
+
+    /// Pipe matching files through a shell command before other transforms: `[GLOB=]COMMAND`, e.g. '*.sql=sqlformat --minify' (repeatable, applied in order)
+    #[arg(long = "filter-cmd", value_name = "[GLOB=]COMMAND", value_parser = FilterCmd::parse)]
+    pub filter_cmds: Vec<FilterCmd>,
+
+    /// Apply the WebAssembly transform plugins (`*.wasm`, `*.wat`) in DIR, after any --filter-cmd
+    #[arg(long, value_name = "DIR")]
+    pub plugins: Option<PathBuf>,
+
+    /// Replace files of more than N tokens with a summary from --summarize-cmd
+    #[arg(long, value_name = "TOKENS", requires = "summarize_cmd")]
+    pub summarize_over: Option<usize>,
+
+    /// Shell command that reads a file on stdin and prints its summary, e.g. 'llm -m gpt-4o-mini summarize' (cached by content hash)
+    #[arg(long, value_name = "COMMAND", requires = "summarize_over")]
+    pub summarize_cmd: Option<String>,
+
+    /// Skip image files instead of listing each as an `[image: path, size, WxH]` placeholder (SVGs are then inlined as text)
+    #[arg(long)]
+    pub no_image_placeholders: bool,
+
+    /// Replace file paths with stable pseudonyms (dir1/file3.rs), in the tree, headers, and mentions in contents
+    #[arg(long, conflicts_with_all = ["meta", "git_meta", "incremental"])]
+    pub anonymize: bool,
+
+    /// With --anonymize, also mask each term listed in FILE (one per line, case-insensitive) as term1, term2, ...
+    #[arg(long, value_name = "FILE", requires = "anonymize")]
+    pub anonymize_terms: Option<PathBuf>,
+
+    /// Replace each term in FILE with [SCRUBBED] throughout the bundle, paths included: one literal per line, or /REGEX/
+    #[arg(long, value_name = "FILE")]
+    pub scrub_file: Option<PathBuf>,
+
+    /// Disable redaction of API keys, credentials, and private keys
+    #[arg(long)]
+    pub no_redact: bool,
+
+    /// Only include files tracked by git
+    #[arg(long)]
+    pub git_tracked: bool,
+
+    /// Only include files last changed at or after WHEN: a time ago (30m, 12h, 2d, 1w) or a UTC date (2024-06-01)
+    #[arg(long, value_name = "WHEN", value_parser = parse_when)]
+    pub since: Option<i64>,
+
+    /// Only include files last changed before WHEN, in the same forms as --since
+    #[arg(long, value_name = "WHEN", value_parser = parse_when)]
+    pub until: Option<i64>,
+
+    /// Only include files written by AUTHOR per git blame (case-insensitive match on name or email)
+    #[arg(long, value_name = "AUTHOR")]
+    pub author: Option<String>,
+
+    /// How much of a file --author must have written
+    #[arg(long, value_enum, default_value = "majority", requires = "author")]
+    pub author_share: AuthorShare,
+
+    /// In a Cargo or JS workspace, pack only package NAME and the members it depends on (repeatable)
+    #[arg(long = "package", value_name = "NAME")]
+    pub packages: Vec<String>,
+
+    /// Aggregate exactly the paths listed in FILE (or stdin with `-`), in order
+    #[arg(long, value_name = "FILE")]
+    pub files_from: Option<PathBuf>,
+
+    /// Always include FILE, ahead of the other files, whatever the filter, budget, or order (repeatable)
+    #[arg(long = "always", value_name = "FILE")]
+    pub always: Vec<PathBuf>,
+
+    /// Depth limit
+    #[arg(short = 'd', long)]
+    pub depth_limit: Option<usize>,
+
+    /// Per-file wrapper replacing the expandable block, e.g. "<<< {path} ({tokens} tok) >>>\n{content}\n".
+    /// Variables: path, ext, size, tokens, hash, truncated, content
+    #[arg(long, value_parser = Template::parse, conflicts_with_all = ["json", "format"])]
+    pub template: Option<Template>,
+
+    /// Prompt text placed before the aggregation
+    #[arg(long, value_name = "TEXT", conflicts_with = "prefix_file")]
+    pub prefix: Option<String>,
+
+    /// Read the prompt prefix from a file
+    #[arg(long, value_name = "FILE")]
+    pub prefix_file: Option<PathBuf>,
+
+    /// Prompt text placed after the aggregation (e.g. "Answer only with a diff.")
+    #[arg(long, value_name = "TEXT", conflicts_with = "suffix_file")]
+    pub suffix: Option<String>,
+
+    /// Read the prompt suffix from a file
+    #[arg(long, value_name = "FILE")]
+    pub suffix_file: Option<PathBuf>,
+
+    /// Output file (if not given, clipboard); `gist` uploads a secret GitHub gist and prints its URL
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+
+    /// How clipboard output is kept available after bound exits on X11/Wayland
+    #[arg(long, value_enum, default_value_t = ClipboardMode::Auto)]
+    pub clipboard_mode: ClipboardMode,
+
+    /// Also set the X11/Wayland primary selection (middle-click paste) when copying to the clipboard
+    #[arg(long)]
+    pub primary: bool,
+
+    /// Copy output larger than SIZE to the clipboard in parts, waiting for Enter between them (e.g. 100K)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, conflicts_with_all = ["out", "out_auto", "post"])]
+    pub clipboard_chunks: Option<u64>,
+
+    /// POST the bundle to this URL instead of writing it (the response body is printed)
+    #[arg(long, value_name = "URL", conflicts_with_all = ["out", "out_auto"])]
+    pub post: Option<String>,
+
+    /// Extra header for --post, as "Name: value" (repeatable)
+    #[arg(long = "post-header", value_name = "HEADER", requires = "post")]
+    pub post_headers: Vec<String>,
+
+    /// Write to a new bound-<repo>-<yyyymmdd-HHMM>.<ext> file in $BOUND_OUT_DIR (default: the current directory)
+    #[arg(long, conflicts_with = "out")]
+    pub out_auto: bool,
+
+    /// Compress the output file; --out-auto names get a .gz or .zst suffix
+    #[arg(long, value_enum, value_name = "CODEC", conflicts_with = "incremental")]
+    pub compress: Option<Compression>,
+
+    /// Replace an existing --out file
+    #[arg(long, requires = "out", conflicts_with = "append")]
+    pub force: bool,
+
+    /// Add to the end of an existing --out file instead of replacing it (text and jsonl output)
+    #[arg(long, requires = "out", conflicts_with_all = ["manifest", "incremental"])]
+    pub append: bool,
+
+    /// Write a JSON manifest of the bundle's files next to --out (<out>.manifest.json)
+    #[arg(long, requires = "out", conflicts_with_all = ["json", "format"])]
+    pub manifest: bool,
+
+    /// Reuse sections of unchanged files from the previous --out bundle
+    #[arg(long, requires = "out", conflicts_with_all = ["json", "format"])]
+    pub incremental: bool,
+
+    /// Include metadata headers
+    #[arg(long)]
+    pub meta: bool,
+
+    /// Include SHA-256 hash in metadata
+    #[arg(long)]
+    pub meta_hash: bool,
+
+    /// Add each file's SHA-256 to its block and a whole-bundle checksum trailer
+    #[arg(long)]
+    pub hash: bool,
+
+    /// Annotate metadata headers with each file's last commit SHA, author, and date
+    #[arg(long)]
+    pub git_meta: bool,
+
+    /// Include file tree
+    #[arg(long)]
+    pub tree: bool,
+
+    /// Enable Furnace analysis
+    #[arg(long)]
+    pub furnace: bool,
+
+    /// Progress reporting style on stderr
+    #[arg(long, value_enum, default_value = "bar")]
+    pub progress: ProgressMode,
+
+    /// List the selected files with their token counts instead of aggregating
+    #[arg(long)]
+    pub list: bool,
+
+    /// Don't read or write the token-count cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Abort on the first unreadable file instead of skipping it, and fail when the budget is exceeded
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Suppress all output except errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Log skipped files with reasons (-v), plus per-file detail (-vv)
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Append a debug trace of the run to FILE: spans for traversal, reference resolution, each file, and output,
+    /// with every inclusion and skip decision (trace detail with -vv)
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// Output JSON format (same as --format json)
+    #[arg(long)]
+    pub json: bool,
+
+    /// Output format: the default text bundle, one JSON document, one JSON object per file (jsonl), or an HTML viewer
+    #[arg(long, value_enum, default_value = "text", conflicts_with = "json")]
+    pub format: OutputFormat,
+
+    /// With `--format jsonl`, split files into line-aligned chunks of at most N tokens (one object per chunk)
+    #[arg(long, value_name = "N")]
+    pub chunk_tokens: Option<usize>,
+
+    /// Tokens of trailing lines repeated at the start of the next chunk
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "chunk_tokens")]
+    pub chunk_overlap: usize,
+}
+
+/// Shape of the aggregated output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// `expandable{}` blocks
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON object per file, for RAG and indexing pipelines
+    Jsonl,
+    /// A self-contained HTML page with a file tree and highlighted sources
+    Html,
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Html => "html",
+        }
+    }
+}
+
+/// Share of a file's lines `--author` must have written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AuthorShare {
+    /// More lines than any other author
+    Majority,
+    /// At least one line
+    Any,
+}
+
+impl AuthorShare {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthorShare::Majority => "majority",
+            AuthorShare::Any => "any",
+        }
+    }
+}
+
+impl PackArgs {
+    /// The output format, with `--json` as shorthand for `--format json`
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format
+        }
+    }
+
+    /// Split the optional filter from the targets (defaulting to `.`), and any `:START-END` line range from each target
+    pub fn filter_and_targets(&self) -> Result<(Option<&str>, Vec<Target>), String> {
+        let (filter, rest) = match self.targets.split_first() {
+            Some((first, rest)) if first.starts_with('[') || first.starts_with('{') => {
+                (Some(first.as_str()), rest)
+            }
+            _ => (None, self.targets.as_slice()),
+        };
+        let mut targets = rest.iter().map(|t| split_target(t)).collect::<Result<Vec<_>, _>>()?;
+        if targets.is_empty() {
+            targets.push((PathBuf::from("."), None));
+        }
+        Ok((filter, targets))
+    }
+}
+
+/// `command` with `names` offered as the values of `--profile`, in every subcommand that takes it
+pub fn complete_profiles(command: clap::Command, names: &[String]) -> clap::Command {
+    if names.is_empty() {
+        return command;
+    }
+    let takes_profile = command.get_arguments().any(|arg| arg.get_id() == "profile");
+    let command = match takes_profile {
+        true => command.mut_arg("profile", |arg| arg.value_parser(clap::builder::PossibleValuesParser::new(names.iter().cloned()))),
+        false => command,
+    };
+    let subcommands: Vec<String> = command.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    subcommands.iter().fold(command, |command, name| command.mut_subcommand(name, |sub| complete_profiles(sub, names)))
+}
+
+/// Parse `--since`/`--until` into Unix seconds: a count of m/h/d/w before now, or a YYYY-MM-DD date at UTC midnight
+fn parse_when(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let invalid = || format!("invalid time '{}' (expected e.g. 2d, 12h, 1w, or 2024-06-01)", s);
+    if let Some((y, rest)) = s.split_once('-') {
+        let (m, d) = rest.split_once('-').ok_or_else(invalid)?;
+        let (y, m, d): (i64, i64, i64) = (
+            y.parse().map_err(|_| invalid())?,
+            m.parse().map_err(|_| invalid())?,
+            d.parse().map_err(|_| invalid())?,
+        );
+        if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+            return Err(invalid());
+        }
+        // Days since 1970-01-01 in the proleptic Gregorian calendar, with March as the first month of the year
+        let y = if m <= 2 { y - 1 } else { y };
+        let era = y.div_euclid(400);
+        let year_of_era = y - era * 400;
+        let day_of_year = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        return Ok((era * 146_097 + day_of_era - 719_468) * 86_400);
+    }
+    let (digits, unit) = match s.char_indices().last() {
+        Some((i, 'm')) => (&s[..i], 60),
+        Some((i, 'h')) => (&s[..i], 3_600),
+        Some((i, 'd')) => (&s[..i], 86_400),
+        Some((i, 'w')) => (&s[..i], 604_800),
+        _ => return Err(invalid()),
+    };
+    let ago = digits.parse::<i64>().ok().and_then(|n| n.checked_mul(unit)).ok_or_else(invalid)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    Ok(now - ago)
+}
+
+/// Parse a duration: a number of seconds with an optional ms/s/m suffix (e.g. 500ms, 10s, 1.5m)
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let invalid = || format!("invalid duration '{}' (expected e.g. 500ms, 10s, or 2m)", s);
+    let (number, scale) = if let Some(n) = s.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 60.0)
+    } else {
+        (s, 1.0)
+    };
+    let secs = number.trim().parse::<f64>().ok().filter(|n| n.is_finite() && *n > 0.0).ok_or_else(invalid)?;
+    Duration::try_from_secs_f64(secs * scale).map_err(|_| invalid())
+}
+
+/// Parse a byte count with an optional K/M/G suffix (binary units)
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (digits, multiplier) = match s.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&s[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&s[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size '{}'", s))
+}
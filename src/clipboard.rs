@@ -0,0 +1,198 @@
+//! clipboard.rs
+//! Copies the bundle to the system clipboard. X11 and Wayland selections are served by the process that set them,
+//! so there the text is handed to wl-copy, xclip, or xsel, or to a background `bound clipboard-serve` that outlives the run.
+
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use arboard::Clipboard;
+use clap::ValueEnum;
+
+/// How the clipboard is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ClipboardMode {
+    /// On Linux and BSD, a clipboard tool if one is installed, else a background process; elsewhere `direct`
+    Auto,
+    /// Set it from this process (on X11/Wayland the contents vanish when bound exits unless a clipboard manager copies them)
+    Direct,
+    /// Hand it to wl-copy, xclip, or xsel
+    Tool,
+    /// Keep serving it from a background bound process until something else is copied
+    Daemon,
+}
+
+/// How long to wait for a clipboard tool or background process to take over before reading the clipboard back
+const VERIFY_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Whether selections belong to the process that set them (X11 and Wayland)
+const OWNED_SELECTIONS: bool = cfg!(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))));
+
+/// Clipboard tools for this session: program, then its arguments for the clipboard and for the primary selection
+type Tool = (&'static str, &'static [&'static str], &'static [&'static str]);
+
+fn tools() -> Vec<Tool> {
+    let mut tools: Vec<Tool> = Vec::new();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        tools.push(("wl-copy", &[], &["--primary"]));
+    }
+    if std::env::var_os("DISPLAY").is_some() {
+        tools.push(("xclip", &["-selection", "clipboard"], &["-selection", "primary"]));
+        tools.push(("xsel", &["--clipboard", "--input"], &["--primary", "--input"]));
+    }
+    tools
+}
+
+/// Set the clipboard (and with `primary`, the X11 primary selection), returning how it was done
+pub fn copy(text: &str, mode: ClipboardMode, primary: bool) -> Result<String, Box<dyn Error>> {
+    if !OWNED_SELECTIONS || mode == ClipboardMode::Direct {
+        set_direct(text, primary)?;
+        return Ok("directly".to_string());
+    }
+    if mode != ClipboardMode::Daemon {
+        match (tools().into_iter().find(|(program, _, _)| on_path(program)), mode) {
+            (Some((program, clipboard_args, primary_args)), _) => {
+                pipe_to(program, clipboard_args, text)?;
+                if primary {
+                    pipe_to(program, primary_args, text)?;
+                }
+                return Ok(format!("via {}", program));
+            }
+            (None, ClipboardMode::Tool) => return Err("--clipboard-mode tool needs wl-copy, xclip, or xsel on PATH".into()),
+            (None, _) => {}
+        }
+    }
+    // The background process can't report errors, so make sure there is a clipboard to serve first
+    drop(Clipboard::new()?);
+    spawn_server(text, false)?;
+    if primary {
+        spawn_server(text, true)?;
+    }
+    Ok("via a background process".to_string())
+}
+
+/// Read the clipboard back and compare it with `text`, returning a description of any difference.
+/// Unreadable clipboards (some platforms refuse reads) pass; carriage returns are ignored.
+pub fn verify(text: &str) -> Result<(), String> {
+    let expected = text.bytes().filter(|&b| b != b'\r').count();
+    let Ok(mut clipboard) = Clipboard::new() else {
+        return Ok(());
+    };
+    let started = Instant::now();
+    loop {
+        let actual = clipboard.get_text().map(|got| got.bytes().filter(|&b| b != b'\r').count());
+        match actual {
+            Ok(n) if n == expected => return Ok(()),
+            _ if started.elapsed() < VERIFY_TIMEOUT => thread::sleep(Duration::from_millis(100)),
+            Ok(n) => return Err(format!("the clipboard holds {} of {} bytes", n, expected)),
+            Err(arboard::Error::ContentNotAvailable) => return Err("the clipboard is empty".to_string()),
+            Err(_) => return Ok(()),
+        }
+    }
+}
+
+/// Split `text` into parts of at most `max_bytes`, breaking after a newline where there is one
+pub fn split(text: &str, max_bytes: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_bytes {
+        let mut end = max_bytes;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let cut = match rest[..end].rfind('\n') {
+            Some(i) => i + 1,
+            None if end > 0 => end,
+            // A single character wider than the limit
+            None => rest.chars().next().map_or(rest.len(), char::len_utf8),
+        };
+        parts.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() {
+        parts.push(rest);
+    }
+    parts
+}
+
+/// Set the selection from this process
+fn set_direct(text: &str, primary: bool) -> Result<(), arboard::Error> {
+    let mut clipboard = Clipboard::new()?;
+    set_selection(&mut clipboard, text, false, false)?;
+    if primary {
+        set_selection(&mut clipboard, text, true, false)?;
+    }
+    Ok(())
+}
+
+/// Set the clipboard or primary selection, with `wait` blocking until another program takes it over
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
+fn set_selection(clipboard: &mut Clipboard, text: &str, primary: bool, wait: bool) -> Result<(), arboard::Error> {
+    use arboard::{LinuxClipboardKind, SetExtLinux};
+    let kind = if primary { LinuxClipboardKind::Primary } else { LinuxClipboardKind::Clipboard };
+    let set = clipboard.set().clipboard(kind);
+    if wait { set.wait() } else { set }.text(text)
+}
+
+// Other platforms keep the clipboard after exit and have no primary selection
+#[cfg(not(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten")))))]
+fn set_selection(clipboard: &mut Clipboard, text: &str, primary: bool, _wait: bool) -> Result<(), arboard::Error> {
+    match primary {
+        true => Ok(()),
+        false => clipboard.set_text(text),
+    }
+}
+
+/// Pass `text` to a clipboard tool on stdin; the tool forks to keep serving it
+fn pipe_to(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child.stdin.take().expect("stdin is piped").write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {}", program, status)));
+    }
+    Ok(())
+}
+
+/// Start `bound clipboard-serve` in the background and hand it `text`
+fn spawn_server(text: &str, primary: bool) -> io::Result<()> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command.arg("clipboard-serve");
+    if primary {
+        command.arg("--primary");
+    }
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+    child.stdin.take().expect("stdin is piped").write_all(text.as_bytes())
+}
+
+/// Body of `bound clipboard-serve`: own the selection read from stdin until another program takes it
+pub fn serve(primary: bool) -> Result<(), Box<dyn Error>> {
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text)?;
+    set_selection(&mut Clipboard::new()?, &text, primary, true)?;
+    Ok(())
+}
+
+/// Whether `program` is an executable file in a `$PATH` directory
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(program))))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,423 @@
+//! comments.rs
+//! Language-aware removal of comments from source files, or extraction of just their documentation.
+
+/// Comment and string syntax for a family of languages
+struct Syntax {
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+    /// Line comment prefixes that mark documentation
+    doc_line: &'static [&'static str],
+    /// Block comment openers that mark documentation
+    doc_block: &'static [&'static str],
+    /// Doc line comments must start at column 0 (they are plain comments above declarations)
+    top_level_docs: bool,
+    nested_blocks: bool,
+    quotes: &'static [u8],
+    triple_quotes: bool,
+    /// `'` between digits separates them (C++14 `1'000'000`) rather than opening a char literal
+    digit_separators: bool,
+    rust_literals: bool,
+    hash_needs_space: bool,
+}
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    doc_line: &["///"],
+    doc_block: &["/**"],
+    top_level_docs: false,
+    nested_blocks: false,
+    quotes: b"\"'`",
+    triple_quotes: false,
+    digit_separators: true,
+    rust_literals: false,
+    hash_needs_space: false,
+};
+
+const RUST: Syntax = Syntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    doc_line: &["///", "//!"],
+    doc_block: &["/**", "/*!"],
+    top_level_docs: false,
+    nested_blocks: true,
+    quotes: b"\"",
+    triple_quotes: false,
+    digit_separators: false,
+    rust_literals: true,
+    hash_needs_space: false,
+};
+
+const GO: Syntax = Syntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    doc_line: &["//"],
+    doc_block: &[],
+    top_level_docs: true,
+    nested_blocks: false,
+    quotes: b"\"'`",
+    triple_quotes: false,
+    digit_separators: false,
+    rust_literals: false,
+    hash_needs_space: false,
+};
+
+const PYTHON: Syntax = Syntax {
+    line: &["#"],
+    block: None,
+    doc_line: &[],
+    doc_block: &[],
+    top_level_docs: false,
+    nested_blocks: false,
+    quotes: b"\"'",
+    triple_quotes: true,
+    digit_separators: false,
+    rust_literals: false,
+    hash_needs_space: false,
+};
+
+const HASH: Syntax = Syntax {
+    line: &["#"],
+    block: None,
+    doc_line: &[],
+    doc_block: &[],
+    top_level_docs: false,
+    nested_blocks: false,
+    quotes: b"\"'",
+    triple_quotes: false,
+    digit_separators: false,
+    rust_literals: false,
+    hash_needs_space: true,
+};
+
+const SQL: Syntax = Syntax {
+    line: &["--"],
+    block: Some(("/*", "*/")),
+    doc_line: &[],
+    doc_block: &[],
+    top_level_docs: false,
+    nested_blocks: false,
+    quotes: b"\"'",
+    triple_quotes: false,
+    digit_separators: false,
+    rust_literals: false,
+    hash_needs_space: false,
+};
+
+/// Look up the comment syntax for a file extension
+fn syntax_for(ext: &str) -> Option<&'static Syntax> {
+    match ext {
+        "rs" => Some(&RUST),
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "java" | "js" | "jsx"
+        | "mjs" | "cjs" | "ts" | "tsx" | "cs" | "kt" | "kts" | "swift" | "scala" => Some(&C_LIKE),
+        "go" => Some(&GO),
+        "py" | "pyi" => Some(&PYTHON),
+        "sh" | "bash" | "zsh" | "rb" | "toml" | "yaml" | "yml" => Some(&HASH),
+        "sql" => Some(&SQL),
+        _ => None,
+    }
+}
+
+/// Remove comments from `content`, leaving unsupported languages untouched.
+/// Lines that held nothing but a comment are dropped entirely.
+pub fn strip_comments(content: &str, ext: &str) -> String {
+    match syntax_for(ext) {
+        Some(syntax) => drop_emptied_lines(content, &strip_with(content, syntax)),
+        None => content.to_string(),
+    }
+}
+
+/// Remove comment text while preserving every newline, so lines stay aligned with the input
+fn strip_with(src: &str, syn: &Syntax) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut code_start = 0;
+    for span in comment_spans(src, syn).into_iter().filter(|s| s.kind != SpanKind::Docstring) {
+        out.push_str(&src[code_start..span.start]);
+        out.extend(src[span.start..span.end].chars().filter(|&c| c == '\n'));
+        code_start = span.end;
+    }
+    out.push_str(&src[code_start..]);
+    out
+}
+
+/// Keep only module docs, doc comments, and docstrings, leaving unsupported languages untouched.
+/// Separate doc groups are divided by a blank line.
+pub fn extract_docs(content: &str, ext: &str) -> String {
+    let Some(syntax) = syntax_for(ext) else {
+        return content.to_string();
+    };
+    let b = content.as_bytes();
+    let mut out = String::new();
+    let mut prev_end = None;
+    for span in comment_spans(content, syntax).into_iter().filter(|s| is_doc(content, s, syntax)) {
+        // Start at the line's indentation when the doc is the first thing on its line
+        let line_start = b[..span.start].iter().rposition(|&c| c == b'\n').map_or(0, |p| p + 1);
+        let start = if b[line_start..span.start].iter().all(u8::is_ascii_whitespace) { line_start } else { span.start };
+        if let Some(prev) = prev_end {
+            let gap_lines = b[prev..start].iter().filter(|&&c| c == b'\n').count();
+            let contiguous = gap_lines <= 1 && b[prev..start].iter().all(u8::is_ascii_whitespace);
+            out.push_str(if contiguous { "\n" } else { "\n\n" });
+        }
+        out.push_str(content[start..span.end].trim_end());
+        prev_end = Some(span.end);
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SpanKind {
+    Line,
+    Block,
+    Docstring,
+}
+
+/// A comment (or docstring) occupying `start..end` in the source
+struct Span {
+    start: usize,
+    end: usize,
+    kind: SpanKind,
+}
+
+/// Whether a span is documentation rather than an ordinary comment
+fn is_doc(src: &str, span: &Span, syn: &Syntax) -> bool {
+    let text = &src[span.start..span.end];
+    match span.kind {
+        SpanKind::Docstring => true,
+        SpanKind::Line => {
+            let line_start = src[..span.start].rfind('\n').map_or(0, |p| p + 1);
+            let indent = &src[line_start..span.start];
+            indent.trim().is_empty()
+                && (!syn.top_level_docs || indent.is_empty())
+                && syn.doc_line.iter().any(|p| text.starts_with(p))
+                && !text.starts_with("////")
+        }
+        SpanKind::Block => syn
+            .doc_block
+            .iter()
+            .any(|p| text.starts_with(p) && !text.starts_with("/***") && text != "/**/"),
+    }
+}
+
+/// Locate every comment in `src`, plus docstrings for languages that have them
+fn comment_spans(src: &str, syn: &Syntax) -> Vec<Span> {
+    let b = src.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+
+    while i < b.len() {
+        // Shebang lines are directives, not comments
+        if i == 0 && b.starts_with(b"#!") {
+            i = line_end(b, 0);
+            continue;
+        }
+
+        if let Some(pat) = syn.line.iter().find(|p| b[i..].starts_with(p.as_bytes())) {
+            let at_word_start = i == 0 || b[i - 1].is_ascii_whitespace();
+            if *pat != "#" || !syn.hash_needs_space || at_word_start {
+                let end = line_end(b, i);
+                spans.push(Span { start: i, end, kind: SpanKind::Line });
+                i = end;
+                continue;
+            }
+        }
+
+        if let Some((open, close)) = syn.block {
+            if b[i..].starts_with(open.as_bytes()) {
+                let end = block_end(b, i, open, close, syn.nested_blocks);
+                spans.push(Span { start: i, end, kind: SpanKind::Block });
+                i = end;
+                continue;
+            }
+        }
+
+        if syn.rust_literals {
+            if let Some(end) = rust_literal_end(src, i) {
+                i = end;
+                continue;
+            }
+        }
+
+        if syn.quotes.contains(&b[i]) && !(syn.digit_separators && b[i] == b'\'' && is_digit_separator(b, i)) {
+            let end = string_end(b, i, syn.triple_quotes);
+            if syn.triple_quotes && end - i >= 6 && b[i..].starts_with(&[b[i]; 3]) && docstring_position(src, i) {
+                spans.push(Span { start: i, end, kind: SpanKind::Docstring });
+            }
+            i = end;
+            continue;
+        }
+
+        i += 1;
+    }
+
+    spans
+}
+
+/// A Python string is a docstring when it starts its line and follows the
+/// start of the file or a line ending in `:` (skipping blank and comment lines)
+fn docstring_position(src: &str, i: usize) -> bool {
+    let line_start = src[..i].rfind('\n').map_or(0, |p| p + 1);
+    if !src[line_start..i].trim().is_empty() {
+        return false;
+    }
+    src[..line_start]
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_none_or(|line| line.ends_with(':'))
+}
+
+/// Whether the `'` at `i` sits inside a number such as `1'000'000` or `0xFF'FF`,
+/// as opposed to closing a prefix like `u8'a'`
+fn is_digit_separator(b: &[u8], i: usize) -> bool {
+    let token = b[..i]
+        .iter()
+        .rposition(|&c| !(c.is_ascii_alphanumeric() || c == b'_' || c == b'\''))
+        .map_or(0, |p| p + 1);
+    token < i && b[token].is_ascii_digit()
+}
+
+/// Index of the newline ending the line that contains `i` (or end of input)
+fn line_end(b: &[u8], i: usize) -> usize {
+    b[i..].iter().position(|&c| c == b'\n').map_or(b.len(), |p| i + p)
+}
+
+/// Index just past the block comment starting at `i`
+fn block_end(b: &[u8], i: usize, open: &str, close: &str, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut j = i;
+    while j < b.len() {
+        if b[j..].starts_with(open.as_bytes()) && (nested || depth == 0) {
+            depth += 1;
+            j += open.len();
+        } else if b[j..].starts_with(close.as_bytes()) {
+            depth -= 1;
+            j += close.len();
+            if depth == 0 {
+                return j;
+            }
+        } else {
+            j += 1;
+        }
+    }
+    b.len()
+}
+
+/// Index just past the quoted string starting at `i`
+fn string_end(b: &[u8], i: usize, triple_quotes: bool) -> usize {
+    let q = b[i];
+    if triple_quotes && b[i..].starts_with(&[q, q, q]) {
+        let mut j = i + 3;
+        while j < b.len() {
+            if b[j] == b'\\' {
+                j += 2;
+            } else if b[j..].starts_with(&[q, q, q]) {
+                return j + 3;
+            } else {
+                j += 1;
+            }
+        }
+        return b.len();
+    }
+
+    let mut j = i + 1;
+    while j < b.len() {
+        match b[j] {
+            b'\\' => j += 2,
+            c if c == q => return j + 1,
+            b'\n' if q != b'`' => return j,
+            _ => j += 1,
+        }
+    }
+    b.len()
+}
+
+/// Skip Rust raw strings and char literals, which don't follow the plain quote rules.
+/// Returns `None` for anything else (including lifetimes).
+fn rust_literal_end(src: &str, i: usize) -> Option<usize> {
+    let b = src.as_bytes();
+    let prev_is_ident = i > 0 && (b[i - 1].is_ascii_alphanumeric() || b[i - 1] == b'_');
+
+    if b[i] == b'r' && !prev_is_ident {
+        let hashes = b[i + 1..].iter().take_while(|&&c| c == b'#').count();
+        if b.get(i + 1 + hashes) == Some(&b'"') {
+            let mut close = vec![b'"'];
+            close.extend(std::iter::repeat_n(b'#', hashes));
+            let body = i + 2 + hashes;
+            return Some(
+                b[body..]
+                    .windows(close.len())
+                    .position(|w| w == close.as_slice())
+                    .map_or(b.len(), |p| body + p + close.len()),
+            );
+        }
+    }
+
+    if b[i] == b'\'' {
+        if b.get(i + 1) == Some(&b'\\') {
+            // The escaped character may itself be a quote, as in '\''
+            let body = (i + 3).min(b.len());
+            return Some(b[body..].iter().position(|&c| c == b'\'').map_or(b.len(), |p| body + p + 1));
+        }
+        let c = src[i + 1..].chars().next()?;
+        let after = i + 1 + c.len_utf8();
+        if b.get(after) == Some(&b'\'') {
+            return Some(after + 1);
+        }
+    }
+    None
+}
+
+/// Drop lines that only became blank because a comment was removed, and trim
+/// trailing whitespace left behind by removed trailing comments
+fn drop_emptied_lines(original: &str, stripped: &str) -> String {
+    original
+        .split('\n')
+        .zip(stripped.split('\n'))
+        .filter(|(orig, new)| !new.trim().is_empty() || orig.trim().is_empty())
+        .map(|(orig, new)| if orig.len() != new.len() { new.trim_end() } else { new })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_comments_but_not_strings() {
+        let src = "int a = 1; // one\n/* gone */\nchar *s = \"// kept\"; /* two */\nchar c = '\"'; // three\n";
+        assert_eq!(strip_comments(src, "c"), "int a = 1;\nchar *s = \"// kept\";\nchar c = '\"';\n");
+        let src = "#!/bin/sh\necho \"# kept\" a#b # gone\n# gone\n";
+        assert_eq!(strip_comments(src, "sh"), "#!/bin/sh\necho \"# kept\" a#b\n");
+        assert_eq!(strip_comments("a = 1 // kept\n", "txt"), "a = 1 // kept\n");
+    }
+
+    #[test]
+    fn digit_separators_do_not_open_char_literals() {
+        let src = "int n = 1'000'000; // note\nint m = 0xFF'FF; // note\n";
+        assert_eq!(strip_comments(src, "cpp"), "int n = 1'000'000;\nint m = 0xFF'FF;\n");
+        // A prefixed literal still quotes what it holds
+        assert_eq!(strip_comments("auto c = u8'\"'; // note\n", "cpp"), "auto c = u8'\"';\n");
+    }
+
+    #[test]
+    fn rust_literals_are_sized_exactly() {
+        assert_eq!(rust_literal_end("'\\'' x", 0), Some(4));
+        assert_eq!(rust_literal_end("'\\\\' x", 0), Some(4));
+        assert_eq!(rust_literal_end("'\\u{1F600}' x", 0), Some(11));
+        assert_eq!(rust_literal_end("'a: loop", 0), None);
+        let src = "let q = '\\''; let s = r#\"/* kept */\"#; // gone\nfn f<'a>(x: &'a str) {} /* gone /* nested */ */\n";
+        assert_eq!(strip_comments(src, "rs"), "let q = '\\''; let s = r#\"/* kept */\"#;\nfn f<'a>(x: &'a str) {}\n");
+    }
+
+    #[test]
+    fn extracts_only_documentation() {
+        let src = "//! Module\n\n/// Item\n/// more\n// plain\nfn f() {} // trailing\n";
+        assert_eq!(extract_docs(src, "rs"), "//! Module\n\n/// Item\n/// more\n");
+        let src = "\"\"\"Module.\"\"\"\n\ndef f():\n    \"\"\"Function.\"\"\"\n    x = \"\"\"not a docstring\"\"\"\n";
+        assert_eq!(extract_docs(src, "py"), "\"\"\"Module.\"\"\"\n\n    \"\"\"Function.\"\"\"\n");
+    }
+}
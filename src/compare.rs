@@ -0,0 +1,218 @@
+//! compare.rs
+//! `bound compare`: pairs the files selected under two snapshots and renders unified diffs between them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::telemetry::SkipReason;
+
+/// Unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Edit distance past which a file is shown as wholly replaced rather than diffed line by line
+const MAX_EDITS: usize = 2000;
+
+/// The old and new snapshot directories
+pub struct Comparison {
+    pub old_root: PathBuf,
+    pub new_root: PathBuf,
+}
+
+impl Comparison {
+    pub fn new(old_root: PathBuf, new_root: PathBuf) -> Result<Self, String> {
+        if !old_root.is_dir() || !new_root.is_dir() {
+            return Err("bound compare needs two directories or archives".to_string());
+        }
+        if old_root.starts_with(&new_root) || new_root.starts_with(&old_root) {
+            return Err("bound compare needs two separate snapshots, neither inside the other".to_string());
+        }
+        Ok(Comparison { old_root, new_root })
+    }
+
+    /// Keep files added, deleted, or changed between the snapshots, in path order.
+    /// Files on both sides are represented by their new copy; identical ones are skipped.
+    pub fn changed_files(&self, files: Vec<PathBuf>, skipped: &mut Vec<(PathBuf, SkipReason)>) -> Vec<PathBuf> {
+        let (old, new): (Vec<PathBuf>, Vec<PathBuf>) = files.into_iter().partition(|p| self.is_old(p));
+        let mut changed = Vec::new();
+        for path in new {
+            match self.old_copy(&path).filter(|old| old.is_file()) {
+                Some(old) if same_contents(&old, &path) => skipped.push((path, SkipReason::Unchanged)),
+                _ => changed.push(path),
+            }
+        }
+        for path in old {
+            let new = self.new_root.join(self.relative(&path));
+            // Files on both sides were handled above, even if the new copy was filtered out
+            if !new.is_file() {
+                changed.push(path);
+            }
+        }
+        changed.sort_by(|a, b| self.relative(a).cmp(self.relative(b)));
+        changed
+    }
+
+    /// Path of a file relative to its snapshot
+    pub fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.new_root)
+            .or_else(|_| path.strip_prefix(&self.old_root))
+            .unwrap_or(path)
+    }
+
+    /// Whether `path` belongs to the old snapshot
+    pub fn is_old(&self, path: &Path) -> bool {
+        path.starts_with(&self.old_root)
+    }
+
+    /// The old snapshot's copy of a file from the new snapshot, if it exists
+    pub fn old_copy(&self, path: &Path) -> Option<PathBuf> {
+        let old = self.old_root.join(path.strip_prefix(&self.new_root).ok()?);
+        old.is_file().then_some(old)
+    }
+}
+
+fn same_contents(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(ma), Ok(mb)) if ma.len() == mb.len() => matches!((fs::read(a), fs::read(b)), (Ok(x), Ok(y)) if x == y),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// A unified diff of one file, `None` standing for a missing side (`/dev/null`)
+pub fn unified(path: &str, old: Option<&str>, new: Option<&str>) -> String {
+    let a: Vec<&str> = old.map_or_else(Vec::new, |s| s.lines().collect());
+    let b: Vec<&str> = new.map_or_else(Vec::new, |s| s.lines().collect());
+    let mut out = format!(
+        "--- {}\n+++ {}\n",
+        old.map_or("/dev/null".to_string(), |_| format!("a/{}", path)),
+        new.map_or("/dev/null".to_string(), |_| format!("b/{}", path)),
+    );
+
+    // Lines with their kind, numbered on each side
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for edit in edit_script(&a, &b) {
+        match edit {
+            Edit::Keep => {
+                lines.push((' ', a[i], i, j));
+                i += 1;
+                j += 1;
+            }
+            Edit::Delete => {
+                lines.push(('-', a[i], i, j));
+                i += 1;
+            }
+            Edit::Insert => {
+                lines.push(('+', b[j], i, j));
+                j += 1;
+            }
+        }
+    }
+
+    let changes: Vec<usize> = lines.iter().enumerate().filter(|(_, l)| l.0 != ' ').map(|(n, _)| n).collect();
+    let mut n = 0;
+    while n < changes.len() {
+        let start = changes[n].saturating_sub(CONTEXT_LINES);
+        let mut last = changes[n];
+        // Merge changes whose context would touch or overlap
+        while n + 1 < changes.len() && changes[n + 1] <= last + 2 * CONTEXT_LINES + 1 {
+            n += 1;
+            last = changes[n];
+        }
+        let end = (last + CONTEXT_LINES + 1).min(lines.len());
+        let hunk = &lines[start..end];
+        let old_len = hunk.iter().filter(|l| l.0 != '+').count();
+        let new_len = hunk.iter().filter(|l| l.0 != '-').count();
+        // An empty side is numbered by the line before it
+        let (_, _, old_start, new_start) = hunk[0];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_len > 0),
+            old_len,
+            new_start + usize::from(new_len > 0),
+            new_len
+        ));
+        for (kind, text, _, _) in hunk {
+            out.push(*kind);
+            out.push_str(text);
+            out.push('\n');
+        }
+        n += 1;
+    }
+    out
+}
+
+/// Shortest edit script from `a` to `b` (Myers), after setting aside the common prefix and suffix
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let mut edits = vec![Edit::Keep; prefix];
+    match myers(a_mid, b_mid) {
+        Some(middle) => edits.extend(middle),
+        None => {
+            edits.extend(std::iter::repeat_n(Edit::Delete, a_mid.len()));
+            edits.extend(std::iter::repeat_n(Edit::Insert, b_mid.len()));
+        }
+    }
+    edits.extend(std::iter::repeat_n(Edit::Keep, suffix));
+    edits
+}
+
+/// Myers' greedy diff, or `None` when more than `MAX_EDITS` edits are needed
+fn myers(a: &[&str], b: &[&str]) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    // The furthest x reached on diagonals -d-1..=d+1 before each round d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut rounds = None;
+    'search: for d in 0..=(max.min(MAX_EDITS) as isize) {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]);
+            let mut x = if down { v[(offset + k + 1) as usize] } else { v[(offset + k - 1) as usize] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                rounds = Some(d);
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..=rounds?).rev() {
+        let furthest = |k: isize| trace[d as usize][(k + d + 1) as usize];
+        if d == 0 {
+            edits.extend(std::iter::repeat_n(Edit::Keep, x as usize));
+            break;
+        }
+        let k = x - y;
+        let down = k == -d || (k != d && furthest(k - 1) < furthest(k + 1));
+        let prev_k = if down { k + 1 } else { k - 1 };
+        let prev_x = furthest(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        edits.push(if down { Edit::Insert } else { Edit::Delete });
+        (x, y) = (prev_x, prev_y);
+    }
+    edits.reverse();
+    Some(edits)
+}
@@ -0,0 +1,162 @@
+//! compdb.rs
+//! `--compile-commands`: include directories and defines from a `compile_commands.json`, so `{ext}` resolution
+//! can find the headers C and C++ files `#include`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{encoding, lang, walk};
+
+static ANGLE_INCLUDE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*#\s*include\s*<([^>\n]+)>").unwrap());
+static MACRO_INCLUDE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*#\s*include\s+([A-Za-z_]\w*)").unwrap());
+
+/// One compile command; `arguments` is preferred, `command` is split like a shell would
+#[derive(Deserialize)]
+struct Entry {
+    directory: PathBuf,
+    #[serde(default)]
+    arguments: Vec<String>,
+    #[serde(default)]
+    command: String,
+}
+
+/// Include directories (in command-line order, across all commands) and defines of a compilation database
+pub struct CompileDb {
+    root: PathBuf,
+    include_dirs: Vec<PathBuf>,
+    defines: HashMap<String, String>,
+}
+
+impl CompileDb {
+    /// Read a compilation database, keeping only the include directories under `root` so system headers are never packed
+    pub fn load(path: &Path, root: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let entries: Vec<Entry> = serde_json::from_str(&text).map_err(|e| format!("Cannot parse {}: {}", path.display(), e))?;
+        let mut db = CompileDb { root: root.to_path_buf(), include_dirs: Vec::new(), defines: HashMap::new() };
+        for entry in entries {
+            let args = match entry.arguments.is_empty() {
+                true => split_command(&entry.command),
+                false => entry.arguments,
+            };
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                let dir = match arg.as_str() {
+                    "-I" | "-iquote" | "-isystem" | "-idirafter" => args.next().map(String::as_str),
+                    a => a.strip_prefix("-I"),
+                };
+                if let Some(dir) = dir {
+                    if let Ok(dir) = walk::canonicalize(&entry.directory.join(dir)) {
+                        if dir.starts_with(root) && !db.include_dirs.contains(&dir) {
+                            db.include_dirs.push(dir);
+                        }
+                    }
+                    continue;
+                }
+                let define = match arg.as_str() {
+                    "-D" => args.next().map(String::as_str),
+                    a => a.strip_prefix("-D"),
+                };
+                if let Some(define) = define {
+                    let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+                    db.defines.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+        Ok(db)
+    }
+
+    pub fn include_dir_count(&self) -> usize {
+        self.include_dirs.len()
+    }
+
+    pub fn define_count(&self) -> usize {
+        self.defines.len()
+    }
+
+    /// The first include directory holding `header`, for a C or C++ `source`; a header reached through `..` or a
+    /// symlink must still lie under the root
+    pub fn find(&self, source: &Path, header: &str) -> Option<PathBuf> {
+        if !is_c_family(source) || Path::new(header).has_root() {
+            return None;
+        }
+        self.include_dirs.iter().find_map(|dir| self.under_root(&dir.join(header)))
+    }
+
+    /// `path`, canonicalized, if it is a file under the root
+    fn under_root(&self, path: &Path) -> Option<PathBuf> {
+        walk::canonicalize(path).ok().filter(|p| p.is_file() && p.starts_with(&self.root))
+    }
+
+    /// Headers `source` includes that the generic patterns miss, resolved: `#include <...>` and `#include MACRO`
+    /// where a define names the header. Includes found in no include directory (the standard library) are left out.
+    pub fn includes(&self, source: &Path) -> Vec<(String, PathBuf)> {
+        if !is_c_family(source) {
+            return Vec::new();
+        }
+        // Unreadable files are skipped with a warning when they are processed
+        let Ok(content) = encoding::read_to_string(source) else {
+            return Vec::new();
+        };
+        let angled = ANGLE_INCLUDE.captures_iter(&content).map(|cap| cap[1].trim().to_string());
+        let macros = MACRO_INCLUDE.captures_iter(&content).filter_map(|cap| {
+            let value = self.defines.get(&cap[1])?.trim();
+            let header = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'));
+            let header = header.or_else(|| value.strip_prefix('<').and_then(|v| v.strip_suffix('>')))?;
+            Some(header.to_string())
+        });
+        let mut found = Vec::new();
+        for header in angled.chain(macros) {
+            let next_to_source = source.parent().filter(|_| !Path::new(&header).has_root()).and_then(|dir| self.under_root(&dir.join(&header)));
+            if let Some(path) = next_to_source.or_else(|| self.find(source, &header)) {
+                found.push((header, path));
+            }
+        }
+        found
+    }
+}
+
+fn is_c_family(path: &Path) -> bool {
+    matches!(lang::language(path), Some("c" | "cpp"))
+}
+
+/// Split a command line into arguments the way a POSIX shell would, for quoting and backslashes
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                current.extend(chars.next());
+                in_arg = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
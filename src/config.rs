@@ -0,0 +1,136 @@
+//! config.rs
+//! Project defaults from a `bound.toml` (or `.bound.toml`) in the root directory or one of its ancestors,
+//! or from the file given with `--config`, with an optional `[profiles.NAME]` section applied on top (`--profile`).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// File names looked up in the root directory and its ancestors, nearest first
+const CONFIG_NAMES: &[&str] = &["bound.toml", ".bound.toml"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Token and size limits per extension (`md = { tl = 2000 }`), used where the command line sets none
+    limits: BTreeMap<String, Limits>,
+    /// Files always included, relative to the config file (`always = ["README.md"]`), on top of `--always`
+    always: Vec<PathBuf>,
+    /// Named sets of the same keys, chosen with `--profile` (`[profiles.docs.limits]`)
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// A `[profiles.NAME]` section: limits that override the defaults per field, and more `always` files
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Profile {
+    limits: BTreeMap<String, Limits>,
+    always: Vec<PathBuf>,
+}
+
+/// Default limits for one extension
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    #[serde(alias = "tl")]
+    pub token_limit: Option<usize>,
+    #[serde(alias = "sl")]
+    pub size_limit: Option<usize>,
+}
+
+impl Config {
+    /// Read `explicit`, or the nearest config file above `root`, applying `profile`; no file gives the defaults
+    pub fn load(root: &Path, explicit: Option<&Path>, profile: Option<&str>) -> Result<(Self, Option<PathBuf>), String> {
+        let path = match explicit {
+            Some(path) => Some(path.to_path_buf()),
+            None => root.ancestors().flat_map(|dir| CONFIG_NAMES.iter().map(move |name| dir.join(name))).find(|p| p.is_file()),
+        };
+        let Some(path) = path else {
+            return match profile {
+                Some(name) => Err(format!("--profile {} needs a bound.toml, and none was found", name)),
+                None => Ok((Config::default(), None)),
+            };
+        };
+        let text = fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let mut config: Config = toml::from_str(&text).map_err(|e| format!("Cannot parse {}: {}", path.display(), e))?;
+        // Keys match extensions case-insensitively, with or without the dot
+        config.limits = config.limits.into_iter().map(|(ext, l)| (extension_key(&ext), l)).collect();
+        if let Some(name) = profile {
+            let Some(overlay) = config.profiles.get(name).cloned() else {
+                let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+                return Err(format!("{} has no profile {:?} (profiles: {})", path.display(), name, known.join(", ")));
+            };
+            for (ext, limits) in overlay.limits {
+                let base = config.limits.entry(extension_key(&ext)).or_default();
+                base.token_limit = limits.token_limit.or(base.token_limit);
+                base.size_limit = limits.size_limit.or(base.size_limit);
+            }
+            config.always.extend(overlay.always);
+        }
+        let dir = path.parent().unwrap_or(Path::new(""));
+        config.always = config.always.iter().map(|p| dir.join(p)).collect();
+        Ok((config, Some(path)))
+    }
+
+    /// The token and size limits for `path`: each one from the command line if given, else the default for its extension
+    pub fn limits_for(&self, path: &Path, token_limit: Option<usize>, size_limit: Option<usize>) -> (Option<usize>, Option<usize>) {
+        let defaults = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|ext| self.limits.get(&ext.to_lowercase()))
+            .copied()
+            .unwrap_or_default();
+        (token_limit.or(defaults.token_limit), size_limit.or(defaults.size_limit))
+    }
+
+    /// The `always` files, resolved against the config file's directory
+    pub fn always(&self) -> &[PathBuf] {
+        &self.always
+    }
+
+    /// Names of the profiles the config file defines
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    /// The per-extension limits, for the incremental options fingerprint
+    pub fn limits(&self) -> &BTreeMap<String, Limits> {
+        &self.limits
+    }
+}
+
+/// The `[limits]` key an extension is stored under: lowercase, without a leading dot
+fn extension_key(ext: &str) -> String {
+    ext.trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_overrides_limits_per_field_and_adds_always() {
+        let dir = std::env::temp_dir().join(format!("bound-config-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("bound.toml");
+        fs::write(
+            &file,
+            "always = [\"README.md\"]\n[limits]\nmd = { tl = 2000, sl = 9000 }\n\n[profiles.docs]\nalways = [\"docs/index.md\"]\n[profiles.docs.limits]\nMD = { tl = 500 }\n",
+        )
+        .unwrap();
+
+        let (config, _) = Config::load(&dir, Some(&file), Some("docs")).unwrap();
+        assert_eq!(config.limits_for(Path::new("a.md"), None, None), (Some(500), Some(9000)));
+        assert_eq!(config.always(), [dir.join("README.md"), dir.join("docs/index.md")]);
+        assert_eq!(config.profile_names(), ["docs"]);
+
+        let (config, _) = Config::load(&dir, Some(&file), None).unwrap();
+        assert_eq!(config.limits_for(Path::new("a.md"), None, None), (Some(2000), Some(9000)));
+        assert_eq!(config.profile_names(), ["docs"]);
+        assert!(Config::load(&dir, Some(&file), Some("nope")).unwrap_err().contains("profiles: docs"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
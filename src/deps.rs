@@ -0,0 +1,274 @@
+//! deps.rs
+//! Finds file references (imports, requires, includes) and resolves them to paths under the root.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::encoding;
+use crate::walk;
+
+/// Patterns whose match is a module name, dotted in Python
+static MODULE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    vec![
+        Regex::new(r"(?m)^\s*import\s+([a-zA-Z0-9_\.]+)").unwrap(),
+        Regex::new(r"(?m)^\s*from\s+([a-zA-Z0-9_\.]+)\s+import").unwrap(),
+    ]
+});
+
+/// Patterns whose match is a path
+static PATH_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    vec![
+        Regex::new(r#"require\(['"](.+?)['"]\)"#).unwrap(),
+        Regex::new(r#"(?m)^\s*import\s+.*\s+from\s+['"](.+?)['"]"#).unwrap(),
+        Regex::new(r#"(?m)^\s*#include\s*["<](.+?)["<]"#).unwrap(),
+        // Data files loaded by a literal path with an extension: open("config/settings.yaml"), Path("data/users.csv"),
+        // fs.readFileSync('./schema.graphql'), os.ReadFile("testdata/in.json"), fopen("table.dat", "r")
+        Regex::new(
+            r#"\b(?:open|fopen|Path|read_to_string|read_csv|read_json|read_text|readFileSync|readFile|ReadFile)\s*\(\s*r?['"]([^'"\s:]+\.[A-Za-z0-9]+)['"]"#,
+        )
+        .unwrap(),
+    ]
+});
+
+/// Rust's ways of wiring in a file outside `mod` statements, relative to the referencing file:
+/// include_str!("../queries/foo.sql"), include_bytes!("logo.png"), #[path = "unix/imp.rs"]
+static RUST_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    vec![
+        Regex::new(r#"\binclude_(?:str|bytes)!\s*\(\s*r?"([^"]+)"\s*\)"#).unwrap(),
+        Regex::new(r#"#\[\s*path\s*=\s*r?"([^"]+)"\s*\]"#).unwrap(),
+    ]
+});
+
+/// Parse references generically (Python, JS, C/C++), plus Rust's file includes
+pub fn parse_references(path: &Path) -> std::io::Result<Vec<String>> {
+    let content = encoding::read_to_string(path)?;
+    let mut references = Vec::new();
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+
+    let patterns = MODULE_PATTERNS.iter().map(|re| (re, true)).chain(PATH_PATTERNS.iter().map(|re| (re, false)));
+    for (re, module) in patterns {
+        for cap in re.captures_iter(&content) {
+            if let Some(m) = cap.get(1) {
+                let mut r = m.as_str().to_string();
+                // Python names modules with dots, leading ones for relative imports; in paths a dot is part of a name
+                if module && ext == "py" {
+                    let name = r.trim_start_matches('.');
+                    let prefix = match r.len() - name.len() {
+                        0 => String::new(),
+                        1 => "./".to_string(),
+                        n => "../".repeat(n - 1),
+                    };
+                    r = format!("{}{}", prefix, name.replace('.', "/"));
+                }
+                // Only the last component decides; `./lib/util` has no extension
+                if Path::new(&r).extension().is_none() { r = format!("{}.{}", r, ext); }
+                references.push(r);
+            }
+        }
+    }
+    // These name the file exactly, so nothing is appended to an extensionless path
+    if ext == "rs" {
+        for re in RUST_PATTERNS.iter() {
+            references.extend(re.captures_iter(&content).map(|cap| cap[1].to_string()));
+        }
+    }
+    Ok(references)
+}
+
+/// Resolve a reference relative to the referencing file, or else to the root.
+/// `None` for absolute references and those leading out of the root, which are never followed.
+pub fn resolve(source: &Path, ref_str: &str, root: &Path) -> Option<PathBuf> {
+    if Path::new(ref_str).has_root() {
+        return None;
+    }
+    let base_dir = source.parent().unwrap_or(root);
+    let mut candidate = base_dir.join(ref_str);
+
+    if let Ok(canon) = walk::canonicalize(&candidate) {
+        candidate = canon;
+    } else {
+        // Missing files can't be canonicalized; fold `.` and `..` by hand
+        let mut normalized = PathBuf::new();
+        for comp in candidate.components() {
+            match comp {
+                std::path::Component::ParentDir => { normalized.pop(); },
+                std::path::Component::CurDir => {}
+                other => normalized.push(other),
+            }
+        }
+        candidate = normalized;
+    }
+
+    let under_root = candidate.starts_with(root);
+    if under_root && candidate.exists() {
+        return Some(candidate);
+    }
+    // Code often names files relative to the project root (`open("config/settings.yaml")`), not to itself
+    match walk::canonicalize(&root.join(ref_str)).ok().filter(|p| p.starts_with(root)) {
+        Some(from_root) => Some(from_root),
+        None => under_root.then_some(candidate),
+    }
+}
+
+/// `resolve`, falling back for `--deps-cross-lang` when the reference named no extension and no file of the
+/// referencing file's language exists: an existing file with the same name and any extension is taken instead
+/// (`import _speedups` finding `_speedups.c`, `require('./fixtures/users')` finding `users.json`)
+pub fn resolve_cross_lang(source: &Path, ref_str: &str, root: &Path) -> Option<PathBuf> {
+    let candidate = resolve(source, ref_str, root)?;
+    if candidate.exists() || candidate.extension() != source.extension() {
+        return Some(candidate);
+    }
+    let (Some(dir), Some(stem)) = (candidate.parent(), candidate.file_stem()) else {
+        return Some(candidate);
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Some(candidate);
+    };
+    let mut others: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).filter(|p| p.file_stem() == Some(stem)).collect();
+    others.sort();
+    // A sibling reached through a symlink must still lie under the root
+    let sibling = others.iter().find_map(|p| walk::canonicalize(p).ok().filter(|p| p.is_file() && p.starts_with(root)));
+    Some(sibling.unwrap_or(candidate))
+}
+
+/// Files among `files` that reference `target`, directly or through other referencing files,
+/// nearest first
+pub fn importers(target: &Path, files: &[PathBuf], root: &Path) -> Vec<PathBuf> {
+    let key = |p: &Path| walk::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let mut referenced_by: HashMap<PathBuf, Vec<&PathBuf>> = HashMap::new();
+    for file in files {
+        // Unreadable files are skipped with a warning when they are processed
+        let Ok(references) = parse_references(file) else { continue };
+        for target in references.iter().filter_map(|r| resolve(file, r, root)) {
+            referenced_by.entry(key(&target)).or_default().push(file);
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut visited = HashSet::from([key(target)]);
+    let mut queue = VecDeque::from([key(target)]);
+    while let Some(current) = queue.pop_front() {
+        for &file in referenced_by.get(&current).into_iter().flatten() {
+            if visited.insert(key(file)) {
+                found.push(file.clone());
+                queue.push_back(key(file));
+            }
+        }
+    }
+    found
+}
+
+/// What `{ext}` dependency resolution followed and what it could not
+#[derive(Debug, Default)]
+pub struct DepReport {
+    /// Referencing file and the reference that matched no file
+    pub unresolved: Vec<(PathBuf, String)>,
+    /// Resolved references, by referencing file
+    pub edges: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+#[derive(Serialize)]
+struct DepReportJson {
+    unresolved: Vec<UnresolvedJson>,
+    cycles: Vec<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct UnresolvedJson {
+    file: String,
+    reference: String,
+}
+
+impl DepReport {
+    pub fn add_edge(&mut self, from: &Path, to: &Path) {
+        self.edges.entry(from.to_path_buf()).or_default().insert(to.to_path_buf());
+    }
+
+    /// Number of resolved references
+    pub fn followed(&self) -> usize {
+        self.edges.values().map(|targets| targets.len()).sum()
+    }
+
+    /// Reference cycles, each starting and ending at its smallest path
+    pub fn cycles(&self) -> Vec<Vec<PathBuf>> {
+        let mut found = BTreeSet::new();
+        let mut done = HashSet::new();
+        for start in self.edges.keys() {
+            let mut stack = Vec::new();
+            self.find_cycles(start, &mut stack, &mut done, &mut found);
+        }
+        found.into_iter().collect()
+    }
+
+    fn find_cycles<'a>(
+        &'a self,
+        node: &'a PathBuf,
+        stack: &mut Vec<&'a PathBuf>,
+        done: &mut HashSet<&'a PathBuf>,
+        found: &mut BTreeSet<Vec<PathBuf>>,
+    ) {
+        if let Some(i) = stack.iter().position(|p| *p == node) {
+            let cycle = &stack[i..];
+            let min = (0..cycle.len()).min_by_key(|&j| cycle[j]).unwrap_or(0);
+            let mut rotated: Vec<PathBuf> = cycle[min..].iter().chain(&cycle[..min]).map(|p| (*p).clone()).collect();
+            rotated.push(rotated[0].clone());
+            found.insert(rotated);
+            return;
+        }
+        if done.contains(node) {
+            return;
+        }
+        stack.push(node);
+        for next in self.edges.get(node).into_iter().flatten() {
+            self.find_cycles(next, stack, done, found);
+        }
+        stack.pop();
+        done.insert(node);
+    }
+
+    /// The report as JSON, with paths relative to `root`
+    pub fn to_json(&self, root: &Path) -> serde_json::Result<String> {
+        let display = |p: &Path| p.strip_prefix(root).unwrap_or(p).display().to_string();
+        let report = DepReportJson {
+            unresolved: self
+                .unresolved
+                .iter()
+                .map(|(file, reference)| UnresolvedJson { file: display(file), reference: reference.clone() })
+                .collect(),
+            cycles: self.cycles().iter().map(|c| c.iter().map(|p| display(p)).collect()).collect(),
+        };
+        serde_json::to_string_pretty(&report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn cross_lang_sibling_stays_under_the_root() {
+        let base = std::env::temp_dir().join(format!("bound-deps-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("proj");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(base.join("outside_secret.txt"), "secret\n").unwrap();
+        fs::write(root.join("a.py"), "import helper\nimport data\n").unwrap();
+        std::os::unix::fs::symlink(base.join("outside_secret.txt"), root.join("helper.txt")).unwrap();
+        fs::write(root.join("data.json"), "{}\n").unwrap();
+        let root = walk::canonicalize(&root).unwrap();
+        let source = root.join("a.py");
+
+        let helper = resolve_cross_lang(&source, "helper.py", &root).unwrap();
+        assert_eq!(helper, root.join("helper.py"));
+        assert!(!helper.exists());
+        assert_eq!(resolve_cross_lang(&source, "data.py", &root), Some(root.join("data.json")));
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+//! diagnose.rs
+//! Explains an empty selection: what was scanned, which filters removed it, and what to try instead.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::cli::{AuthorShare, PackArgs};
+use crate::telemetry::SkipReason;
+
+/// Extensions listed for files the language filter dropped
+const TOP_EXTENSIONS: usize = 5;
+
+/// One line per reason files were left out, with a suggested adjustment where there is one
+pub fn nothing_matched(
+    skipped: &[(PathBuf, SkipReason)],
+    args: &PackArgs,
+    filter: Option<&str>,
+    diff_range: Option<&str>,
+) -> Vec<String> {
+    let count = |reason: SkipReason| skipped.iter().filter(|(_, r)| *r == reason).count();
+    let mut lines = Vec::new();
+
+    let filtered: Vec<&PathBuf> = skipped.iter().filter(|(_, r)| *r == SkipReason::Filtered).map(|(p, _)| p).collect();
+    if !filtered.is_empty() {
+        let mut by_ext: HashMap<String, usize> = HashMap::new();
+        for path in &filtered {
+            let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+            *by_ext.entry(ext.to_string()).or_default() += 1;
+        }
+        let mut exts: Vec<(String, usize)> = by_ext.into_iter().filter(|(e, _)| !e.is_empty()).collect();
+        exts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let found = exts
+            .iter()
+            .take(TOP_EXTENSIONS)
+            .map(|(e, n)| format!(".{} ({})", e, n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let what = match filter {
+            Some(f) => format!("didn't match the {} filter", f),
+            None => "were excluded by --exclude-ext or --notebook skip".to_string(),
+        };
+        let mut line = format!("{} file(s) {}", filtered.len(), what);
+        if !found.is_empty() {
+            line.push_str(&format!("; found {}", found));
+        }
+        if let (Some((top, _)), Some(_)) = (exts.first(), filter) {
+            line.push_str(&format!(" (try [{}] or drop the filter)", top));
+        }
+        lines.push(line);
+    }
+
+    for (reason, what, hint) in [
+        (SkipReason::Lock, "lock file(s)", "--include-locks".to_string()),
+        (SkipReason::Vendored, "file(s) under vendored directories", "--include-vendor".to_string()),
+        (SkipReason::Generated, "generated or minified file(s)", "--include-generated".to_string()),
+        (
+            SkipReason::NoMatch,
+            "file(s) without a --grep match",
+            format!("loosen --grep {}", args.grep.as_ref().map_or("", |r| r.as_str())),
+        ),
+        (
+            SkipReason::TooDeep,
+            "director(ies) below the depth limit",
+            format!("raise or drop -d {}", args.depth_limit.unwrap_or(0)),
+        ),
+        (SkipReason::OutsideWindow, "file(s) not changed in the --since/--until window", "widen the window".to_string()),
+        (
+            SkipReason::OtherAuthor,
+            "file(s) not written by the --author",
+            match args.author_share {
+                AuthorShare::Majority => "try --author-share any".to_string(),
+                AuthorShare::Any => "check the name with `git shortlog -sne`".to_string(),
+            },
+        ),
+        (SkipReason::Unchanged, "file(s) identical in both snapshots", String::new()),
+        (SkipReason::Ignored, "hidden or ignored path(s) (.gitignore/.boundignore)", "list them explicitly".to_string()),
+        (SkipReason::TooLarge, "file(s) over --max-file-size", "raise --max-file-size".to_string()),
+        (SkipReason::TimedOut, "file(s) that exceeded --file-timeout", "raise --file-timeout".to_string()),
+        (SkipReason::Binary, "binary file(s)", String::new()),
+        (SkipReason::Special, "FIFO(s), socket(s), or device node(s)", String::new()),
+        (SkipReason::UnknownEncoding, "file(s) in an unknown encoding", String::new()),
+        (SkipReason::Unreadable, "unreadable file(s)", "check permissions".to_string()),
+    ] {
+        let n = count(reason);
+        if n == 0 {
+            continue;
+        }
+        if hint.is_empty() {
+            lines.push(format!("{} {}", n, what));
+        } else {
+            lines.push(format!("{} {} ({})", n, what, hint));
+        }
+    }
+
+    if args.git_tracked {
+        lines.push("only git-tracked files were considered (--git-tracked)".to_string());
+    }
+    if let Some(range) = diff_range {
+        lines.push(format!("only files changed in {} were considered", range));
+    }
+    if lines.is_empty() {
+        lines.push("no files were found under the given paths".to_string());
+    }
+    lines
+}
@@ -0,0 +1,196 @@
+//! documents.rs
+//! Extracts plain text from PDF and DOCX files so design documents can be packed alongside code.
+
+use std::fs;
+use std::io::{self, Read};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use flate2::read::DeflateDecoder;
+
+use crate::encoding;
+
+/// Largest decompressed `word/document.xml` read from a DOCX
+const MAX_DOCX_XML_BYTES: u64 = 64 << 20;
+
+/// A document format text is extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DocumentFormat {
+    Pdf,
+    Docx,
+}
+
+impl DocumentFormat {
+    /// The format of `path`, judged by its extension
+    pub fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "pdf" => Some(DocumentFormat::Pdf),
+            "docx" => Some(DocumentFormat::Docx),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentFormat::Pdf => "pdf",
+            DocumentFormat::Docx => "docx",
+        }
+    }
+}
+
+/// The text of a document
+pub fn extract(path: &Path, format: DocumentFormat) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    let text = match format {
+        DocumentFormat::Pdf => pdf_text(&bytes),
+        DocumentFormat::Docx => docx_text(&bytes),
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("cannot extract {} text: {}", format.as_str(), e)))?;
+    Ok(text)
+}
+
+/// A file's text: extracted for documents, decoded for everything else
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    match DocumentFormat::of(path) {
+        Some(format) => extract(path, format),
+        None => encoding::read_to_string(path),
+    }
+}
+
+fn pdf_text(bytes: &[u8]) -> Result<String, String> {
+    // The extractor panics on some malformed files
+    let text = panic::catch_unwind(AssertUnwindSafe(|| pdf_extract::extract_text_from_mem(bytes)))
+        .map_err(|_| "malformed PDF".to_string())?
+        .map_err(|e| e.to_string())?;
+    // Collapse the runs of blank lines left between text objects and pages
+    let mut out = String::with_capacity(text.len());
+    let mut blank = 0;
+    for line in text.lines().map(str::trim_end) {
+        blank = if line.is_empty() { blank + 1 } else { 0 };
+        if blank < 2 {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    Ok(out.trim_start_matches('\n').to_string())
+}
+
+/// Paragraph text from `word/document.xml`, one paragraph per line
+fn docx_text(bytes: &[u8]) -> Result<String, String> {
+    let xml = zip_entry(bytes, "word/document.xml")?;
+    let xml = String::from_utf8(xml).map_err(|_| "document.xml is not UTF-8".to_string())?;
+    let mut out = String::new();
+    let mut in_text = false;
+    let mut rest = xml.as_str();
+    while let Some(lt) = rest.find('<') {
+        if in_text {
+            out.push_str(&unescape(&rest[..lt]));
+        }
+        let Some(gt) = rest[lt..].find('>') else {
+            break;
+        };
+        let tag = &rest[lt + 1..lt + gt];
+        let closing = tag.starts_with('/');
+        let empty = tag.ends_with('/');
+        let name = tag.trim_start_matches('/').split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        match name {
+            // Deleted tracked changes are `w:delText` and stay out
+            "w:t" => in_text = !closing && !empty,
+            "w:tab" if !closing => out.push('\t'),
+            "w:br" | "w:cr" if !closing => out.push('\n'),
+            "w:p" if closing || empty => out.push('\n'),
+            _ => {}
+        }
+        rest = &rest[lt + gt + 1..];
+    }
+    Ok(out)
+}
+
+/// Decode the XML entities in text content
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let decoded = match &rest[1..semi] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity.strip_prefix('#').and_then(|d| d.parse().ok()).and_then(char::from_u32),
+            },
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The contents of one stored or deflated entry of a ZIP archive
+fn zip_entry(zip: &[u8], name: &str) -> Result<Vec<u8>, String> {
+    let u16_at = |at: usize| zip.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let u32_at = |at: usize| zip.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let not_zip = || "not a ZIP archive".to_string();
+
+    // The end-of-central-directory record sits in the last 64 KiB, before an optional comment
+    let search_from = zip.len().saturating_sub(22 + 0xFFFF);
+    let eocd = (search_from..zip.len().saturating_sub(21))
+        .rev()
+        .find(|&i| zip[i..i + 4] == [0x50, 0x4B, 0x05, 0x06])
+        .ok_or_else(not_zip)?;
+    let entries = u16_at(eocd + 10).ok_or_else(not_zip)?;
+    let mut at = u32_at(eocd + 16).ok_or_else(not_zip)?;
+
+    for _ in 0..entries {
+        if u32_at(at) != Some(0x0201_4B50) {
+            return Err(not_zip());
+        }
+        let method = u16_at(at + 10).ok_or_else(not_zip)?;
+        let compressed = u32_at(at + 20).ok_or_else(not_zip)?;
+        let name_len = u16_at(at + 28).ok_or_else(not_zip)?;
+        let extra_len = u16_at(at + 30).ok_or_else(not_zip)?;
+        let comment_len = u16_at(at + 32).ok_or_else(not_zip)?;
+        let local = u32_at(at + 42).ok_or_else(not_zip)?;
+        let entry_name = zip.get(at + 46..at + 46 + name_len).ok_or_else(not_zip)?;
+        at += 46 + name_len + extra_len + comment_len;
+        if entry_name != name.as_bytes() {
+            continue;
+        }
+
+        // The local header's name and extra field lengths can differ from the central directory's
+        let start = local + 30 + u16_at(local + 26).ok_or_else(not_zip)? + u16_at(local + 28).ok_or_else(not_zip)?;
+        let data = zip.get(start..start + compressed).ok_or_else(not_zip)?;
+        return match method {
+            0 => Ok(data.to_vec()),
+            8 => {
+                let mut out = Vec::new();
+                DeflateDecoder::new(data)
+                    .take(MAX_DOCX_XML_BYTES)
+                    .read_to_end(&mut out)
+                    .map_err(|e| e.to_string())?;
+                Ok(out)
+            }
+            other => Err(format!("{} uses unsupported compression method {}", name, other)),
+        };
+    }
+    Err(format!("no {} in the archive", name))
+}
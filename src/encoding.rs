@@ -0,0 +1,159 @@
+//! encoding.rs
+//! Detects UTF-8, UTF-16, and Latin-1/Windows-1252 text (BOM sniffing plus byte heuristics) and transcodes it to UTF-8.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Share of byte pairs that must have a NUL on one side to treat BOM-less text as UTF-16
+const UTF16_NUL_RATIO: f64 = 0.4;
+
+/// Share of control bytes above which 8-bit text is considered undetermined
+const CONTROL_RATIO: f64 = 0.01;
+
+/// Windows-1252 characters for bytes 0x80-0x9F; unassigned bytes keep their Latin-1 code point
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}', '\u{2C6}', '\u{2030}',
+    '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}',
+    '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}',
+    '\u{17E}', '\u{178}',
+];
+
+/// A text encoding bound can transcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+    Latin1,
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "Windows-1252",
+            Encoding::Latin1 => "Latin-1",
+        }
+    }
+
+    /// Decode `bytes` (possibly cut at either end by a partial read) to UTF-8.
+    /// UTF-16 byte order marks are dropped since the output is no longer UTF-16.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => {
+                // Skip continuation bytes of a character split at the start, and drop one split at the end
+                let start = bytes.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count();
+                let bytes = &bytes[start..];
+                match std::str::from_utf8(bytes) {
+                    Ok(s) => s.to_string(),
+                    Err(e) if e.error_len().is_none() => String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned(),
+                    Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+                }
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| match self {
+                        Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                        _ => u16::from_be_bytes([pair[0], pair[1]]),
+                    })
+                    .collect();
+                let text = String::from_utf16_lossy(&units);
+                text.strip_prefix('\u{FEFF}').map(str::to_string).unwrap_or(text)
+            }
+            Encoding::Windows1252 => bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                    _ => b as char,
+                })
+                .collect(),
+            Encoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+        }
+    }
+}
+
+/// A file whose bytes don't look like text in any supported encoding
+#[derive(Debug)]
+pub struct UnknownEncoding;
+
+impl fmt::Display for UnknownEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("could not determine text encoding")
+    }
+}
+
+impl Error for UnknownEncoding {}
+
+/// Guess the encoding of `bytes` (a whole file or its first chunk).
+/// Fails with `InvalidData` for binary content, wrapping [`UnknownEncoding`] when it looks like text in no known encoding.
+pub fn detect(bytes: &[u8]) -> io::Result<Encoding> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Ok(Encoding::Utf8);
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Ok(Encoding::Utf16Le);
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Ok(Encoding::Utf16Be);
+    }
+
+    let has_nul = bytes.contains(&0);
+    if !has_nul {
+        match std::str::from_utf8(bytes) {
+            Ok(_) => return Ok(Encoding::Utf8),
+            // Only a character cut off at the end of the sample
+            Err(e) if e.error_len().is_none() => return Ok(Encoding::Utf8),
+            Err(_) => {}
+        }
+    }
+
+    if has_nul {
+        let pairs = (bytes.len() / 2).max(1) as f64;
+        let even_nuls = bytes.iter().step_by(2).filter(|&&b| b == 0).count() as f64;
+        let odd_nuls = bytes.iter().skip(1).step_by(2).filter(|&&b| b == 0).count() as f64;
+        if odd_nuls / pairs >= UTF16_NUL_RATIO && even_nuls == 0.0 {
+            return Ok(Encoding::Utf16Le);
+        }
+        if even_nuls / pairs >= UTF16_NUL_RATIO && odd_nuls == 0.0 {
+            return Ok(Encoding::Utf16Be);
+        }
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "binary content"));
+    }
+
+    let controls = bytes
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    if controls as f64 > bytes.len() as f64 * CONTROL_RATIO {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, UnknownEncoding));
+    }
+    if bytes.iter().any(|b| (0x80..=0x9F).contains(b)) {
+        Ok(Encoding::Windows1252)
+    } else {
+        Ok(Encoding::Latin1)
+    }
+}
+
+/// Read a whole file as UTF-8 text, transcoding it if needed
+pub fn read_text(path: &Path) -> io::Result<(String, Encoding)> {
+    let bytes = fs::read(path)?;
+    let encoding = detect(&bytes)?;
+    let text = match encoding {
+        // Keep the fast path allocation-free for the common case
+        Encoding::Utf8 => String::from_utf8(bytes).unwrap_or_else(|e| encoding.decode(e.as_bytes())),
+        _ => encoding.decode(&bytes),
+    };
+    Ok((text, encoding))
+}
+
+/// [`read_text`] without the detected encoding
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    read_text(path).map(|(text, _)| text)
+}
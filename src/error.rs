@@ -0,0 +1,117 @@
+//! error.rs
+//! `BoundError`: run failures by kind, so exit codes and `--error-format json` tell wrappers what went wrong.
+
+use std::error::Error;
+use std::fmt;
+
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::exit;
+
+/// A failure that ends the run; other errors are reported as kind `other`
+pub enum BoundError {
+    /// Invalid arguments or option combinations
+    Usage(String),
+    /// A malformed language filter
+    Filter(String),
+    /// A path, archive, repository, or workspace that can't be found or walked
+    Traversal(String),
+    /// A file that can't be read or transformed with `--strict`
+    Read(String),
+    /// The aggregation exceeded the token budget with `--strict` or `--fail-on-overflow`
+    Budget(String),
+    /// No files were left to aggregate after filtering
+    NoMatch(String),
+    /// Writing, uploading, or posting the output failed
+    Output(String),
+    /// The clipboard couldn't be set
+    Clipboard(String),
+}
+
+impl BoundError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BoundError::Usage(_) => "usage",
+            BoundError::Filter(_) => "filter",
+            BoundError::Traversal(_) => "traversal",
+            BoundError::Read(_) => "read",
+            BoundError::Budget(_) => "budget",
+            BoundError::NoMatch(_) => "no_match",
+            BoundError::Output(_) => "output",
+            BoundError::Clipboard(_) => "clipboard",
+        }
+    }
+
+    /// Process exit code for this kind of failure
+    pub fn code(&self) -> i32 {
+        match self {
+            BoundError::Budget(_) => exit::BUDGET_EXCEEDED,
+            BoundError::NoMatch(_) => exit::NO_MATCH,
+            BoundError::Output(_) | BoundError::Clipboard(_) => exit::OUTPUT_FAILED,
+            _ => exit::FAILURE,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            BoundError::Usage(m)
+            | BoundError::Filter(m)
+            | BoundError::Traversal(m)
+            | BoundError::Read(m)
+            | BoundError::Budget(m)
+            | BoundError::NoMatch(m)
+            | BoundError::Output(m)
+            | BoundError::Clipboard(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for BoundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+// Matches how `main` prints plain string errors
+impl fmt::Debug for BoundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.message())
+    }
+}
+
+impl Error for BoundError {}
+
+/// How a failed run reports its error on stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// `Error: "message"`
+    Text,
+    /// One line of `{"error": {"kind", "code", "message"}}`
+    Json,
+}
+
+impl ErrorFormat {
+    /// The format asked for on the command line, for errors raised while parsing it
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let json = args.iter().enumerate().any(|(i, a)| {
+            a == "--error-format=json" || (a == "--error-format" && args.get(i + 1).is_some_and(|v| v == "json"))
+        });
+        if json { ErrorFormat::Json } else { ErrorFormat::Text }
+    }
+}
+
+/// Print a run's error in `format`, returning its exit code
+pub fn report(error: &(dyn Error + 'static), format: ErrorFormat) -> i32 {
+    let known = error.downcast_ref::<BoundError>();
+    let code = known.map_or(exit::FAILURE, BoundError::code);
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {:?}", error),
+        ErrorFormat::Json => {
+            let kind = known.map_or("other", BoundError::kind);
+            eprintln!("{}", json!({ "error": { "kind": kind, "code": code, "message": error.to_string() } }));
+        }
+    }
+    code
+}
@@ -0,0 +1,11 @@
+//! exit.rs
+//! Process exit codes, so scripts can branch on why a run failed.
+
+/// Any other error (bad arguments, unreadable inputs with `--strict`, ...)
+pub const FAILURE: i32 = 1;
+/// The aggregation exceeded the token budget with `--strict` or `--fail-on-overflow`
+pub const BUDGET_EXCEEDED: i32 = 2;
+/// No files were left to aggregate after filtering
+pub const NO_MATCH: i32 = 3;
+/// Writing the output file or setting the clipboard failed
+pub const OUTPUT_FAILED: i32 = 4;
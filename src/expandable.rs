@@ -1,14 +1,14 @@
 //! expandable.rs
 //! Provides helper functions to wrap content in expandable{} blocks.
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use serde::Serialize;
 
 /// Represents a generic expandable block
 #[derive(Debug, Clone, Serialize)]
 pub struct ExpandableBlock {
     pub tag: String,
-    pub attributes: HashMap<String, String>,
+    pub attributes: BTreeMap<String, String>,
     pub content: String,
 }
 
@@ -17,7 +17,7 @@ impl ExpandableBlock {
     pub fn new(tag: &str, content: &str) -> Self {
         ExpandableBlock {
             tag: tag.to_string(),
-            attributes: HashMap::new(),
+            attributes: BTreeMap::new(),
             content: content.to_string(),
         }
     }
@@ -31,7 +31,7 @@ impl ExpandableBlock {
     /// Render the block as a string
     pub fn render(&self) -> String {
         let mut lines = Vec::new();
-        lines.push(format!("expandable{{"));
+        lines.push("expandable{".to_string());
         lines.push(format!("  type: {}", self.tag));
 
         for (k, v) in &self.attributes {
@@ -0,0 +1,109 @@
+//! filter.rs
+//! Parses the `[ext]` / `{ext}` filter syntax and matches paths against it.
+
+use std::path::Path;
+
+use crate::lang;
+
+/// Extension-based file selection
+#[derive(Debug, Clone, Default)]
+pub struct LanguageFilter {
+    /// Extensions to include; empty means every extension
+    pub include: Vec<String>,
+    /// Extensions to exclude, checked after `include`
+    pub exclude: Vec<String>,
+    /// Whether referenced files should be followed (`{ext}` syntax)
+    pub dep_aware: bool,
+    /// Match extensionless files by name and shebang (`--detect-lang`)
+    pub detect_lang: bool,
+}
+
+impl LanguageFilter {
+    /// Parse `[ext]`, `{ext}`, comma-separated lists (`[rs,toml]`), language names (`[python]`), and negations (`[!lock,min.js]`)
+    pub fn parse(filter: Option<&str>) -> Result<Self, String> {
+        let (inner, dep_aware) = match filter {
+            None => return Ok(Self::default()),
+            Some(f) if f.starts_with('[') && f.ends_with(']') => (&f[1..f.len() - 1], false),
+            Some(f) if f.starts_with('{') && f.ends_with('}') => (&f[1..f.len() - 1], true),
+            Some(f) => return Err(format!("Invalid filter format: '{}'", f)),
+        };
+
+        let (negated, list) = match inner.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, inner),
+        };
+        let exts = split_extensions(list);
+
+        Ok(if negated {
+            LanguageFilter { include: Vec::new(), exclude: exts, dep_aware, detect_lang: false }
+        } else {
+            LanguageFilter { include: exts, exclude: Vec::new(), dep_aware, detect_lang: false }
+        })
+    }
+
+    /// Add extensions to exclude (from `--exclude-ext`)
+    pub fn exclude_extensions(&mut self, exts: &[String]) {
+        self.exclude.extend(exts.iter().flat_map(|e| split_extensions(e)));
+    }
+
+    /// Whether `path` passes the include and exclude lists
+    pub fn matches(&self, path: &Path) -> bool {
+        let filtering = !self.include.is_empty() || !self.exclude.is_empty();
+        let detected = if self.detect_lang && filtering && path.extension().is_none() {
+            lang::detect(path).map_or(&[][..], lang::language_extensions)
+        } else {
+            &[]
+        };
+        let listed = |exts: &[String]| exts.iter().any(|e| has_extension(path, e) || detected.contains(&e.as_str()));
+        (self.include.is_empty() || listed(&self.include)) && !listed(&self.exclude)
+    }
+}
+
+/// Split a comma-separated extension list, dropping leading dots and blanks.
+/// Entries are lowercased, and language names (`python`, `cpp`) expand to their extensions.
+fn split_extensions(list: &str) -> Vec<String> {
+    let mut exts: Vec<String> = Vec::new();
+    for entry in list.split(',').map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase()) {
+        let expanded = match lang::extensions(&entry) {
+            Some(aliased) => aliased.iter().map(|e| e.to_string()).collect(),
+            None if !entry.is_empty() => vec![entry],
+            None => Vec::new(),
+        };
+        for ext in expanded {
+            if !exts.contains(&ext) {
+                exts.push(ext);
+            }
+        }
+    }
+    exts
+}
+
+/// Whether the file name ends in `.ext`; multi-part extensions like `min.js` are supported
+fn has_extension(path: &Path, ext: &str) -> bool {
+    // Lossy conversion keeps a valid suffix intact when earlier bytes aren't UTF-8
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+        return false;
+    };
+    let suffix_start = name.len().wrapping_sub(ext.len());
+    name.len() > ext.len() + 1
+        && name.is_char_boundary(suffix_start)
+        && name[suffix_start..].eq_ignore_ascii_case(ext)
+        && name.as_bytes()[suffix_start - 1] == b'.'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn extension_matches_after_odd_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        assert!(has_extension(Path::new(OsStr::from_bytes(b"bad\xff.rs")), "rs"));
+        assert!(has_extension(Path::new(OsStr::from_bytes(b"new\nline.min.js")), "min.js"));
+        assert!(!has_extension(Path::new(OsStr::from_bytes(b"bad.r\xff")), "rs"));
+        assert!(!has_extension(Path::new(OsStr::from_bytes(b".rs")), "rs"));
+    }
+}
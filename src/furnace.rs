@@ -18,7 +18,7 @@ impl FurnaceReport {
     /// Render the report as an expandable{} block
     pub fn render(&self) -> String {
         let mut content = String::new();
-        content.push_str(&format!("Notes:\n"));
+        content.push_str("Notes:\n");
         for note in &self.notes {
             content.push_str(&format!("- {}\n", note));
         }
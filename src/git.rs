@@ -0,0 +1,293 @@
+//! git.rs
+//! Thin wrappers around the git CLI for repository-aware file selection.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+
+/// Last commit that touched a file
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub author: String,
+    pub date: String,
+}
+
+/// Run a git command in `dir` and return its stdout, failing on a nonzero exit
+fn run_git(dir: &Path, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Convert NUL-separated path output from git into absolute paths under `root`
+fn paths_from_nul_output(root: &Path, output: &[u8]) -> Vec<PathBuf> {
+    output
+        .split(|&b| b == 0)
+        .filter(|p| !p.is_empty())
+        .map(|p| root.join(path_from_bytes(p)))
+        .collect()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Files under `root` that are tracked by git
+pub fn tracked_files(root: &Path) -> io::Result<HashSet<PathBuf>> {
+    let output = run_git(root, &["ls-files", "-z"])?;
+    Ok(paths_from_nul_output(root, &output).into_iter().collect())
+}
+
+/// Files under `root` added or modified in the given revision range (deletions excluded)
+pub fn changed_files(root: &Path, range: &str) -> io::Result<HashSet<PathBuf>> {
+    let output = run_git(
+        root,
+        &["diff", "--name-only", "-z", "--relative", "--diff-filter=d", range],
+    )?;
+    Ok(paths_from_nul_output(root, &output).into_iter().collect())
+}
+
+/// Lines added or modified in each file under `root` in the given revision range, as
+/// `(start, count)` spans of the new version; a pure deletion has count 0 and starts at the line before it
+pub fn changed_hunks(root: &Path, range: &str) -> io::Result<HashMap<PathBuf, Vec<(usize, usize)>>> {
+    let output = run_git(
+        root,
+        &["-c", "core.quotePath=false", "diff", "-U0", "--no-color", "--no-ext-diff", "--relative", "--diff-filter=d", range],
+    )?;
+    // Patch text has no -z form; names are read as raw bytes, and git C-quotes those with control characters
+    let mut hunks: HashMap<PathBuf, Vec<(usize, usize)>> = HashMap::new();
+    let mut current = None;
+    for line in output.split(|&b| b == b'\n') {
+        if let Some(name) = line.strip_prefix(b"+++ ") {
+            let name = match name.first() {
+                Some(b'"') => unquote_c(name),
+                _ => name.strip_suffix(b"\t").unwrap_or(name).to_vec(),
+            };
+            current = name.strip_prefix(b"b/").map(|p| root.join(path_from_bytes(p)));
+        } else if let (Some(header), Some(path)) = (line.strip_prefix(b"@@ "), &current) {
+            // @@ -old_start[,old_count] +new_start[,new_count] @@
+            let header = String::from_utf8_lossy(header);
+            let Some(new) = header.split_whitespace().find_map(|f| f.strip_prefix('+')) else {
+                continue;
+            };
+            let (start, count) = match new.split_once(',') {
+                Some((start, count)) => (start.parse().unwrap_or(0), count.parse().unwrap_or(0)),
+                None => (new.parse().unwrap_or(0), 1),
+            };
+            hunks.entry(path.clone()).or_default().push((start, count));
+        }
+    }
+    Ok(hunks)
+}
+
+/// The most recent commit touching `path`, or `None` if the file is untracked
+pub fn last_commit(root: &Path, path: &Path) -> io::Result<Option<CommitInfo>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["log", "-1", "--format=%H%x00%an%x00%aI", "--"])
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("git log failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.trim_end().splitn(3, '\0');
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(sha), Some(author), Some(date)) if !sha.is_empty() => Ok(Some(CommitInfo {
+            sha: sha.to_string(),
+            author: author.to_string(),
+            date: date.to_string(),
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Unix time of the last commit touching each file under `root`, from a single `git log` pass
+pub fn commit_times(root: &Path) -> io::Result<HashMap<PathBuf, i64>> {
+    let output = run_git(root, &["log", "--format=%x01%ct", "--name-only", "-z", "--relative"])?;
+    // Fields are NUL-terminated: `\x01TIME`, then `\nNAME` for a commit's first file and `NAME` for the rest
+    let mut times = HashMap::new();
+    let mut current = 0;
+    for field in output.split(|&b| b == 0) {
+        let field = field.strip_prefix(b"\n").unwrap_or(field);
+        if let Some(ts) = field.strip_prefix(b"\x01") {
+            current = String::from_utf8_lossy(ts).trim().parse().unwrap_or(0);
+        } else if !field.is_empty() {
+            // Newest commits come first, so the first time seen is the latest
+            times.entry(root.join(path_from_bytes(field))).or_insert(current);
+        }
+    }
+    Ok(times)
+}
+
+/// The bytes of a name git wrapped in double quotes, with its C escapes (`\n`, `\"`, `\303`) undone
+fn unquote_c(quoted: &[u8]) -> Vec<u8> {
+    let inner = quoted.strip_prefix(b"\"").unwrap_or(quoted);
+    let inner = inner.strip_suffix(b"\"").unwrap_or(inner);
+    let mut out = Vec::with_capacity(inner.len());
+    let mut bytes = inner.iter().copied();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(d @ b'0'..=b'7') => {
+                let mut value = u32::from(d - b'0');
+                for _ in 0..2 {
+                    match bytes.clone().next() {
+                        Some(d @ b'0'..=b'7') => {
+                            bytes.next();
+                            value = value * 8 + u32::from(d - b'0');
+                        }
+                        _ => break,
+                    }
+                }
+                out.push(value as u8);
+            }
+            Some(b'a') => out.push(0x07),
+            Some(b'b') => out.push(0x08),
+            Some(b'f') => out.push(0x0c),
+            Some(b'n') => out.push(b'\n'),
+            Some(b'r') => out.push(b'\r'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'v') => out.push(0x0b),
+            Some(other) => out.push(other),
+            None => out.push(b'\\'),
+        }
+    }
+    out
+}
+
+/// Lines of `path` last changed by each author, keyed `Name <email>`, from `git blame`
+pub fn blame_authors(root: &Path, path: &Path) -> io::Result<HashMap<String, usize>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("git blame failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+
+    let mut lines = HashMap::new();
+    let mut author = "";
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        if let Some(name) = line.strip_prefix("author ") {
+            author = name;
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            // Each blamed line repeats its commit's headers, so this runs once per line
+            *lines.entry(format!("{} {}", author, mail)).or_insert(0) += 1;
+        }
+    }
+    Ok(lines)
+}
+
+/// The commit HEAD points at in the repository containing `dir`
+pub fn head(dir: &Path) -> io::Result<String> {
+    let output = run_git(dir, &["rev-parse", "HEAD"])?;
+    Ok(String::from_utf8_lossy(&output).trim_end().to_string())
+}
+
+/// Top-level directory of the repository containing `dir`
+pub fn toplevel(dir: &Path) -> io::Result<PathBuf> {
+    let output = run_git(dir, &["rev-parse", "--show-toplevel"])?;
+    Ok(PathBuf::from(String::from_utf8_lossy(&output).trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unquote_c_undoes_git_escapes() {
+        assert_eq!(unquote_c(br#""b/new\nline.txt""#), b"b/new\nline.txt");
+        assert_eq!(unquote_c(br#""b/q\"uote\\.txt""#), b"b/q\"uote\\.txt");
+        assert_eq!(unquote_c(br#""b/caf\303\251\t.txt""#), "b/café\t.txt".as_bytes());
+    }
+
+    /// A repository with one commit adding `names`, and a second appending a line to each
+    #[cfg(unix)]
+    fn repo_with(names: &[&[u8]]) -> PathBuf {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("bound-git-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let git = |args: &[&str]| {
+            let ok = Command::new("git")
+                .arg("-C")
+                .arg(&dir)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t", "-c", "commit.gpgsign=false"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status
+                .success();
+            assert!(ok, "git {:?} failed", args);
+        };
+        git(&["init", "-q"]);
+        for name in names {
+            std::fs::write(dir.join(OsStr::from_bytes(name)), "one\n").unwrap();
+        }
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "add"]);
+        for name in names {
+            std::fs::write(dir.join(OsStr::from_bytes(name)), "one\ntwo\n").unwrap();
+        }
+        git(&["commit", "-q", "-a", "-m", "edit"]);
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn odd_file_names_survive_git_output() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let names: &[&[u8]] = &[b"new\nline.txt", b"bad\xff.txt", b"q\"uote\\.txt", b"tab\t.txt", b"plain.txt"];
+        let dir = repo_with(names);
+        let expected: HashSet<PathBuf> = names.iter().map(|n| dir.join(OsStr::from_bytes(n))).collect();
+
+        assert_eq!(tracked_files(&dir).unwrap(), expected);
+        assert_eq!(changed_files(&dir, "HEAD~1").unwrap(), expected);
+
+        let times = commit_times(&dir).unwrap();
+        assert_eq!(times.keys().cloned().collect::<HashSet<_>>(), expected);
+        assert!(times.values().all(|&t| t > 0));
+
+        let sha = head(&dir).unwrap();
+        assert!(sha.len() == 40 && sha.bytes().all(|b| b.is_ascii_hexdigit()), "{}", sha);
+
+        let hunks = changed_hunks(&dir, "HEAD~1").unwrap();
+        assert_eq!(hunks.keys().cloned().collect::<HashSet<_>>(), expected);
+        assert!(hunks.values().all(|spans| spans == &[(2, 1)]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+//! html.rs
+//! Renders `--format html`: a single self-contained page with a collapsible file tree and highlighted sources.
+
+use tree_sitter::Node;
+
+use crate::skeleton;
+
+/// Node kinds rendered as numbers
+const NUMBER_KINDS: &[&str] = &["integer_literal", "float_literal", "number", "integer", "float", "int_literal", "imaginary_literal"];
+
+const STYLE: &str = "
+:root { --bg: #fff; --fg: #1f2328; --muted: #656d76; --border: #d0d7de; --panel: #f6f8fa;
+  --kw: #cf222e; --str: #0a3069; --com: #6e7781; --num: #0550ae; --ty: #8250df; }
+@media (prefers-color-scheme: dark) {
+  :root { --bg: #0d1117; --fg: #e6edf3; --muted: #8d96a0; --border: #30363d; --panel: #161b22;
+    --kw: #ff7b72; --str: #a5d6ff; --com: #8b949e; --num: #79c0ff; --ty: #d2a8ff; }
+}
+body { margin: 0; display: flex; font: 14px/1.5 system-ui, sans-serif; background: var(--bg); color: var(--fg); }
+nav { position: sticky; top: 0; height: 100vh; overflow: auto; min-width: 260px; padding: 12px; box-sizing: border-box;
+  background: var(--panel); border-right: 1px solid var(--border); }
+nav details { margin-left: 12px; }
+nav > details { margin-left: 0; }
+nav a { display: flex; justify-content: space-between; gap: 12px; margin-left: 12px; color: inherit; text-decoration: none; }
+nav a:hover { text-decoration: underline; }
+main { flex: 1; min-width: 0; padding: 12px 24px; }
+h1 { font-size: 18px; margin: 0 0 4px; }
+.summary, .tok { color: var(--muted); }
+section > details { border: 1px solid var(--border); border-radius: 6px; margin: 12px 0; }
+section summary { display: flex; justify-content: space-between; padding: 6px 12px; background: var(--panel); cursor: pointer; }
+pre { margin: 0; padding: 12px; overflow: auto; font: 12px/1.45 ui-monospace, monospace; }
+.prompt { border: 1px dashed var(--border); border-radius: 6px; white-space: pre-wrap; }
+.kw { color: var(--kw); } .str { color: var(--str); } .com { color: var(--com); font-style: italic; }
+.num { color: var(--num); } .ty { color: var(--ty); }
+";
+
+/// One file of the page
+pub struct HtmlFile {
+    pub path: String,
+    pub ext: String,
+    pub tokens: usize,
+    pub content: String,
+}
+
+/// Render the whole page; `prefix`/`suffix` are shown as prompt text around the files
+pub fn render(title: &str, files: &[HtmlFile], prefix: Option<&str>, suffix: Option<&str>) -> String {
+    let total: usize = files.iter().map(|f| f.tokens).sum();
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escape(title), STYLE));
+    out.push_str(&format!("<nav>\n{}</nav>\n<main>\n", tree(files)));
+    out.push_str(&format!(
+        "<h1>{}</h1>\n<div class=\"summary\">{} file(s), {} tokens</div>\n",
+        escape(title),
+        files.len(),
+        total
+    ));
+    if let Some(text) = prefix {
+        out.push_str(&format!("<pre class=\"prompt\">{}</pre>\n", escape(text.trim_end())));
+    }
+    out.push_str("<section>\n");
+    for (i, file) in files.iter().enumerate() {
+        out.push_str(&format!(
+            "<details open id=\"f{}\"><summary><span>{}</span><span class=\"tok\">{} tokens</span></summary><pre><code>{}</code></pre></details>\n",
+            i,
+            escape(&file.path),
+            file.tokens,
+            highlight(&file.content, &file.ext)
+        ));
+    }
+    out.push_str("</section>\n");
+    if let Some(text) = suffix {
+        out.push_str(&format!("<pre class=\"prompt\">{}</pre>\n", escape(text.trim_end())));
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+/// Nested `<details>` per directory, with a link and token count per file
+fn tree(files: &[HtmlFile]) -> String {
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by(|&a, &b| files[a].path.cmp(&files[b].path));
+
+    let mut out = String::new();
+    let mut open: Vec<&str> = Vec::new();
+    for i in order {
+        let parts: Vec<&str> = files[i].path.split('/').collect();
+        let (name, dirs) = parts.split_last().unwrap();
+        let common = open.iter().zip(dirs).take_while(|(a, b)| a == b).count();
+        for _ in common..open.len() {
+            out.push_str("</details>\n");
+        }
+        open.truncate(common);
+        for dir in &dirs[common..] {
+            out.push_str(&format!("<details open><summary>{}</summary>\n", escape(dir)));
+            open.push(dir);
+        }
+        out.push_str(&format!(
+            "<a href=\"#f{}\"><span>{}</span><span class=\"tok\">{}</span></a>\n",
+            i,
+            escape(name),
+            files[i].tokens
+        ));
+    }
+    for _ in 0..open.len() {
+        out.push_str("</details>\n");
+    }
+    out
+}
+
+/// Escaped HTML for `content`, with tree-sitter highlighting where a grammar is available
+fn highlight(content: &str, ext: &str) -> String {
+    let Some(tree) = skeleton::parse(content, ext) else {
+        return escape(content);
+    };
+    let mut spans = Vec::new();
+    collect_spans(tree.root_node(), &mut spans);
+
+    let mut out = String::with_capacity(content.len() * 2);
+    let mut pos = 0;
+    for (start, end, class) in spans {
+        if start < pos {
+            continue;
+        }
+        out.push_str(&escape(&content[pos..start]));
+        out.push_str(&format!("<span class=\"{}\">{}</span>", class, escape(&content[start..end])));
+        pos = end;
+    }
+    out.push_str(&escape(&content[pos..]));
+    out
+}
+
+/// Byte ranges and CSS classes of comments, strings, keywords, numbers, and type names, in source order
+fn collect_spans(node: Node, out: &mut Vec<(usize, usize, &'static str)>) {
+    let kind = node.kind();
+    let class = if kind.contains("comment") {
+        Some("com")
+    } else if node.is_named() && (kind.contains("string") || matches!(kind, "char_literal" | "rune_literal")) {
+        Some("str")
+    } else if NUMBER_KINDS.contains(&kind) {
+        Some("num")
+    } else if kind == "type_identifier" || kind == "primitive_type" {
+        Some("ty")
+    } else if !node.is_named() && node.child_count() == 0 && is_word(kind) {
+        Some("kw")
+    } else {
+        None
+    };
+    if let Some(class) = class {
+        if node.end_byte() > node.start_byte() {
+            out.push((node.start_byte(), node.end_byte(), class));
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_spans(child, out);
+    }
+}
+
+/// Anonymous tokens like `fn` or `else_if` are keywords; `_` and punctuation are not
+fn is_word(kind: &str) -> bool {
+    kind.starts_with(|c: char| c.is_ascii_alphabetic()) && kind.chars().all(|c| c.is_ascii_alphabetic() || c == '_')
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,123 @@
+//! images.rs
+//! Stands in for image files with a one-line placeholder giving their path, size, and dimensions.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::progress::human_bytes;
+
+/// Bytes read when looking for dimensions; JPEG frame headers can follow large metadata segments
+const HEADER_BYTES: u64 = 256 * 1024;
+
+/// Extensions of the image formats replaced by a placeholder
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "svg", "tif", "tiff", "avif", "heic"];
+
+/// Whether `path` is an image, judged by its extension
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// `[image: PATH, SIZE, WxH]`, leaving out the dimensions when they can't be read cheaply
+pub fn placeholder(path: &Path, display: &str) -> io::Result<String> {
+    let mut header = Vec::new();
+    let size = File::open(path)?.metadata()?.len();
+    File::open(path)?.take(HEADER_BYTES).read_to_end(&mut header)?;
+    let dimensions = match dimensions(&header) {
+        Some((width, height)) => format!(", {}x{}", width, height),
+        None => String::new(),
+    };
+    let size = if size < 1024 { format!("{} B", size) } else { human_bytes(size as f64) };
+    Ok(format!("[image: {}, {}{}]", display, size, dimensions))
+}
+
+/// Width and height from the start of an image file
+fn dimensions(bytes: &[u8]) -> Option<(String, String)> {
+    let le16 = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32);
+    let le24 = |at: usize| bytes.get(at..at + 3).map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]));
+    let be32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let le32 = |at: usize| bytes.get(at..at + 4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let pair = |w: u32, h: u32| Some((w.to_string(), h.to_string()));
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return pair(be32(16)?, be32(20)?);
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return pair(le16(6)?, le16(8)?);
+    }
+    if bytes.starts_with(b"BM") {
+        // Bottom-up bitmaps have a negative height
+        return pair(le32(18)?.unsigned_abs(), le32(22)?.unsigned_abs());
+    }
+    if bytes.starts_with(&[0, 0, 1, 0]) {
+        // The first icon in the directory; 0 means 256
+        let side = |b: u8| if b == 0 { 256 } else { b as u32 };
+        return pair(side(*bytes.get(6)?), side(*bytes.get(7)?));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return match bytes.get(12..16)? {
+            b"VP8 " => pair(le16(26)? & 0x3FFF, le16(28)? & 0x3FFF),
+            b"VP8L" => {
+                let b = bytes.get(21..25)?;
+                let width = 1 + (((b[1] as u32 & 0x3F) << 8) | b[0] as u32);
+                let height = 1 + (((b[3] as u32 & 0x0F) << 10) | ((b[2] as u32) << 2) | ((b[1] as u32 & 0xC0) >> 6));
+                pair(width, height)
+            }
+            b"VP8X" => pair(1 + le24(24)?, 1 + le24(27)?),
+            _ => None,
+        };
+    }
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        return jpeg_dimensions(bytes).and_then(|(w, h)| pair(w, h));
+    }
+    svg_dimensions(&String::from_utf8_lossy(bytes))
+}
+
+/// Dimensions from the first start-of-frame segment of a JPEG
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32);
+    let mut i = 2;
+    while i + 1 < bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        match marker {
+            // Fill byte before a marker
+            0xFF => i += 1,
+            // Markers without a length
+            0x01 | 0xD0..=0xD9 => i += 2,
+            // Start of frame, except DHT, JPG, and DAC, which share the range
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => return Some((be16(i + 7)?, be16(i + 5)?)),
+            _ => i += 2 + be16(i + 2)? as usize,
+        }
+    }
+    None
+}
+
+/// The `width` and `height` of an `<svg>` element, or the size of its `viewBox`
+fn svg_dimensions(text: &str) -> Option<(String, String)> {
+    let start = text.find("<svg")?;
+    let tag = &text[start..start + text[start..].find('>')?];
+    let attr = |name: &str| {
+        let at = tag.match_indices(name).find(|(i, _)| tag[..*i].ends_with(char::is_whitespace))?.0 + name.len();
+        let rest = tag[at..].trim_start().strip_prefix('=')?.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &rest[1..];
+        Some(value[..value.find(quote)?].trim().to_string())
+    };
+    let size = |value: String| value.strip_suffix("px").map_or(value.clone(), str::to_string);
+    match (attr("width"), attr("height")) {
+        (Some(w), Some(h)) if !w.ends_with('%') && !h.ends_with('%') => Some((size(w), size(h))),
+        _ => {
+            let view_box = attr("viewBox")?;
+            let parts: Vec<&str> = view_box.split(|c: char| c.is_whitespace() || c == ',').filter(|s| !s.is_empty()).collect();
+            match parts.as_slice() {
+                [_, _, w, h] => Some((w.to_string(), h.to_string())),
+                _ => None,
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! interrupt.rs
+//! Ctrl-C handling: the first interrupt stops the run after the current file, a second one exits immediately.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Exit code used when a run is cut short by Ctrl-C (128 + SIGINT)
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Install the Ctrl-C handler
+pub fn install() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+    })
+}
+
+/// Whether Ctrl-C has been pressed
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
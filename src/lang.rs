@@ -0,0 +1,126 @@
+//! lang.rs
+//! Language names and their extensions, for structured output and `[python]`-style filter aliases.
+//! Extensionless files are recognized by well-known names and shebang lines.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read when looking for a shebang line
+const SHEBANG_BYTES: u64 = 256;
+
+/// Languages and the extensions they cover; the first entry listing an extension names it
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("python", &["py", "pyi"]),
+    ("javascript", &["js", "mjs", "cjs", "jsx"]),
+    ("typescript", &["ts", "mts", "cts", "tsx"]),
+    ("go", &["go"]),
+    ("c", &["c", "h"]),
+    ("cpp", &["cc", "cpp", "cxx", "h", "hh", "hpp", "hxx"]),
+    ("csharp", &["cs"]),
+    ("java", &["java"]),
+    ("kotlin", &["kt", "kts"]),
+    ("swift", &["swift"]),
+    ("scala", &["scala"]),
+    ("ruby", &["rb"]),
+    ("php", &["php"]),
+    ("shell", &["sh", "bash", "zsh"]),
+    ("sql", &["sql"]),
+    ("html", &["html", "htm"]),
+    ("css", &["css"]),
+    ("scss", &["scss"]),
+    ("markdown", &["md", "markdown"]),
+    ("json", &["json"]),
+    ("toml", &["toml"]),
+    ("yaml", &["yaml", "yml"]),
+    ("xml", &["xml"]),
+    ("jupyter", &["ipynb"]),
+    ("lua", &["lua"]),
+    ("haskell", &["hs"]),
+    ("elixir", &["ex", "exs"]),
+    ("dart", &["dart"]),
+    ("protobuf", &["proto"]),
+    ("perl", &["pl", "pm"]),
+    ("groovy", &["groovy", "gradle"]),
+    ("make", &["mk", "mak"]),
+    ("dockerfile", &["dockerfile"]),
+];
+
+/// Extensionless files known by name
+const FILE_NAMES: &[(&str, &str)] = &[
+    ("Dockerfile", "dockerfile"),
+    ("Containerfile", "dockerfile"),
+    ("Makefile", "make"),
+    ("GNUmakefile", "make"),
+    ("makefile", "make"),
+    ("Rakefile", "ruby"),
+    ("Gemfile", "ruby"),
+    ("Vagrantfile", "ruby"),
+    ("Jenkinsfile", "groovy"),
+];
+
+/// Shebang interpreters, matched on the program name without a version suffix
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("node", "javascript"),
+    ("deno", "typescript"),
+    ("bun", "javascript"),
+    ("ts-node", "typescript"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("dash", "shell"),
+    ("ksh", "shell"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("lua", "lua"),
+];
+
+/// Other names accepted for a language in filters
+const ALIASES: &[(&str, &str)] = &[("golang", "go"), ("c++", "cpp"), ("cs", "csharp"), ("bash", "shell"), ("yml", "yaml")];
+
+/// Language name for a file, from its extension, or for extensionless files its name or shebang
+pub fn language(path: &Path) -> Option<&'static str> {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some(ext) => {
+            let ext = ext.to_ascii_lowercase();
+            LANGUAGES.iter().find(|(_, exts)| exts.contains(&ext.as_str())).map(|(name, _)| *name)
+        }
+        None => detect(path),
+    }
+}
+
+/// Language of an extensionless file from its well-known name or `#!` line
+pub fn detect(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?;
+    if let Some((_, lang)) = FILE_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(lang);
+    }
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(SHEBANG_BYTES).read_to_end(&mut head).ok()?;
+    let line = String::from_utf8_lossy(&head);
+    let line = line.strip_prefix("#!")?.lines().next()?;
+
+    // `#!/usr/bin/env -S python3 -u` names the program after env and its flags
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS.iter().find(|(p, _)| *p == program).map(|(_, lang)| *lang)
+}
+
+/// Extensions of a language, for matching detected files against extension filters
+pub fn language_extensions(lang: &str) -> &'static [&'static str] {
+    LANGUAGES.iter().find(|(name, _)| *name == lang).map_or(&[], |(_, exts)| *exts)
+}
+
+/// Extensions for a language name or alias (case-insensitive), e.g. `python` -> `py`, `pyi`
+pub fn extensions(name: &str) -> Option<&'static [&'static str]> {
+    let name = name.to_ascii_lowercase();
+    let name = ALIASES.iter().find(|(alias, _)| *alias == name).map_or(name.as_str(), |(_, lang)| lang);
+    LANGUAGES.iter().find(|(lang, _)| *lang == name).map(|(_, exts)| *exts)
+}
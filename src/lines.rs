@@ -0,0 +1,110 @@
+//! lines.rs
+//! `path:START-END` targets that include only a range of a file's lines.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A 1-based, inclusive line range; `end: None` runs to the end of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.end {
+            Some(end) if end == self.start => write!(f, "{}", self.start),
+            Some(end) => write!(f, "{}-{}", self.start, end),
+            None => write!(f, "{}-", self.start),
+        }
+    }
+}
+
+/// A command-line target and the line range it was given, if any
+pub type Target = (PathBuf, Option<LineRange>);
+
+/// Split `path:120-480`, `path:120-`, or `path:120` into the path and its range.
+/// A target that exists as written is never split, so paths containing `:` still work.
+pub fn split_target(target: &str) -> Result<Target, String> {
+    let Some((path, spec)) = target.rsplit_once(':') else {
+        return Ok((PathBuf::from(target), None));
+    };
+    if Path::new(target).exists() || path.is_empty() || !spec.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok((PathBuf::from(target), None));
+    }
+    let invalid = || format!("invalid line range '{}' (expected START-END, START-, or LINE)", spec);
+    let (start, end) = match spec.split_once('-') {
+        Some((start, "")) => (start.parse().map_err(|_| invalid())?, None),
+        Some((start, end)) => (start.parse().map_err(|_| invalid())?, Some(end.parse().map_err(|_| invalid())?)),
+        None => {
+            let line = spec.parse().map_err(|_| invalid())?;
+            (line, Some(line))
+        }
+    };
+    if start == 0 || end.is_some_and(|end| end < start) {
+        return Err(invalid());
+    }
+    Ok((PathBuf::from(path), Some(LineRange { start, end })))
+}
+
+/// Render ranges for a block attribute, e.g. "120-480,600-"
+pub fn describe(ranges: &[LineRange]) -> String {
+    ranges.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// Keep only the lines inside `ranges`; gaps between ranges are marked with `...`
+pub fn select(content: &str, ranges: &[LineRange]) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    for (start, end, gap) in plan(ranges) {
+        if gap {
+            out.push(GAP);
+        }
+        out.extend(content.lines().skip(start - 1).take(end.saturating_sub(start).saturating_add(1)));
+    }
+    out.join("\n")
+}
+
+/// Prefix each line with its 1-based number, right-aligned; for ranged files the
+/// numbers follow the original file and the `...` gap markers are left unnumbered
+pub fn number(content: &str, ranges: Option<&[LineRange]>) -> String {
+    let numbers: Box<dyn Iterator<Item = Option<usize>>> = match ranges {
+        Some(ranges) => Box::new(
+            plan(ranges)
+                .into_iter()
+                .flat_map(|(start, end, gap)| gap.then_some(None).into_iter().chain((start..=end).map(Some))),
+        ),
+        None => Box::new((1..).map(Some)),
+    };
+    let numbered: Vec<(Option<usize>, &str)> = numbers.zip(content.lines()).collect();
+    let width = numbered.iter().filter_map(|(n, _)| *n).max().unwrap_or(1).to_string().len();
+    numbered
+        .into_iter()
+        .map(|(n, line)| match n {
+            Some(n) => format!("{:>width$} | {}", n, line, width = width),
+            None => format!("{:>width$} | {}", "", line, width = width),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Marker placed between non-adjacent ranges of one file
+const GAP: &str = "...";
+
+/// Sorted, non-overlapping `(start, end, gap_before)` spans covering `ranges`
+fn plan(ranges: &[LineRange]) -> Vec<(usize, usize, bool)> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort();
+    let mut spans = Vec::new();
+    let mut next = 1;
+    for range in ranges {
+        let end = range.end.unwrap_or(usize::MAX);
+        if end < next {
+            continue;
+        }
+        let start = range.start.max(next);
+        spans.push((start, end, start > next && next > 1));
+        next = end.saturating_add(1);
+    }
+    spans
+}
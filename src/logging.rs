@@ -1,12 +1,14 @@
 
 //! logging.rs
-//! Simple logging wrapper with levels and optional file output.
+//! Simple logging wrapper with levels and optional file output, forwarding every message to `tracing` for `--log-file`.
 
 use std::fs::OpenOptions;
-use std::io::Write;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 use colored::Colorize;
+use tracing_subscriber::fmt::format::FmtSpan;
 
 #[derive(Debug, Clone, Copy)]
 pub enum LogLevel {
@@ -39,6 +41,14 @@ impl Logger {
 
     /// Log a message with a given level
     pub fn log(&self, lvl: LogLevel, msg: &str) {
+        // The trace keeps every message, whatever the console level
+        match lvl {
+            LogLevel::Error => tracing::error!("{}", msg),
+            LogLevel::Warn => tracing::warn!("{}", msg),
+            LogLevel::Info => tracing::info!("{}", msg),
+            LogLevel::Debug => tracing::debug!("{}", msg),
+            LogLevel::Trace => tracing::trace!("{}", msg),
+        }
         if (lvl as u8) > (self.level as u8) {
             return; // Skip messages below current level
         }
@@ -68,8 +78,9 @@ impl Logger {
 
         let plain_line = format!("[{}] [{}] {}\n", ts, level_plain, msg);
 
-        // Print to stderr with colors
-        eprint!("{} {} {}\n", ts_colored, level_colored, msg);
+        // Print to stderr with colors, clearing any in-place progress bar first
+        let clear = if std::io::stderr().is_terminal() { "\r\x1b[2K" } else { "" };
+        eprintln!("{}{} {} {}", clear, ts_colored, level_colored, msg);
 
         // Write to file if configured (plain)
         if let Some(f) = &self.file {
@@ -85,3 +96,18 @@ impl Logger {
     pub fn debug(&self, msg: &str) { self.log(LogLevel::Debug, msg); }
     pub fn trace(&self, msg: &str) { self.log(LogLevel::Trace, msg); }
 }
+
+/// Append a plain-text trace of the run to `path`: debug events and, with `detailed`, trace events,
+/// each under the spans it happened in, plus a line with the busy time of every closed span
+pub fn trace_to_file(path: &Path, detailed: bool) -> io::Result<()> {
+    let file = OpenOptions::new().append(true).create(true).open(path)?;
+    let level = if detailed { tracing::Level::TRACE } else { tracing::Level::DEBUG };
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_target(false)
+        .with_max_level(level)
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+        .map_err(io::Error::other)
+}
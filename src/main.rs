@@ -1,185 +1,674 @@
-use std::collections::{HashSet, VecDeque};
-use std::fs::{self, File};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::io::Write;
+use std::sync::Arc;
 
-use arboard::Clipboard;
-use clap::Parser;
-use ignore::WalkBuilder;
-use once_cell::sync::Lazy;
-use regex::Regex;
+use clap::{CommandFactory, Parser};
 
+mod cli;
 mod metadata;
 mod tree;
 mod telemetry;
 mod logging;
 mod expandable;
 mod furnace;
+mod truncate;
+mod comments;
+mod redact;
+mod git;
+mod source;
+mod walk;
+mod filter;
+mod progress;
+mod interrupt;
+mod cache;
+mod manifest;
+mod unpack;
+mod model;
+mod rank;
+mod pack;
+mod skeleton;
+mod api;
+mod lines;
+mod classify;
+mod encoding;
+mod normalize;
+mod template;
+mod lang;
+mod chunk;
+mod html;
+mod exit;
+mod error;
+mod diagnose;
+mod order;
+mod deps;
+mod output;
+mod share;
+mod clipboard;
+mod serve;
+mod notebook;
+mod compare;
+mod workspace;
+mod plugins;
+mod summarize;
+mod documents;
+mod tabular;
+mod images;
+mod anonymize;
+mod scrub;
+mod bench;
+mod timeout;
+mod prescan;
+mod config;
+mod compdb;
+mod tsconfig;
+mod barrel;
 
-use metadata::{collect_metadata, FileMetadata};
+use cache::{mtime_ns, TokenCache};
+use cli::{AuthorShare, Cli, Command, OutputFormat, PackArgs};
+use deps::DepReport;
+use output::WriteMode;
+use error::{BoundError, ErrorFormat};
+use html::HtmlFile;
+use filter::LanguageFilter;
+use progress::{Progress, ProgressMode};
+use metadata::{bundle_checksum, collect_metadata, hash_file, sha256_hex, FileMetadata};
 use tree::generate_tree;
-use telemetry::Telemetry;
+use telemetry::{SkipReason, Telemetry};
 use logging::{Logger, LogLevel};
-use expandable::wrap_expandable;
+use manifest::{Manifest, ManifestEntry, PreviousBundle};
+use encoding::Encoding;
+use lines::LineRange;
+use model::Tokenizer;
+use notebook::NotebookMode;
+use order::LastChanged;
+use pack::{Candidate, DirBudget, PackStrategy};
+use expandable::{wrap_expandable, ExpandableBlock};
 use furnace::{analyze_file, FurnaceReport};
+use truncate::{apply_limits, read_limited, LimitedRead, TruncationNotice};
+use comments::{extract_docs, strip_comments};
+use compare::Comparison;
+use workspace::Workspace;
+use plugins::{FilterError, Plugins};
+use config::Config;
+use compdb::CompileDb;
+use tsconfig::TsPaths;
+use summarize::Summarizer;
+use documents::DocumentFormat;
+use anonymize::Anonymizer;
+use scrub::Scrubber;
+use redact::redact_secrets;
 use serde::Serialize;
 
+/// Runs past either size ask for confirmation first (`--yes` skips the question)
+const LARGE_RUN_FILES: usize = 10_000;
+const LARGE_RUN_BYTES: u64 = 256 << 20;
+
+/// What a run produces once files are selected
+#[derive(PartialEq)]
+enum Mode {
+    Pack,
+    Count,
+    Query { query: String, top_k: usize },
+    Rdeps { file: PathBuf },
+    Compare { old: PathBuf, new: PathBuf },
+}
+
+/// What a run hands back instead of printing, writing, or copying it, when `bound serve` runs it in-process
+#[derive(Default)]
+struct Captured {
+    /// What the run would have printed on stdout (`--list`, `count`)
+    stdout: Vec<u8>,
+    bundle: String,
+    /// Totals of the run's `run_complete` progress event
+    stats: serde_json::Value,
+}
+
+/// The project config and the files it and the command line place: `--always` ahead of the rest, `--first`/`--last` pinned
+struct Placement {
+    config: Config,
+    always: Vec<PathBuf>,
+    first: Vec<PathBuf>,
+    last: Vec<PathBuf>,
+}
+
+/// Where the bundle goes, settled before any file is read so bad output options fail early
+struct Delivery {
+    /// GitHub token and repository name for `--out gist`
+    gist: Option<(String, String)>,
+    out_path: Option<PathBuf>,
+    write_mode: WriteMode,
+    /// Whether a manifest is written next to the bundle (`--manifest`, `--incremental`)
+    write_manifest: bool,
+}
+
 #[derive(Serialize)]
 struct OutputJson {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
     tree: Option<String>,
     files: Vec<FileJson>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
 }
 
+/// One line of `--format jsonl` output
 #[derive(Serialize)]
-struct FileJson {
-    metadata: Option<FileMetadata>,
-    content: Option<String>,
-    furnace_report: Option<FurnaceReport>,
+struct JsonlRecord {
+    path: String,
+    language: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunk: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_line: Option<usize>,
+    tokens: usize,
+    hash: String,
+    content: String,
 }
 
-static REF_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
-    vec![
-        Regex::new(r"(?m)^\s*import\s+([a-zA-Z0-9_\.]+)").unwrap(),
-        Regex::new(r"(?m)^\s*from\s+([a-zA-Z0-9_\.]+)\s+import").unwrap(),
-        Regex::new(r#"require\(['"](.+?)['"]\)"#).unwrap(),
-        Regex::new(r#"(?m)^\s*import\s+.*\s+from\s+['"](.+?)['"]"#).unwrap(),
-        Regex::new(r#"(?m)^\s*#include\s*["<](.+?)["<]"#).unwrap(),
-    ]
-});
-
-#[derive(Parser, Debug)]
-#[command(version, about)]
-struct Args {
-    /// Language filter [.ext] or {.ext}
-    #[arg()]
-    filter: Option<String>,
-
-    /// Target directory
-    #[arg(default_value = ".")]
-    directory: PathBuf,
-
-    /// Token limit per file
-    #[arg(short = 't', long)]
-    token_limit: Option<usize>,
-
-    /// Size limit per file (bytes)
-    #[arg(short = 's', long)]
-    size_limit: Option<usize>,
-
-    /// Depth limit
-    #[arg(short = 'd', long)]
-    depth_limit: Option<usize>,
-
-    /// Output file (if not given, clipboard)
-    #[arg(long)]
-    out: Option<PathBuf>,
-
-    /// Include metadata headers
-    #[arg(long)]
-    meta: bool,
-
-    /// Include SHA-256 hash in metadata
-    #[arg(long)]
-    meta_hash: bool,
+#[derive(Serialize)]
+struct ListEntry {
+    path: String,
+    tokens: usize,
+}
 
-    /// Include file tree
-    #[arg(long)]
-    tree: bool,
+#[derive(Serialize)]
+struct CountGroup {
+    name: String,
+    files: usize,
+    tokens: usize,
+}
 
-    /// Enable Furnace analysis
-    #[arg(long)]
-    furnace: bool,
+#[derive(Serialize)]
+struct CountSummary {
+    total_tokens: usize,
+    files: usize,
+    by_directory: Vec<CountGroup>,
+    by_extension: Vec<CountGroup>,
+}
 
-    /// Output JSON format
-    #[arg(long)]
-    json: bool,
+#[derive(Serialize)]
+struct FileJson {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grep_matches: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lines: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_of: Option<String>,
+    /// Token count of the file a summary replaced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summarized_from: Option<usize>,
+    metadata: Option<FileMetadata>,
+    content: Option<String>,
+    truncation: Option<TruncationNotice>,
+    furnace_report: Option<FurnaceReport>,
 }
 
-fn parse_filter(filter: Option<&str>) -> Result<(Option<String>, bool), String> {
-    match filter {
-        None => Ok((None, false)),
-        Some(f) if f.starts_with('[') && f.ends_with(']') => {
-            let ext = f[1..f.len()-1].trim_start_matches('.').to_string();
-            Ok((Some(ext), false))
+fn main() {
+    // clap reports usage errors with code 2, which is reserved for budget overflows
+    let cli = Cli::try_parse().unwrap_or_else(|e| {
+        if !e.use_stderr() {
+            let _ = e.print();
+            std::process::exit(0);
         }
-        Some(f) if f.starts_with('{') && f.ends_with('}') => {
-            let ext = f[1..f.len()-1].trim_start_matches('.').to_string();
-            Ok((Some(ext), true))
+        match ErrorFormat::from_args() {
+            ErrorFormat::Text => {
+                let _ = e.print();
+                std::process::exit(exit::FAILURE);
+            }
+            ErrorFormat::Json => {
+                // The first line of clap's report, without its `error: ` prefix and usage block
+                let rendered = e.to_string();
+                let line = rendered.lines().next().unwrap_or_default();
+                let message = line.strip_prefix("error: ").unwrap_or(line).to_string();
+                std::process::exit(error::report(&BoundError::Usage(message), ErrorFormat::Json));
+            }
         }
-        Some(f) => Err(format!("Invalid filter format: '{}'", f)),
+    });
+    let error_format = cli.error_format;
+    if let Err(e) = run(cli, None) {
+        std::process::exit(error::report(e.as_ref(), error_format));
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
-    let (filter_ext, dep_aware) = parse_filter(args.filter.as_deref())?;
+fn run(cli: Cli, capture: Option<&mut Captured>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut hunk_context = None;
+    let (args, diff_range, mode) = match cli.command {
+        Some(Command::Diff { range, hunks_only, context, pack }) => {
+            hunk_context = hunks_only.then_some(context);
+            (pack, Some(range), Mode::Pack)
+        }
+        Some(Command::Count { pack }) => (pack, None, Mode::Count),
+        Some(Command::Query { query, top_k, pack }) => (pack, None, Mode::Query { query, top_k }),
+        Some(Command::Rdeps { file, pack }) => (pack, None, Mode::Rdeps { file }),
+        Some(Command::Compare { old, new, pack }) => (pack, None, Mode::Compare { old, new }),
+        Some(Command::Serve { http, .. }) => {
+            let logger = Logger::new(LogLevel::Info, None);
+            // clap requires exactly one of --mcp and --http
+            match http {
+                Some(addr) => serve::http(&addr, &logger)?,
+                None => serve::mcp(&logger)?,
+            }
+            return Ok(());
+        }
+        Some(Command::Bench { files, file_size, runs, dir, options }) => {
+            let logger = Logger::new(LogLevel::Info, None);
+            let config = bench::BenchConfig { files, file_size, runs, dir, options };
+            bench::run(&config, &logger)?;
+            return Ok(());
+        }
+        Some(Command::Completions { shell }) => {
+            // Profile names are read from the config nearest the current directory when the script is generated
+            let profiles = std::env::current_dir()
+                .ok()
+                .and_then(|dir| Config::load(&dir, None, None).ok())
+                .map(|(config, _)| config.profile_names())
+                .unwrap_or_default();
+            clap_complete::generate(shell, &mut cli::complete_profiles(Cli::command(), &profiles), "bound", &mut std::io::stdout());
+            return Ok(());
+        }
+        Some(Command::ClipboardServe { primary }) => {
+            clipboard::serve(primary)?;
+            return Ok(());
+        }
+        Some(Command::Unpack { bundle, dest, force }) => {
+            let logger = Logger::new(LogLevel::Info, None);
+            let written = unpack::run(&bundle, &dest, force, &logger)?;
+            logger.info(&format!("Unpacked {} file(s) into {}", written, dest.display()));
+            return Ok(());
+        }
+        None => (cli.pack, None, Mode::Pack),
+    };
+    let (filter, mut targets) = args.filter_and_targets().map_err(BoundError::Usage)?;
+    if let Mode::Compare { old, new } = &mode {
+        if args.targets.len() > usize::from(filter.is_some()) {
+            return Err(BoundError::Usage("bound compare takes only a filter after OLD and NEW".to_string()).into());
+        }
+        if args.incremental || args.files_from.is_some() {
+            return Err(BoundError::Usage("bound compare can't be combined with --incremental or --files-from".to_string()).into());
+        }
+        targets = vec![(old.clone(), None), (new.clone(), None)];
+    }
+    let output_format = args.output_format();
+    let structured = output_format != OutputFormat::Text;
+    if let (Some(since), Some(until)) = (args.since, args.until) {
+        if since >= until {
+            return Err(BoundError::Usage("--since must be earlier than --until".to_string()).into());
+        }
+    }
+    if let Some(max_tokens) = args.chunk_tokens {
+        if output_format != OutputFormat::Jsonl {
+            return Err(BoundError::Usage("--chunk-tokens requires --format jsonl".to_string()).into());
+        }
+        if max_tokens == 0 || args.chunk_overlap >= max_tokens {
+            return Err(BoundError::Usage("--chunk-tokens must be positive and larger than --chunk-overlap".to_string()).into());
+        }
+    }
+    if args.append && matches!(output_format, OutputFormat::Json | OutputFormat::Html) {
+        return Err(BoundError::Usage(format!("--append can't add to a {} document; use text or jsonl output", output_format.as_str())).into());
+    }
+    let to_gist = args.out.as_deref() == Some(Path::new("gist"));
+    if to_gist && (args.force || args.append || args.compress.is_some() || args.manifest || args.incremental) {
+        return Err(BoundError::Usage("--out gist uploads the bundle and can't be combined with --force, --append, --compress, --manifest, or --incremental".to_string()).into());
+    }
+    // Look the token up before doing the work
+    let gist_token = match to_gist {
+        true => Some(share::github_token().ok_or_else(|| BoundError::Usage("--out gist needs a GitHub token in $GITHUB_TOKEN or $GH_TOKEN (or `gh auth login`)".to_string()))?),
+        false => None,
+    };
+    if let Some(header) = args.post_headers.iter().find(|h| !h.contains(':')) {
+        return Err(BoundError::Usage(format!("--post-header {:?} should look like \"Name: value\"", header)).into());
+    }
+    if args.compress.is_some() && args.out.is_none() && !args.out_auto {
+        return Err(BoundError::Usage("--compress needs --out or --out-auto; the clipboard takes plain text".to_string()).into());
+    }
+    let write_mode = if args.append {
+        WriteMode::Append
+    } else if args.force || args.incremental {
+        WriteMode::Overwrite
+    } else {
+        WriteMode::CreateNew
+    };
+    if let (Some(out), WriteMode::CreateNew, false) = (&args.out, write_mode, to_gist) {
+        // Fail before doing the work rather than after
+        if let Some(notice) = output::clobber_notice(out) {
+            return Err(BoundError::Output(notice).into());
+        }
+    }
+    let mut language_filter = LanguageFilter::parse(filter).map_err(BoundError::Filter)?;
+    language_filter.exclude_extensions(&args.exclude_ext);
+    language_filter.detect_lang = args.detect_lang;
     let mut telemetry = Telemetry::new();
-    let root_dir = fs::canonicalize(&args.directory)?;
+    let tokenizer = args.model.map_or(Tokenizer::Whitespace, |m| m.tokenizer());
+    let budget = args.budget.or(args.model.map(|m| m.default_budget()));
 
-    let logger = Logger::new(LogLevel::Info, None);
+    let log_level = match (args.quiet, args.verbose) {
+        (true, _) => LogLevel::Error,
+        (false, 0) => LogLevel::Info,
+        (false, 1) => LogLevel::Debug,
+        (false, _) => LogLevel::Trace,
+    };
+    let logger = Logger::new(log_level, None);
+    if let Some(path) = &args.log_file {
+        logging::trace_to_file(path, args.verbose > 1).map_err(|e| BoundError::Output(format!("Cannot open {}: {}", path.display(), e)))?;
+    }
+    // In-process runs (`bound serve`) leave Ctrl-C to the server
+    if capture.is_none() {
+        if let Err(e) = interrupt::install() {
+            logger.warn(&format!("Failed to install Ctrl-C handler: {}", e));
+        }
+    }
+    if args.clipboard_chunks == Some(0) {
+        return Err(BoundError::Usage("--clipboard-chunks must be positive".to_string()).into());
+    }
+    if args.primary && (args.out.is_some() || args.out_auto || args.post.is_some()) {
+        logger.warn("--primary has no effect unless output goes to the clipboard");
+    }
+    if args.deps_report.is_some() && !language_filter.dep_aware {
+        logger.warn("--deps-report has no effect without a {ext} filter");
+    }
+    if args.deps_cross_lang && !language_filter.dep_aware {
+        logger.warn("--deps-cross-lang has no effect without a {ext} filter");
+    }
+    if args.compile_commands.is_some() && !language_filter.dep_aware {
+        logger.warn("--compile-commands has no effect without a {ext} filter");
+    }
+    if args.flatten_barrels && !language_filter.dep_aware {
+        logger.warn("--flatten-barrels has no effect without a {ext} filter");
+    }
+    if !args.packages.is_empty() {
+        let [(root, None)] = targets.as_slice() else {
+            return Err(BoundError::Usage("--package takes the workspace root as its only path".to_string()).into());
+        };
+        let workspace = Workspace::load(root)
+            .and_then(|w| w.ok_or_else(|| format!("--package needs a workspace manifest in {}", root.display())))
+            .map_err(BoundError::Traversal)?;
+        let selected = workspace.select(&args.packages).map_err(BoundError::Usage)?;
+        let names: Vec<&str> = selected.iter().map(|m| m.name.as_str()).collect();
+        logger.info(&format!("Packing package(s) {}", names.join(", ")));
+        targets = workspace.manifests.iter().chain(selected.iter().map(|m| &m.dir)).map(|p| (p.clone(), None)).collect();
+    }
+    let mut sources = Vec::new();
+    let mut line_ranges: BTreeMap<PathBuf, Vec<LineRange>> = BTreeMap::new();
+    for (target, range) in &targets {
+        if source::is_remote(&target.to_string_lossy()) {
+            logger.info(&format!("Cloning {}", target.display()));
+        }
+        let source = source::acquire(target).map_err(|e| BoundError::Traversal(format!("Cannot open {}: {}", target.display(), e)))?;
+        if let Some(range) = range {
+            if source.path.is_dir() {
+                return Err(BoundError::Usage(format!("Line range given for directory {}", target.display())).into());
+            }
+            line_ranges.entry(source.path.clone()).or_default().push(*range);
+        }
+        sources.push(source);
+    }
+    let target_paths: Vec<PathBuf> = sources.iter().map(|s| s.path.clone()).collect();
+    let comparison = match &mode {
+        Mode::Compare { .. } => Some(Comparison::new(target_paths[0].clone(), target_paths[1].clone()).map_err(BoundError::Usage)?),
+        _ => None,
+    };
+    let root_dir = walk::common_root(&target_paths);
+    let Placement { config, always, first, last } = load_config(&args, &root_dir, comparison.is_some(), &logger)?;
+    let delivery = Delivery {
+        gist: gist_token.map(|token| (token, repo_name(&targets, &root_dir))),
+        out_path: match args.out_auto {
+            true => Some(output::auto_path(&repo_name(&targets, &root_dir), output_format, args.compress)?),
+            false => args.out.clone().filter(|_| !to_gist),
+        },
+        write_mode,
+        write_manifest: args.manifest || args.incremental,
+    };
     logger.info(&format!("Scanning directory: {}", root_dir.display()));
 
     // --- Build file list ---
-    let mut walker = WalkBuilder::new(&root_dir);
-    walker.add_custom_ignore_filename(".boundignore");
-    if let Some(dl) = args.depth_limit {
-        walker.max_depth(Some(dl));
-    }
-    let all_files: Vec<PathBuf> = walker.build()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().map_or(false, |ft| ft.is_file()))
-        .map(|e| e.into_path())
-        .collect();
-
-    let mut files_to_process = HashSet::new();
-    let mut files_to_scan_deps = VecDeque::new();
-
-    // --- Language filter ---
-    if let Some(ext) = &filter_ext {
-        for path in &all_files {
-            if path.extension().and_then(|s| s.to_str()) == Some(ext) {
-                files_to_process.insert(path.clone());
-                if dep_aware {
-                    files_to_scan_deps.push_back(path.clone());
+    let mut skipped = Vec::new();
+    let (mut files, dep_report) = match &args.files_from {
+        Some(list) => (walk::read_file_list(list)?, DepReport::default()),
+        None => discover_files(&args, &target_paths, &root_dir, &language_filter, diff_range.as_deref(), &mut skipped, &logger)?,
+    };
+    // Listed files can name FIFOs or devices too, and hard links or bind mounts reach one file by several paths
+    // Snapshots being compared may share hard-linked files (`cp -al`), which must stay on both sides
+    let inode_dedup = !args.no_inode_dedup && !matches!(mode, Mode::Compare { .. });
+    let mut inodes = HashSet::new();
+    files.retain(|path| {
+        let Ok(meta) = fs::metadata(path) else {
+            return true;
+        };
+        let reason = if walk::is_special(meta.file_type()) {
+            Some(SkipReason::Special)
+        } else if inode_dedup && walk::file_id(&meta).is_some_and(|id| !inodes.insert(id)) {
+            Some(SkipReason::HardLink)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            skipped.push((path.clone(), reason));
+        }
+        reason.is_none()
+    });
+    if let (Some(context), Some(range)) = (hunk_context, &diff_range) {
+        let mut hunks = HashMap::new();
+        for target in target_paths.iter().filter(|t| t.is_dir()) {
+            hunks.extend(git::changed_hunks(target, range)?);
+        }
+        let mut count = 0;
+        for path in &files {
+            // Files with explicit line ranges keep them; those without text hunks stay whole
+            let Some(spans) = hunks.get(path).filter(|_| !line_ranges.contains_key(path)) else {
+                continue;
+            };
+            let mut ranges: Vec<LineRange> = Vec::new();
+            for &(start, lines) in spans {
+                let (start, end) = (start.saturating_sub(context).max(1), start + lines.saturating_sub(1) + context);
+                // Hunks come in order; merge those whose context touches
+                match ranges.last_mut() {
+                    Some(last) if last.end.is_some_and(|e| start <= e + 1) => last.end = Some(end),
+                    _ => ranges.push(LineRange { start, end: Some(end) }),
                 }
             }
+            count += spans.len();
+            line_ranges.insert(path.clone(), ranges);
         }
+        logger.info(&format!("Keeping {} hunk(s) with {} line(s) of context", count, context));
+    }
+    if let Mode::Rdeps { file } = &mode {
+        let target = walk::canonicalize(file).map_err(|e| format!("Cannot read {}: {}", file.display(), e))?;
+        let dependents = deps::importers(&target, &files, &root_dir);
+        logger.info(&format!("{} file(s) depend on {}", dependents.len(), file.display()));
+        files = std::iter::once(target).chain(dependents).collect();
+    }
+    if let Some(comparison) = &comparison {
+        files = comparison.changed_files(files, &mut skipped);
+        logger.info(&format!("{} file(s) differ between the snapshots", files.len()));
+    }
+    if let Some(order) = args.order {
+        logger.debug(&format!("Ordering files by {}", order.as_str()));
+        order::sort(&mut files, &root_dir, order);
+    }
+    // --always files are set aside here and put back at the front once the rest has been chosen
+    files.retain(|path| !always.contains(path));
+    skipped.retain(|(path, _)| !always.contains(path));
+    record_skips(&skipped, &mut telemetry, &root_dir, &logger);
+    let mut unreadable: Vec<(PathBuf, String)> = skipped
+        .iter()
+        .filter(|(_, reason)| *reason == SkipReason::Unreadable)
+        .map(|(path, _)| (path.clone(), "could not be listed".to_string()))
+        .collect();
+    if args.strict {
+        if let Some((path, _)) = unreadable.first() {
+            return Err(BoundError::Read(format!("Cannot read {}", path.display())).into());
+        }
+    }
+
+    let plugins = Plugins::new(&root_dir, &args.filter_cmds, args.plugins.as_deref())?.with_timeout(args.file_timeout);
+    let plugin_names = plugins.wasm_names();
+    if !plugin_names.is_empty() {
+        logger.info(&format!("Loaded plugin(s) {}", plugin_names.join(", ")));
+    }
+    let summarizer = args.summarize_cmd.as_deref().zip(args.summarize_over).map(|(cmd, over)| Summarizer::new(cmd, over, !args.no_cache));
+
+    let mut cache = if args.no_cache {
+        TokenCache::disabled(tokenizer)
     } else {
-        files_to_process.extend(all_files.iter().cloned());
+        TokenCache::load(&root_dir, tokenizer)
     }
+    .with_notebook(args.notebook)
+    .with_csv_rows(args.csv_rows)
+    .with_image_placeholders(!args.no_image_placeholders)
+    .with_summary_cap(args.summarize_over);
 
-    // --- Resolve dependencies ---
-    if dep_aware {
-        let mut visited = HashSet::new();
-        while let Some(path) = files_to_scan_deps.pop_front() {
-            if !visited.insert(path.clone()) { continue; }
-            for r in parse_references_generic(&path)? {
-                let candidate = resolve_ref_path(&path, &r, &root_dir);
-                if candidate.exists() && !files_to_process.contains(&candidate) {
-                    files_to_process.insert(candidate.clone());
-                    files_to_scan_deps.push_back(candidate);
-                }
+    // --- Relevance and budget packing ---
+    let mut relevance = HashMap::new();
+    if let Mode::Query { query, .. } = &mode {
+        let ranked = rank::rank(&files, &root_dir, query);
+        logger.info(&format!("Query matched {} of {} files", ranked.len(), files.len()));
+        files = Vec::new();
+        for (path, score) in ranked {
+            logger.debug(&format!("{} scored {:.2}", path.strip_prefix(&root_dir).unwrap_or(&path).display(), score));
+            files.push(path.clone());
+            relevance.insert(path, score);
+        }
+    }
+    // Query mode always fits its picks to the budget; otherwise only when --pack is given
+    let strategy = match mode {
+        Mode::Query { .. } => Some(args.pack.unwrap_or(PackStrategy::Priority)),
+        _ => args.pack,
+    };
+    if !args.dir_budgets.is_empty() {
+        let strategy = strategy.unwrap_or(PackStrategy::Priority);
+        files = pack_to_dir_budgets(files, &root_dir, &relevance, &args.dir_budgets, strategy, &mut cache, &logger);
+    }
+    match (strategy, budget) {
+        (Some(strategy), Some(budget)) => {
+            // --always files have first claim on the budget; the rest is packed into what they leave
+            let reserved: usize = budget_candidates(always.clone(), &root_dir, &relevance, &mut cache).iter().map(|c| c.tokens).sum();
+            if reserved > 0 {
+                logger.debug(&format!("--always files take {} of the {}-token budget", reserved, budget));
             }
+            files = pack_to_budget(files, &root_dir, &relevance, budget.saturating_sub(reserved), strategy, &mut cache, &logger)
         }
+        (Some(_), None) if args.pack.is_some() && args.dir_budgets.is_empty() => {
+            logger.warn("--pack has no effect without --budget, --dir-budget, or --model")
+        }
+        _ => {}
+    }
+    if strategy.is_some() || !args.dir_budgets.is_empty() {
+        if let Err(e) = cache.save() {
+            logger.warn(&format!("Failed to write token cache: {}", e));
+        }
+    }
+    if let Mode::Query { top_k, .. } = mode {
+        files.truncate(top_k);
+    }
+    files.splice(0..0, always);
+    if let Some(max) = args.max_files.filter(|&max| files.len() > max) {
+        logger.warn(&format!("Keeping the first {} of {} files (--max-files)", max, files.len()));
+        files.truncate(max);
+    }
+    for path in order::pin(&mut files, &first, &last) {
+        logger.warn(&format!("Pinned file {} is not in the bundle", path.display()));
     }
 
-    // --- Sort files for consistent output ---
-    let mut sorted_files: Vec<PathBuf> = files_to_process.into_iter().collect();
-    sorted_files.sort();
+    if files.is_empty() {
+        return Err(nothing_matched(&skipped, &args, filter, diff_range.as_deref(), &logger));
+    }
 
+    if args.list || mode == Mode::Count {
+        let entries = count_entries(&files, &root_dir, &mut cache, &logger);
+        let mut stdout = std::io::stdout().lock();
+        let out: &mut dyn Write = match capture {
+            Some(captured) => &mut captured.stdout,
+            None => &mut stdout,
+        };
+        if mode == Mode::Count {
+            print_counts(&entries, structured, out)?;
+        } else {
+            list_files(&entries, structured, out)?;
+        }
+        if let Err(e) = cache.save() {
+            logger.warn(&format!("Failed to write token cache: {}", e));
+        }
+        return Ok(());
+    }
+
+    let prefix = prompt_text(&args.prefix, &args.prefix_file)?;
+    let suffix = prompt_text(&args.suffix, &args.suffix_file)?;
     let mut aggregated = String::new();
-    let mut json_output = if args.json {
+    let mut jsonl_records = Vec::new();
+    let mut html_files = Vec::new();
+    let mut json_output = if output_format == OutputFormat::Json {
         Some(OutputJson {
+            prefix: prefix.clone(),
             tree: None,
             files: Vec::new(),
+            checksum: None,
+            suffix: suffix.clone(),
         })
     } else {
         None
     };
+    if let (Some(text), false) = (&prefix, structured) {
+        aggregated.push_str(text.trim_end());
+        aggregated.push_str("\n\n");
+    }
+
+    let relative_path = |path: &Path| -> PathBuf {
+        match &comparison {
+            Some(c) => c.relative(path),
+            None => path.strip_prefix(&root_dir).unwrap_or(path),
+        }
+        .to_path_buf()
+    };
+    let anonymizer = match args.anonymize {
+        true => {
+            let terms = match &args.anonymize_terms {
+                Some(file) => anonymize::read_terms(file)?,
+                None => Vec::new(),
+            };
+            let relative: Vec<PathBuf> = files.iter().map(|p| relative_path(p)).collect();
+            Some(Anonymizer::new(&relative, terms)?)
+        }
+        false => None,
+    };
+    let scrubber = match &args.scrub_file {
+        Some(file) => Scrubber::load(file)?,
+        None => None,
+    };
+    // Paths as the bundle shows them
+    let shown_path = |path: &Path| -> String {
+        let shown = match &anonymizer {
+            Some(a) => a.path(&relative_path(path)),
+            None => relative_path(path).to_string_lossy().to_string(),
+        };
+        match &scrubber {
+            Some(s) => s.scrub(&shown).0,
+            None => shown,
+        }
+    };
 
     // --- File tree ---
-    if args.tree && sorted_files.len() > 1 {
-        let tree_str = generate_tree(&root_dir, &sorted_files);
+    if args.tree && files.len() > 1 {
+        let tree_str = match &comparison {
+            // Rewritten paths are shown under a neutral root
+            _ if anonymizer.is_some() || scrubber.is_some() => {
+                let paths: Vec<PathBuf> = files.iter().map(|p| PathBuf::from(shown_path(p))).collect();
+                generate_tree(Path::new("."), &paths)
+            }
+            Some(c) => {
+                let paths: Vec<PathBuf> = files.iter().map(|p| c.new_root.join(c.relative(p))).collect();
+                generate_tree(&c.new_root, &paths)
+            }
+            None => generate_tree(&root_dir, &files),
+        };
         if let Some(ref mut j) = json_output {
             j.tree = Some(tree_str);
         } else {
@@ -188,12 +677,123 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // --- Incremental state ---
+    // A new commit can change any file's last-commit header without touching the file itself
+    let head = args.git_meta.then(|| git::head(&root_dir).ok()).flatten();
+    let options = format!(
+        "{:?}",
+        (
+            (&root_dir, args.token_limit, args.size_limit, args.truncate, args.strip_comments, args.skeleton, args.docs_only, args.api_only, args.no_redact, args.line_numbers, args.normalize, args.dedup),
+            (args.meta, args.meta_hash, args.git_meta, &head, args.furnace, args.hash, args.model),
+            config.limits(),
+            (args.grep.as_ref().map(|re| re.as_str()), &line_ranges, args.notebook, args.csv_rows, args.no_image_placeholders, &args.template, &args.filter_cmds, &args.plugins, args.summarize_over, &args.summarize_cmd, &args.scrub_file),
+        )
+    );
+    let previous = match (&delivery.out_path, args.incremental) {
+        (Some(out), true) => {
+            let previous = PreviousBundle::load(out, &options);
+            if previous.is_none() {
+                logger.info("No reusable previous bundle; building from scratch");
+            }
+            previous
+        }
+        _ => None,
+    };
+    let mut manifest = Manifest::new(options);
+    let mut reused = 0;
+    let mut first_seen: HashMap<String, String> = HashMap::new();
+    let mut undetermined: Vec<PathBuf> = Vec::new();
+    let mut file_hashes: Vec<(String, String)> = Vec::new();
+
     // --- Process files ---
-    let total_files = sorted_files.len();
-    for path in &sorted_files {
-        let meta = if args.meta {
+    let total_files = files.len();
+    // Pre-scan sizes so the ETA weighs files by bytes rather than counting them equally
+    let file_sizes = if args.estimate {
+        let (sizes, dirs) = prescan::estimate(&files);
+        logger.debug(&format!("Estimated sizes from {} sampled director(ies)", dirs));
+        sizes
+    } else {
+        prescan::sizes(&files)
+    };
+    telemetry.total_bytes = file_sizes.iter().sum();
+    if !args.yes {
+        confirm_large_run(total_files, telemetry.total_bytes, &logger)?;
+    }
+    // Reads and transforms under --file-timeout run on worker threads, which need their own handle on the options
+    let shared_args = Arc::new(args.clone());
+    let mut progress = Progress::new(if args.quiet { ProgressMode::None } else { args.progress });
+    for (index, path) in files.iter().enumerate() {
+        if interrupt::requested() {
+            break;
+        }
+        // Counted up front so skipped files also advance the estimate
+        telemetry.bytes_done += file_sizes[index];
+        let display_path = shown_path(path);
+        let _file_span = tracing::debug_span!("file", path = %display_path).entered();
+        progress.file_started(&display_path, index, total_files);
+        logger.trace(&format!("Processing {}", display_path));
+
+        let stat = match fs::metadata(path) {
+            Ok(m) => m,
+            Err(e) => {
+                if args.strict {
+                    return Err(BoundError::Read(format!("Cannot read {}: {}", path.display(), e)).into());
+                }
+                logger.warn(&format!("Skipping {} ({}): {}", path.display(), SkipReason::Unreadable, e));
+                unreadable.push((path.clone(), e.to_string()));
+                skipped.push((path.clone(), SkipReason::Unreadable));
+                telemetry.record_skip(SkipReason::Unreadable);
+                continue;
+            }
+        };
+        let file_bytes = stat.len();
+        if let Some(max) = args.max_file_size.filter(|&max| file_bytes > max) {
+            logger.warn(&format!(
+                "Skipping {} ({}): {} bytes exceeds --max-file-size {}",
+                path.display(),
+                SkipReason::TooLarge,
+                file_bytes,
+                max
+            ));
+            skipped.push((path.clone(), SkipReason::TooLarge));
+            telemetry.record_skip(SkipReason::TooLarge);
+            continue;
+        }
+
+        let mtime = mtime_ns(&stat);
+        let reusable = previous
+            .as_ref()
+            .and_then(|p| p.section(&display_path, mtime, file_bytes))
+            // An earlier file may now claim this content, turning it into a duplicate
+            .filter(|(_, entry)| entry.dedup_key.as_ref().is_none_or(|k| !first_seen.contains_key(k)));
+        if let Some((section, entry)) = reusable {
+            if let Some(key) = &entry.dedup_key {
+                first_seen.insert(key.clone(), display_path.clone());
+            }
+            manifest.files.push(ManifestEntry { offset: aggregated.len(), ..entry.clone() });
+            aggregated.push_str(section);
+            if args.hash {
+                file_hashes.push((entry.sha256.clone(), display_path.clone()));
+            }
+            reused += 1;
+            telemetry.files_processed += 1;
+            telemetry.bytes_read += file_bytes as usize;
+            telemetry.tokens_aggregated += entry.tokens + entry.wrapper_tokens;
+            progress.file_done(&display_path, file_bytes as usize, entry.tokens, &telemetry, total_files, &logger);
+            continue;
+        }
+
+        let meta = if args.meta || args.git_meta {
             match collect_metadata(path, &root_dir, args.meta_hash) {
-                Ok(m) => Some(m),
+                Ok(mut m) => {
+                    if args.git_meta {
+                        match git::last_commit(&root_dir, path) {
+                            Ok(commit) => m.git = commit,
+                            Err(e) => logger.warn(&format!("Failed to read git history for {}: {}", path.display(), e)),
+                        }
+                    }
+                    Some(m)
+                }
                 Err(e) => {
                     logger.warn(&format!("Failed to collect metadata for {}: {}", path.display(), e));
                     None
@@ -203,45 +803,217 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             None
         };
 
-        let content = match fs::read_to_string(path) {
-            Ok(c) => c,
+        // Ranged files are read whole so line numbers stay exact; limits apply to the selected lines
+        let ranges = line_ranges.get(path);
+        let (token_limit, size_limit) = config.limits_for(path, args.token_limit, args.size_limit);
+        let read_limit = if ranges.is_some() { None } else { size_limit };
+        let read = {
+            let (path, display, args) = (path.clone(), display_path.clone(), Arc::clone(&shared_args));
+            timeout::run(args.file_timeout, move || read_file(&path, &display, file_bytes, read_limit, &args))
+        };
+        let Some(read) = read else {
+            skip_timed_out(path, "reading", &args, &mut skipped, &mut telemetry, &logger)?;
+            continue;
+        };
+        let read = match read {
+            Ok(r) => r,
             Err(e) => {
-                logger.warn(&format!("Skipping {}: {}", path.display(), e));
+                let reason = SkipReason::from_io_error(&e);
+                if reason == SkipReason::Unreadable {
+                    if args.strict {
+                        return Err(BoundError::Read(format!("Cannot read {}: {}", path.display(), e)).into());
+                    }
+                    unreadable.push((path.clone(), e.to_string()));
+                }
+                if reason == SkipReason::UnknownEncoding {
+                    undetermined.push(path.clone());
+                }
+                logger.warn(&format!("Skipping {} ({}): {}", path.display(), reason, e));
+                skipped.push((path.clone(), reason));
+                telemetry.record_skip(reason);
+                continue;
+            }
+        };
+        let LimitedRead { content, partial_of, encoding } = read;
+        if !args.no_image_placeholders && images::is_image(path) {
+            telemetry.image_placeholders += 1;
+        }
+        if encoding != Encoding::Utf8 {
+            logger.debug(&format!("Transcoded {} from {}", path.display(), encoding.as_str()));
+            telemetry.transcoded += 1;
+        }
+        let bytes_read = content.len();
+        // Filter commands are killed at --file-timeout by `run_filter` itself, so none outlives the run
+        let content = match plugins.apply(path, &display_path, &content) {
+            Ok(Some(filtered)) => filtered,
+            Ok(None) => content,
+            Err(FilterError::TimedOut) => {
+                skip_timed_out(path, "filtering", &args, &mut skipped, &mut telemetry, &logger)?;
                 continue;
             }
+            Err(FilterError::Failed(e)) if args.strict => return Err(BoundError::Read(e).into()),
+            Err(FilterError::Failed(e)) => {
+                logger.warn(&format!("{}; keeping the file unfiltered", e));
+                content
+            }
+        };
+        let grep_matches = args.grep.as_ref().map(|re| re.find_iter(&content).count());
+        let wants_hash = delivery.write_manifest
+            || args.hash
+            || output_format == OutputFormat::Jsonl
+            || args.template.as_ref().is_some_and(|t| t.uses("hash"));
+        let sha256 = if !wants_hash {
+            String::new()
+        } else if partial_of.is_some() || encoding != Encoding::Utf8 {
+            hash_file(path).unwrap_or_default()
+        } else {
+            sha256_hex(content.as_bytes())
+        };
+
+        let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+        let reshaped = {
+            let (ext, ranges, args) = (ext.to_string(), ranges.cloned(), Arc::clone(&shared_args));
+            timeout::run(args.file_timeout, move || reshape(content, &ext, ranges.as_ref(), &args))
+        };
+        let Some(content) = reshaped else {
+            skip_timed_out(path, "transforming", &args, &mut skipped, &mut telemetry, &logger)?;
+            continue;
+        };
+
+        // Both sides of a comparison are reshaped the same way before diffing
+        let content = match &comparison {
+            Some(c) if c.is_old(path) => compare::unified(&display_path, Some(&content), None),
+            Some(c) => {
+                let old = c.old_copy(path).and_then(|old| {
+                    let old_bytes = fs::metadata(&old).map_or(0, |m| m.len());
+                    match read_file(&old, &display_path, old_bytes, None, &args) {
+                        Ok(read) => {
+                            let filtered = plugins.apply(&old, &display_path, &read.content).ok().flatten();
+                            Some(reshape(filtered.unwrap_or(read.content), ext, None, &args))
+                        }
+                        Err(e) => {
+                            logger.warn(&format!("Showing {} as added; its old copy is unreadable: {}", display_path, e));
+                            None
+                        }
+                    }
+                });
+                compare::unified(&display_path, old.as_deref(), Some(&content))
+            }
+            None => content,
+        };
+
+        let content = if args.no_redact {
+            content
+        } else {
+            let (redacted, count) = redact_secrets(&content);
+            if count > 0 {
+                logger.warn(&format!("Redacted {} secret(s) in {}", count, path.display()));
+                telemetry.redactions += count;
+            }
+            redacted
+        };
+
+        let content = match &scrubber {
+            Some(s) => {
+                let (clean, count) = s.scrub(&content);
+                telemetry.scrubbed += count;
+                clean
+            }
+            None => content,
+        };
+
+        let content = match &anonymizer {
+            Some(a) => {
+                let (masked, count) = a.text(&content);
+                telemetry.anonymized += count;
+                masked
+            }
+            None => content,
+        };
+
+        // Summarize after redaction, scrubbing, and anonymization so neither secrets nor names reach the summarizer
+        let mut summarized_from = None;
+        let content = match summarizer.as_ref().map(|s| s.summarize(&display_path, &content, &tokenizer)) {
+            Some(Ok(Some(summary))) => {
+                let original = tokenizer.count(&content);
+                logger.debug(&format!("Summarized {} ({} tokens)", display_path, original));
+                telemetry.summarized += 1;
+                telemetry.summary_tokens_saved += original.saturating_sub(tokenizer.count(&summary));
+                summarized_from = Some(original);
+                summary
+            }
+            Some(Err(e)) if args.strict => return Err(BoundError::Read(e).into()),
+            Some(Err(e)) => {
+                logger.warn(&format!("{}; keeping the whole file", e));
+                content
+            }
+            Some(Ok(None)) | None => content,
+        };
+
+        // --- Deduplication ---
+        let mut duplicate_of = None;
+        let mut dedup_key = None;
+        let content = if args.dedup {
+            let key = sha256_hex(normalize::normalize(&content).as_bytes());
+            match first_seen.get(&key) {
+                Some(first) => {
+                    let stub = format!("identical to {}", first);
+                    telemetry.duplicates += 1;
+                    telemetry.dedup_tokens_saved += tokenizer.count(&content).saturating_sub(tokenizer.count(&stub));
+                    duplicate_of = Some(first.clone());
+                    stub
+                }
+                None => {
+                    first_seen.insert(key.clone(), display_path.clone());
+                    dedup_key = Some(key);
+                    content
+                }
+            }
+        } else {
+            content
+        };
+
+        let content = if args.line_numbers && duplicate_of.is_none() {
+            lines::number(&content, ranges.map(|r| r.as_slice()))
+        } else {
+            content
         };
         let mut file_block = String::new();
 
         if let Some(ref m) = meta {
             file_block.push_str(&wrap_expandable("metadata", &m.to_header()));
+            file_block.push('\n');
         }
 
         // Apply token/size limits
-        let mut processed_content = content.clone();
-        if let Some(tl) = args.token_limit {
-            processed_content = processed_content
-                .split_whitespace()
-                .take(tl)
-                .collect::<Vec<&str>>()
-                .join(" ");
-        }
-        if let Some(sl) = args.size_limit {
-            if processed_content.len() > sl {
-                processed_content.truncate(sl);
+        let (processed_content, truncation, content_tokens) =
+            apply_limits(&content, partial_of, token_limit, size_limit, args.truncate);
+        if let Some(ref t) = truncation {
+            if !structured {
+                file_block.push_str(&t.render());
+                file_block.push('\n');
             }
         }
 
-        let mut file_json = if args.json {
+        let mut file_json = if output_format == OutputFormat::Json {
             Some(FileJson {
+                path: display_path.clone(),
+                sha256: args.hash.then(|| sha256.clone()),
+                grep_matches,
+                lines: ranges.map(|r| lines::describe(r)),
+                duplicate_of: duplicate_of.clone(),
+                summarized_from,
                 metadata: meta.clone(),
                 content: Some(processed_content.clone()),
+                truncation: truncation.clone(),
                 furnace_report: None,
             })
         } else {
             None
         };
 
-        if !args.json {
+        let content_at = file_block.len();
+        if !structured {
             file_block.push_str(&processed_content);
             file_block.push_str("\n\n");
         }
@@ -259,82 +1031,1029 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        if let Some(j) = file_json {
+        // Tokens are counted on what the bundle holds: the text after redaction, reshaping, and truncation
+        let count_content = || match (tokenizer, &truncation) {
+            // The limits pass already counted whitespace tokens
+            (Tokenizer::Whitespace, None) => content_tokens,
+            (Tokenizer::Whitespace, Some(t)) => t.kept_tokens,
+            _ => tokenizer.count(&processed_content),
+        };
+        if args.hash {
+            file_hashes.push((sha256.clone(), display_path.clone()));
+        }
+        let tokens = if let (OutputFormat::Jsonl, Some(max_tokens)) = (output_format, args.chunk_tokens) {
+            let chunks = chunk::chunk(&processed_content, &tokenizer, max_tokens, args.chunk_overlap);
+            let tokens = chunks.iter().map(|c| c.tokens).sum();
+            for (i, c) in chunks.into_iter().enumerate() {
+                jsonl_records.push(JsonlRecord {
+                    path: display_path.clone(),
+                    language: lang::language(path),
+                    chunk: Some(i),
+                    start_line: Some(c.start_line),
+                    end_line: Some(c.end_line),
+                    tokens: c.tokens,
+                    hash: sha256.clone(),
+                    content: c.content,
+                });
+            }
+            tokens
+        } else if output_format == OutputFormat::Jsonl {
+            let tokens = count_content();
+            jsonl_records.push(JsonlRecord {
+                path: display_path.clone(),
+                language: lang::language(path),
+                chunk: None,
+                start_line: None,
+                end_line: None,
+                tokens,
+                hash: sha256,
+                content: processed_content,
+            });
+            tokens
+        } else if output_format == OutputFormat::Html {
+            let tokens = count_content();
+            html_files.push(HtmlFile {
+                path: display_path.clone(),
+                ext: ext.to_string(),
+                tokens,
+                content: processed_content,
+            });
+            tokens
+        } else if let Some(j) = file_json {
             json_output.as_mut().unwrap().files.push(j);
+            count_content()
         } else {
-            aggregated.push_str(&wrap_expandable("file", &file_block));
+            let tokens = count_content();
+            let render = |file_block: &str| match &args.template {
+                Some(template) => template.render(&[
+                    ("path", &display_path),
+                    ("ext", ext),
+                    ("size", &file_bytes.to_string()),
+                    ("tokens", &tokens.to_string()),
+                    ("hash", &sha256),
+                    ("truncated", if truncation.is_some() { "truncated" } else { "" }),
+                    ("content", file_block.trim_end_matches('\n')),
+                ]),
+                None => {
+                    let mut block = ExpandableBlock::new("file", file_block).add_attr("path", &display_path);
+                    if args.hash {
+                        block = block.add_attr("sha256", &sha256);
+                    }
+                    if let Some(n) = grep_matches {
+                        block = block.add_attr("grep_matches", &n.to_string());
+                    }
+                    if let Some(ranges) = ranges {
+                        block = block.add_attr("lines", &lines::describe(ranges));
+                    }
+                    if let Some(ref first) = duplicate_of {
+                        block = block.add_attr("duplicate_of", first);
+                    }
+                    if let Some(original) = summarized_from {
+                        block = block.add_attr("summarized_from_tokens", &original.to_string());
+                    }
+                    block.render()
+                }
+            };
+            let section = render(&file_block);
+            // The wrapper is counted on its own so the content keeps the count every format reports
+            let wrapper = [&file_block[..content_at], &file_block[content_at + processed_content.len()..]].concat();
+            let wrapper_tokens = tokenizer.count(&render(&wrapper));
+            telemetry.tokens_aggregated += wrapper_tokens;
+            manifest.files.push(ManifestEntry {
+                path: display_path.clone(),
+                mtime_ns: mtime,
+                size: file_bytes,
+                offset: aggregated.len(),
+                length: section.len(),
+                tokens,
+                wrapper_tokens,
+                sha256,
+                truncated: truncation.is_some(),
+                duplicate_of,
+                dedup_key,
+            });
+            aggregated.push_str(&section);
+            tokens
+        };
+        if let Some(format) = DocumentFormat::of(path) {
+            let (count, format_tokens) = telemetry.documents.entry(format).or_default();
+            *count += 1;
+            *format_tokens += tokens;
         }
 
         telemetry.files_processed += 1;
-        telemetry.bytes_read += content.len();
-        telemetry.tokens_aggregated += content.split_whitespace().count();
+        telemetry.bytes_read += bytes_read;
+        telemetry.tokens_aggregated += tokens;
 
-        if telemetry.files_processed % 10 == 0 || telemetry.files_processed == total_files {
-            logger.info(&telemetry.report(total_files));
+        progress.file_done(&display_path, bytes_read, tokens, &telemetry, total_files, &logger);
+    }
+    progress.finish(&telemetry, total_files, &logger);
+    report_totals(&telemetry, anonymizer.as_ref().map(Anonymizer::len), &undetermined, &unreadable, &root_dir, &logger);
+    let interrupted = interrupt::requested();
+    if telemetry.files_processed == 0 && !interrupted {
+        return Err(nothing_matched(&skipped, &args, filter, diff_range.as_deref(), &logger));
+    }
+    if interrupted {
+        logger.warn(&format!(
+            "Interrupted after {}/{} files; writing partial output",
+            telemetry.files_processed, total_files
+        ));
+    }
+
+    if args.hash {
+        let checksum = bundle_checksum(file_hashes.iter().map(|(h, p)| (h.as_str(), p.as_str())));
+        if let Some(ref mut j) = json_output {
+            j.checksum = Some(checksum);
+        } else {
+            aggregated.push_str(
+                &ExpandableBlock::new("checksum", "")
+                    .add_attr("sha256", &checksum)
+                    .add_attr("files", &file_hashes.len().to_string())
+                    .render(),
+            );
+        }
+    }
+
+    match output_format {
+        OutputFormat::Json => aggregated = serde_json::to_string_pretty(&json_output.unwrap())?,
+        OutputFormat::Jsonl => {
+            let lines = jsonl_records.iter().map(serde_json::to_string).collect::<Result<Vec<_>, _>>()?;
+            aggregated = lines.join("\n");
+        }
+        OutputFormat::Html => {
+            let title = match args.anonymize || scrubber.is_some() {
+                true => "bound".to_string(),
+                false => format!("bound: {}", root_dir.display()),
+            };
+            aggregated = html::render(&title, &html_files, prefix.as_deref(), suffix.as_deref());
+        }
+        OutputFormat::Text => {
+            if let Some(text) = &suffix {
+                aggregated.push_str("\n\n");
+                aggregated.push_str(text.trim_end());
+            }
         }
     }
 
-    if args.json {
-        aggregated = serde_json::to_string_pretty(&json_output.unwrap())?;
+    if let Some(budget) = budget {
+        check_budget(&aggregated, budget, tokenizer, &args, &logger)?;
     }
 
     // --- Output ---
-    if let Some(out_path) = args.out {
-        let mut f = File::create(&out_path)?;
-        writeln!(f, "{}", aggregated)?;
-        logger.info(&format!("Output written to {:?}", out_path));
-    } else {
-        let mut clipboard = Clipboard::new()?;
-        clipboard.set_text(aggregated)?;
-        logger.info("Output copied to clipboard.");
+    let _output_span = tracing::info_span!("output").entered();
+    match capture {
+        Some(captured) => {
+            captured.bundle = aggregated;
+            captured.stats = progress::summary(&telemetry);
+        }
+        None => {
+            write_output(aggregated, delivery, manifest, &args, &logger)?;
+            if args.incremental {
+                logger.info(&format!("Reused {}/{} unchanged file(s)", reused, total_files));
+            }
+        }
     }
 
+    if language_filter.dep_aware {
+        report_dependencies(&dep_report, &root_dir, args.deps_report.as_deref(), &logger)?;
+    }
+
+    if interrupted {
+        // Exit skips destructors, so remove any temporary clones first
+        drop(sources);
+        std::process::exit(interrupt::EXIT_INTERRUPTED);
+    }
     Ok(())
 }
 
-/// Parse references generically (Python, JS, C/C++)
-fn parse_references_generic(path: &Path) -> std::io::Result<Vec<String>> {
-    let content = fs::read_to_string(path)?;
-    let mut references = Vec::new();
-    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+/// Load the config (with `--profile` applied) for `root_dir` and resolve the `--always`, `--first`, and `--last` files
+fn load_config(args: &PackArgs, root_dir: &Path, comparing: bool, logger: &Logger) -> Result<Placement, BoundError> {
+    let (config, config_path) = Config::load(root_dir, args.config.as_deref(), args.profile.as_deref()).map_err(BoundError::Usage)?;
+    if let Some(path) = &config_path {
+        logger.debug(&format!("Using config {}", path.display()));
+    }
+    let mut always: Vec<PathBuf> = Vec::new();
+    let from_config = config.always().iter().map(|p| (p, true));
+    for (path, configured) in args.always.iter().map(|p| (p, false)).chain(from_config) {
+        let resolved = walk::canonicalize(path).map_err(|e| BoundError::Usage(format!("Cannot read --always {}: {}", path.display(), e)))?;
+        if !resolved.is_file() {
+            return Err(BoundError::Usage(format!("--always {} is not a file", path.display())));
+        }
+        if !resolved.starts_with(root_dir) {
+            // A config above the root may name files outside a narrower run; only a command-line path is worth a warning
+            let log = if configured { Logger::debug } else { Logger::warn };
+            log(logger, &format!("Ignoring --always {}: outside {}", path.display(), root_dir.display()));
+        } else if !always.contains(&resolved) {
+            always.push(resolved);
+        }
+    }
+    let (mut first, mut last) = (Vec::new(), Vec::new());
+    for (flag, paths, pinned) in [("--first", &args.first, &mut first), ("--last", &args.last, &mut last)] {
+        for path in paths {
+            let resolved = walk::canonicalize(path).map_err(|e| BoundError::Usage(format!("Cannot read {} {}: {}", flag, path.display(), e)))?;
+            if !pinned.contains(&resolved) {
+                pinned.push(resolved);
+            }
+        }
+    }
+    if let Some(path) = first.iter().find(|p| last.contains(p)) {
+        return Err(BoundError::Usage(format!("{} is pinned both --first and --last", path.display())));
+    }
+    if comparing && !always.is_empty() {
+        logger.warn("--always has no effect when comparing snapshots");
+        always.clear();
+    }
+    Ok(Placement { config, always, first, last })
+}
+
+/// Count the paths left out while selecting files, and point out the skips a flag would undo
+fn record_skips(skipped: &[(PathBuf, SkipReason)], telemetry: &mut Telemetry, root_dir: &Path, logger: &Logger) {
+    for (path, reason) in skipped {
+        logger.debug(&format!("Skipped {} ({})", path.strip_prefix(root_dir).unwrap_or(path).display(), reason));
+        telemetry.record_skip(*reason);
+    }
+    for (reason, what, flag) in [
+        (SkipReason::Lock, "lock", "--include-locks"),
+        (SkipReason::Vendored, "vendored", "--include-vendor"),
+        (SkipReason::Generated, "generated or minified", "--include-generated"),
+        (SkipReason::HardLink, "hard-linked", "--no-inode-dedup"),
+    ] {
+        let count = skipped.iter().filter(|(_, r)| *r == reason).count();
+        if count > 0 {
+            logger.info(&format!("Skipped {} {} file(s); use {} to keep them", count, what, flag));
+        }
+    }
+}
+
+/// Log what processing changed beyond packing files, and the files whose encoding or contents could not be read.
+/// `anonymized_paths` is the number of paths `--anonymize` renamed.
+fn report_totals(
+    telemetry: &Telemetry,
+    anonymized_paths: Option<usize>,
+    undetermined: &[PathBuf],
+    unreadable: &[(PathBuf, String)],
+    root_dir: &Path,
+    logger: &Logger,
+) {
+    if telemetry.transcoded > 0 {
+        logger.info(&format!("Transcoded {} non-UTF-8 file(s) to UTF-8", telemetry.transcoded));
+    }
+    if telemetry.scrubbed > 0 {
+        logger.info(&format!("Scrubbed {} deny-listed occurrence(s)", telemetry.scrubbed));
+    }
+    if let Some(paths) = anonymized_paths {
+        logger.info(&format!("Anonymized {} path(s) and {} mention(s) of paths or terms", paths, telemetry.anonymized));
+    }
+    if telemetry.image_placeholders > 0 {
+        logger.info(&format!("Listed {} image(s) as placeholders", telemetry.image_placeholders));
+    }
+    if !telemetry.documents.is_empty() {
+        let formats: Vec<String> = telemetry
+            .documents
+            .iter()
+            .map(|(format, (count, tokens))| format!("{} {} file(s), {} tokens", count, format.as_str(), tokens))
+            .collect();
+        logger.info(&format!("Extracted text from documents: {}", formats.join(", ")));
+    }
+    if !undetermined.is_empty() {
+        let mut summary = format!("Could not determine the encoding of {} file(s):", undetermined.len());
+        for path in undetermined {
+            summary.push_str(&format!("\n  {}", path.strip_prefix(root_dir).unwrap_or(path).display()));
+        }
+        logger.warn(&summary);
+    }
+    if !unreadable.is_empty() {
+        let mut summary = format!("Skipped {} unreadable path(s):", unreadable.len());
+        for (path, reason) in unreadable {
+            summary.push_str(&format!("\n  {}: {}", path.strip_prefix(root_dir).unwrap_or(path).display(), reason));
+        }
+        logger.warn(&summary);
+    }
+}
 
-    for re in REF_PATTERNS.iter() {
-        for cap in re.captures_iter(&content) {
-            if let Some(m) = cap.get(1) {
-                let mut r = m.as_str().to_string();
-                if ext == "py" || ext == "js" || ext == "ts" {
-                    r = r.replace('.', "/");
+/// Log the bundle's cost and how it compares to `budget`; going over fails the run under --fail-on-overflow or --strict
+fn check_budget(bundle: &str, budget: usize, tokenizer: Tokenizer, args: &PackArgs, logger: &Logger) -> Result<(), BoundError> {
+    let total = tokenizer.count(bundle);
+    if let Some(model) = args.model {
+        logger.info(&model.cost_summary(total));
+    }
+    let target = args.model.map(|m| format!(" for {}", m.as_str())).unwrap_or_default();
+    if total > budget {
+        let message = format!(
+            "OVER BUDGET: aggregation is {} tokens, {} over the {}-token budget{}",
+            total,
+            total - budget,
+            budget,
+            target
+        );
+        if args.fail_on_overflow || args.strict {
+            logger.error(&message);
+            return Err(BoundError::Budget("token budget exceeded".to_string()));
+        }
+        logger.warn(&message);
+    } else {
+        logger.info(&format!("Using {} of {} budget tokens{}", total, budget, target));
+    }
+    Ok(())
+}
+
+/// Send the finished bundle to a gist, an HTTP endpoint, a file (with its manifest), or the clipboard
+fn write_output(mut aggregated: String, delivery: Delivery, mut manifest: Manifest, args: &PackArgs, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
+    let output_format = args.output_format();
+    if let Some((token, repo)) = delivery.gist {
+        let file_name = format!("{}.{}", repo, output::extension(output_format));
+        let url = share::upload_gist(&token, &file_name, &format!("{} packed by bound", repo), &aggregated)
+            .map_err(|e| BoundError::Output(format!("Cannot upload gist: {}", e)))?;
+        logger.info(&format!("Output uploaded to secret gist {}", url));
+        println!("{}", url);
+    } else if let Some(url) = &args.post {
+        let mut headers = args.post_headers.clone();
+        if !headers.iter().any(|h| h.split(':').next().is_some_and(|name| name.trim().eq_ignore_ascii_case("content-type"))) {
+            headers.push(format!("Content-Type: {}", share::content_type(output_format)));
+        }
+        let (status, response) = share::post(url, &headers, aggregated.as_bytes())
+            .map_err(|e| BoundError::Output(format!("Cannot post to {}: {}", url, e)))?;
+        if !(200..300).contains(&status) {
+            let detail = response.trim();
+            let detail = if detail.is_empty() { String::new() } else { format!(": {}", detail) };
+            return Err(BoundError::Output(format!("{} answered HTTP {}{}", url, status, detail)).into());
+        }
+        logger.info(&format!("Output posted to {} (HTTP {}, {} bytes)", url, status, aggregated.len()));
+        if !response.is_empty() {
+            println!("{}", response.trim_end());
+        }
+    } else if let Some(out_path) = delivery.out_path {
+        let output_failed = |e: std::io::Error| BoundError::Output(format!("Cannot write {}: {}", out_path.display(), e));
+        let appending = delivery.write_mode == WriteMode::Append && fs::metadata(&out_path).is_ok_and(|m| m.len() > 0);
+        if appending && output_format == OutputFormat::Text {
+            aggregated.insert(0, '\n');
+        }
+        let written = output::write(&out_path, &aggregated, delivery.write_mode, args.compress).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => BoundError::Output(output::clobber_notice(&out_path).unwrap_or_default()),
+            _ => output_failed(e),
+        })?;
+        if appending {
+            logger.info(&format!("Output appended to {:?}", out_path));
+        } else {
+            logger.info(&format!("Output written to {:?}", out_path));
+        }
+        if let Some(codec) = args.compress {
+            // The bundle plus its trailing newline, as written before compression
+            let raw = aggregated.len() as u64 + 1;
+            logger.info(&format!(
+                "Compressed {} bytes to {} with {} ({:.1}x, {:.0}% saved)",
+                raw,
+                written,
+                codec.as_str(),
+                raw as f64 / written.max(1) as f64,
+                100.0 * (1.0 - written as f64 / raw as f64)
+            ));
+        }
+        if delivery.write_manifest {
+            manifest.bundle_bytes = aggregated.len();
+            let manifest_path = manifest::manifest_path(&out_path);
+            manifest
+                .save(&manifest_path)
+                .map_err(|e| BoundError::Output(format!("Cannot write {}: {}", manifest_path.display(), e)))?;
+        }
+    } else {
+        let parts = match args.clipboard_chunks {
+            Some(max) => clipboard::split(&aggregated, max as usize),
+            None => vec![aggregated.as_str()],
+        };
+        if parts.len() == 1 {
+            if copy_to_clipboard(&aggregated, &aggregated, args, output_format, logger)? {
+                logger.info("Output copied to clipboard.");
+            }
+        } else {
+            let stdin = std::io::stdin();
+            for (i, part) in parts.iter().enumerate() {
+                if i > 0 {
+                    eprint!("Press Enter to copy part {}/{}...", i + 1, parts.len());
+                    let mut line = String::new();
+                    if stdin.read_line(&mut line)? == 0 {
+                        return Err(BoundError::Output(format!("Stopped after part {}/{}", i, parts.len())).into());
+                    }
+                }
+                if copy_to_clipboard(part, &aggregated, args, output_format, logger)? {
+                    logger.info(&format!("Part {}/{} ({} bytes) copied to clipboard.", i + 1, parts.len(), part.len()));
                 }
-                if !r.contains('.') { r = format!("{}.{}", r, ext); }
-                references.push(r);
             }
         }
     }
-    Ok(references)
+    Ok(())
 }
 
-/// Resolve reference path relative to source and root
-fn resolve_ref_path(source: &Path, ref_str: &str, root: &Path) -> PathBuf {
-    let base_dir = source.parent().unwrap_or(root);
-    let mut candidate = base_dir.join(ref_str);
+/// Read a file for packing: an image placeholder, or its text limited by `read_limited`
+fn read_file(path: &Path, display: &str, file_bytes: u64, size_limit: Option<usize>, args: &PackArgs) -> std::io::Result<LimitedRead> {
+    if !args.no_image_placeholders && images::is_image(path) {
+        let content = images::placeholder(path, display)?;
+        return Ok(LimitedRead { content, partial_of: None, encoding: Encoding::Utf8 });
+    }
+    read_limited(path, file_bytes, size_limit, args.truncate)
+}
 
-    if let Ok(canon) = fs::canonicalize(&candidate) {
-        candidate = canon;
+/// Skip a file whose read or transform ran past `--file-timeout`, or fail the run with `--strict`
+fn skip_timed_out(
+    path: &Path,
+    stage: &str,
+    args: &PackArgs,
+    skipped: &mut Vec<(PathBuf, SkipReason)>,
+    telemetry: &mut Telemetry,
+    logger: &Logger,
+) -> Result<(), BoundError> {
+    let limit = args.file_timeout.unwrap_or_default().as_secs_f64();
+    if args.strict {
+        return Err(BoundError::Read(format!("Timed out {} {} after {}s", stage, path.display(), limit)));
+    }
+    logger.warn(&format!("Skipping {} ({}): {} took longer than {}s", path.display(), SkipReason::TimedOut, stage, limit));
+    skipped.push((path.to_path_buf(), SkipReason::TimedOut));
+    telemetry.record_skip(SkipReason::TimedOut);
+    Ok(())
+}
+
+/// Apply the options that reshape a file's text: notebook extraction, line ranges or row previews, normalization,
+/// and comment stripping or skeleton, docs, or API reduction
+fn reshape(content: String, ext: &str, ranges: Option<&Vec<LineRange>>, args: &PackArgs) -> String {
+    let content = if notebook::is_notebook(ext) {
+        notebook::extract(&content, args.notebook).unwrap_or(content)
     } else {
-        let mut comps = Vec::new();
-        for comp in candidate.components() {
-            match comp {
-                std::path::Component::Normal(c) => comps.push(c),
-                std::path::Component::ParentDir => { comps.pop(); },
-                _ => {}
-            }
+        content
+    };
+
+    // Explicit line ranges take precedence over a row preview
+    let content = match (ranges, args.csv_rows.zip(tabular::delimiter(ext))) {
+        (Some(ranges), _) => lines::select(&content, ranges),
+        (None, Some((rows, delimiter))) => tabular::preview(&content, delimiter, rows),
+        (None, None) => content,
+    };
+
+    let content = if args.normalize {
+        normalize::normalize(&content)
+    } else {
+        content
+    };
+
+    let content = if args.strip_comments {
+        strip_comments(&content, ext)
+    } else {
+        content
+    };
+
+    if args.skeleton {
+        skeleton::skeletonize(&content, ext).unwrap_or(content)
+    } else if args.docs_only {
+        extract_docs(&content, ext)
+    } else if args.api_only && ext == "rs" {
+        api::public_api(&content).unwrap_or(content)
+    } else {
+        content
+    }
+}
+
+/// Copy `text` to the clipboard and read it back, returning whether it arrived intact.
+/// When it didn't, `bundle` is saved to a temp file so the output isn't lost.
+fn copy_to_clipboard(text: &str, bundle: &str, args: &PackArgs, format: OutputFormat, logger: &Logger) -> Result<bool, BoundError> {
+    let save_fallback = || output::save_clipboard_fallback(bundle, format);
+    let how = clipboard::copy(text, args.clipboard_mode, args.primary).map_err(|e| {
+        let saved = save_fallback().map_or_else(|_| String::new(), |path| format!("; the bundle is in {}", path.display()));
+        BoundError::Clipboard(format!("Cannot set clipboard: {}{}", e, saved))
+    })?;
+    logger.debug(&format!("Clipboard set {}", how));
+    let Err(problem) = clipboard::verify(text) else {
+        return Ok(true);
+    };
+    match save_fallback() {
+        Ok(path) => logger.warn(&format!("Clipboard copy is incomplete ({}); the full bundle is in {}", problem, path.display())),
+        Err(e) => logger.warn(&format!("Clipboard copy is incomplete ({}) and saving it failed: {}", problem, e)),
+    }
+    Ok(false)
+}
+
+/// Name for `--out-auto` files and gists: the cloned repository, or the git repository enclosing the root
+fn repo_name(targets: &[(PathBuf, Option<LineRange>)], root_dir: &Path) -> String {
+    let name = match targets {
+        [(target, _)] if source::is_remote(&target.to_string_lossy()) => {
+            source::remote_name(&target.to_string_lossy()).map(str::to_string)
+        }
+        // Name local runs after the enclosing repository, not a subdirectory being packed
+        _ => git::toplevel(root_dir)
+            .unwrap_or_else(|_| root_dir.to_path_buf())
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned()),
+    };
+    name.unwrap_or_else(|| "root".to_string())
+}
+
+/// Summarize what `{ext}` resolution followed, warn about unresolved references and cycles,
+/// and write the JSON report when asked
+fn report_dependencies(
+    report: &DepReport,
+    root_dir: &Path,
+    json_path: Option<&Path>,
+    logger: &Logger,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let display = |p: &Path| p.strip_prefix(root_dir).unwrap_or(p).display().to_string();
+    logger.info(&format!("Followed {} reference(s)", report.followed()));
+    if !report.unresolved.is_empty() {
+        logger.warn(&format!(
+            "{} reference(s) could not be resolved (-v lists them{})",
+            report.unresolved.len(),
+            if json_path.is_some() { "" } else { ", --deps-report writes them as JSON" }
+        ));
+        for (file, reference) in &report.unresolved {
+            logger.debug(&format!("Unresolved: {} -> {}", display(file), reference));
         }
-        candidate = root.join(comps.iter().collect::<PathBuf>());
     }
+    for cycle in report.cycles() {
+        let chain: Vec<String> = cycle.iter().map(|p| display(p)).collect();
+        logger.warn(&format!("Reference cycle: {}", chain.join(" -> ")));
+    }
+    if let Some(path) = json_path {
+        fs::write(path, report.to_json(root_dir)? + "\n")
+            .map_err(|e| BoundError::Output(format!("Cannot write {}: {}", path.display(), e)))?;
+        logger.info(&format!("Dependency report written to {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Log why the selection came up empty and build the matching exit error
+fn nothing_matched(
+    skipped: &[(PathBuf, SkipReason)],
+    args: &PackArgs,
+    filter: Option<&str>,
+    diff_range: Option<&str>,
+    logger: &Logger,
+) -> Box<dyn std::error::Error> {
+    let mut summary = "Nothing matched:".to_string();
+    for line in diagnose::nothing_matched(skipped, args, filter, diff_range) {
+        summary.push_str(&format!("\n  {}", line));
+    }
+    logger.warn(&summary);
+    BoundError::NoMatch("no files matched".to_string()).into()
+}
 
-    if candidate.strip_prefix(root).is_ok() {
-        candidate
+/// Ask before aggregating more than `LARGE_RUN_FILES` files or `LARGE_RUN_BYTES` bytes.
+/// Without a terminal to ask on, warn and carry on.
+fn confirm_large_run(files: usize, bytes: u64, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
+    if files <= LARGE_RUN_FILES && bytes <= LARGE_RUN_BYTES {
+        return Ok(());
+    }
+    let what = format!("{} files ({})", thousands(files), progress::human_bytes(bytes as f64));
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        logger.warn(&format!("Aggregating {}; pass --max-files or narrow the paths if that's more than intended", what));
+        return Ok(());
+    }
+    eprint!("About to aggregate {}, continue? [y/N] ", what);
+    let mut answer = String::new();
+    // Declining is a usage outcome: the same run with --yes or --max-files goes through
+    let aborted = || BoundError::Usage("Aborted; pass --yes to skip this question or --max-files to cap the run".to_string());
+    std::io::stdin().read_line(&mut answer).map_err(|_| aborted())?;
+    if matches!(answer.trim(), "y" | "Y" | "yes" | "Yes") {
+        Ok(())
     } else {
-        root.join(ref_str)
+        Err(aborted().into())
+    }
+}
+
+/// `48000` as `48,000`
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Budget candidates for `files`, with token counts served from the cache
+fn budget_candidates(files: Vec<PathBuf>, root_dir: &Path, relevance: &HashMap<PathBuf, f64>, cache: &mut TokenCache) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for path in files {
+        // Include the block wrapper so the packed bundle, not just the contents, fits
+        let display = path.strip_prefix(root_dir).unwrap_or(&path).to_string_lossy().to_string();
+        let overhead = cache.tokenizer().count(&ExpandableBlock::new("file", "").add_attr("path", &display).render());
+        let Ok(tokens) = cache.budget_tokens(&path).map(|t| t + overhead) else {
+            // Unreadable files cost nothing; they are skipped with a warning later
+            candidates.push(Candidate { path, tokens: 0, priority: 0.0 });
+            continue;
+        };
+        let score = relevance.get(&path).copied().unwrap_or(0.0);
+        let priority = pack::priority(&path, root_dir, tokens, score);
+        candidates.push(Candidate { path, tokens, priority });
+    }
+    candidates
+}
+
+/// Drop files that don't fit the budget using `strategy`, keeping the current order
+fn pack_to_budget(
+    files: Vec<PathBuf>,
+    root_dir: &Path,
+    relevance: &HashMap<PathBuf, f64>,
+    budget: usize,
+    strategy: PackStrategy,
+    cache: &mut TokenCache,
+    logger: &Logger,
+) -> Vec<PathBuf> {
+    let candidates = budget_candidates(files, root_dir, relevance, cache);
+    let total: usize = candidates.iter().map(|c| c.tokens).sum();
+    let count = candidates.len();
+    if total <= budget {
+        return candidates.into_iter().map(|c| c.path).collect();
+    }
+    let selected = pack::select(candidates, budget, strategy);
+    logger.info(&format!(
+        "Packed {} of {} files into the {}-token budget ({} strategy)",
+        selected.len(),
+        count,
+        budget,
+        strategy.as_str()
+    ));
+    selected
+}
+
+/// Fit the files under each `--dir-budget` directory to its budget, keeping the current order.
+/// Files outside every budgeted directory are left alone.
+fn pack_to_dir_budgets(
+    files: Vec<PathBuf>,
+    root_dir: &Path,
+    relevance: &HashMap<PathBuf, f64>,
+    budgets: &[DirBudget],
+    strategy: PackStrategy,
+    cache: &mut TokenCache,
+    logger: &Logger,
+) -> Vec<PathBuf> {
+    let mut groups: Vec<Vec<PathBuf>> = vec![Vec::new(); budgets.len()];
+    for path in &files {
+        if let Some(i) = pack::dir_budget_for(path.strip_prefix(root_dir).unwrap_or(path), budgets) {
+            groups[i].push(path.clone());
+        }
+    }
+    let mut dropped = HashSet::new();
+    for (budget, group) in budgets.iter().zip(groups) {
+        if group.is_empty() {
+            logger.warn(&format!("--dir-budget {} matched no files", budget.dir.display()));
+            continue;
+        }
+        let candidates = budget_candidates(group, root_dir, relevance, cache);
+        let total: usize = candidates.iter().map(|c| c.tokens).sum();
+        if total <= budget.tokens {
+            logger.debug(&format!("{}/ fits its budget: {} of {} tokens", budget.dir.display(), total, budget.tokens));
+            continue;
+        }
+        let paths: Vec<PathBuf> = candidates.iter().map(|c| c.path.clone()).collect();
+        let selected: HashSet<PathBuf> = pack::select(candidates, budget.tokens, strategy).into_iter().collect();
+        logger.info(&format!(
+            "Packed {} of {} files under {}/ into its {}-token budget ({} strategy)",
+            selected.len(),
+            paths.len(),
+            budget.dir.display(),
+            budget.tokens,
+            strategy.as_str()
+        ));
+        dropped.extend(paths.into_iter().filter(|p| !selected.contains(p)));
+    }
+    files.into_iter().filter(|p| !dropped.contains(p)).collect()
+}
+
+/// Token counts for each readable file, served from the cache where possible
+fn count_entries(files: &[PathBuf], root_dir: &Path, cache: &mut TokenCache, logger: &Logger) -> Vec<ListEntry> {
+    let mut entries = Vec::new();
+    for path in files {
+        match cache.tokens(path) {
+            Ok(tokens) => entries.push(ListEntry {
+                path: path.strip_prefix(root_dir).unwrap_or(path).to_string_lossy().to_string(),
+                tokens,
+            }),
+            Err(e) => logger.warn(&format!(
+                "Skipping {} ({}): {}",
+                path.display(),
+                SkipReason::from_io_error(&e),
+                e
+            )),
+        }
+    }
+    entries
+}
+
+/// Print each file with its token count, then the total
+fn list_files(entries: &[ListEntry], json: bool, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        writeln!(out, "{}", serde_json::to_string_pretty(entries)?)?;
+        return Ok(());
+    }
+    for entry in entries {
+        writeln!(out, "{:>8}  {}", entry.tokens, entry.path)?;
+    }
+    let total: usize = entries.iter().map(|e| e.tokens).sum();
+    writeln!(out, "{:>8}  total ({} files)", total, entries.len())?;
+    Ok(())
+}
+
+/// Print the total with per-directory and per-extension breakdowns
+fn print_counts(entries: &[ListEntry], json: bool, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    let group = |key: fn(&Path) -> String| {
+        let mut groups: HashMap<String, CountGroup> = HashMap::new();
+        for entry in entries {
+            let name = key(Path::new(&entry.path));
+            let g = groups.entry(name.clone()).or_insert(CountGroup { name, files: 0, tokens: 0 });
+            g.files += 1;
+            g.tokens += entry.tokens;
+        }
+        let mut groups: Vec<CountGroup> = groups.into_values().collect();
+        groups.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.name.cmp(&b.name)));
+        groups
+    };
+    let summary = CountSummary {
+        total_tokens: entries.iter().map(|e| e.tokens).sum(),
+        files: entries.len(),
+        by_directory: group(|p| {
+            let mut components = p.components();
+            match (components.next(), components.next()) {
+                (Some(dir), Some(_)) => dir.as_os_str().to_string_lossy().to_string(),
+                _ => ".".to_string(),
+            }
+        }),
+        by_extension: group(|p| {
+            p.extension()
+                .map(|e| format!(".{}", e.to_string_lossy()))
+                .unwrap_or_else(|| "(none)".to_string())
+        }),
+    };
+
+    if json {
+        writeln!(out, "{}", serde_json::to_string_pretty(&summary)?)?;
+        return Ok(());
+    }
+    writeln!(out, "Total: {} tokens in {} files", summary.total_tokens, summary.files)?;
+    for (title, groups) in [("By directory:", &summary.by_directory), ("By extension:", &summary.by_extension)] {
+        writeln!(out, "\n{}", title)?;
+        for g in groups {
+            writeln!(out, "{:>8}  {} ({} files)", g.tokens, g.name, g.files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prompt text given inline or read from a file
+fn prompt_text(inline: &Option<String>, file: &Option<PathBuf>) -> std::io::Result<Option<String>> {
+    match (inline, file) {
+        (Some(text), _) => Ok(Some(text.clone())),
+        (None, Some(path)) => fs::read_to_string(path).map(Some),
+        (None, None) => Ok(None),
     }
 }
+
+/// Keep the files `author` wrote the required share of according to `git blame`, blaming in parallel
+fn written_by(
+    files: Vec<PathBuf>,
+    root: &Path,
+    author: &str,
+    share: AuthorShare,
+    skipped: &mut Vec<(PathBuf, SkipReason)>,
+) -> Vec<PathBuf> {
+    let needle = author.to_lowercase();
+    let needle = needle.as_str();
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_len = files.len().div_ceil(threads).max(1);
+    let keep: Vec<bool> = std::thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(chunk_len)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|path| {
+                            // Untracked files have no blame and belong to nobody
+                            let Ok(lines) = git::blame_authors(root, path) else {
+                                return false;
+                            };
+                            let (mine, others): (Vec<_>, Vec<_>) =
+                                lines.iter().partition(|(who, _)| who.to_lowercase().contains(needle));
+                            let mine: usize = mine.iter().map(|(_, &n)| n).sum();
+                            match share {
+                                AuthorShare::Any => mine > 0,
+                                AuthorShare::Majority => mine > 0 && others.iter().all(|(_, &n)| n < mine),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().expect("blame thread panicked")).collect()
+    });
+    let mut kept = Vec::new();
+    for (path, keep) in files.into_iter().zip(keep) {
+        if keep {
+            kept.push(path);
+        } else {
+            skipped.push((path, SkipReason::OtherAuthor));
+        }
+    }
+    kept
+}
+
+/// Walk the target directories and apply git, language, and dependency selection.
+/// Explicit file targets are always included.
+fn discover_files(
+    args: &PackArgs,
+    targets: &[PathBuf],
+    root_dir: &Path,
+    filter: &LanguageFilter,
+    diff_range: Option<&str>,
+    skipped: &mut Vec<(PathBuf, SkipReason)>,
+    logger: &Logger,
+) -> Result<(Vec<PathBuf>, DepReport), Box<dyn std::error::Error>> {
+    let _traversal_span = tracing::info_span!("traversal", root = %root_dir.display()).entered();
+    let mut all_files = Vec::new();
+    let mut dep_report = DepReport::default();
+    let mut explicit_files = Vec::new();
+    for target in targets {
+        if !target.is_dir() {
+            explicit_files.push(target.clone());
+            continue;
+        }
+
+        let mut dir_files = walk::walk_files(target, args.depth_limit, skipped);
+        if args.git_tracked {
+            let tracked = git::tracked_files(target)?;
+            dir_files.retain(|p| tracked.contains(p));
+        }
+        if let Some(range) = diff_range {
+            let changed = git::changed_files(target, range)?;
+            logger.info(&format!("{} file(s) changed in {}", changed.len(), range));
+            dir_files.retain(|p| changed.contains(p));
+        }
+        if args.since.is_some() || args.until.is_some() {
+            let changed = LastChanged::new(target);
+            let (since, until) = (args.since.unwrap_or(i64::MIN), args.until.unwrap_or(i64::MAX));
+            let before = dir_files.len();
+            dir_files.retain(|p| {
+                let secs = changed.secs(p);
+                let keep = since <= secs && secs < until;
+                if !keep {
+                    skipped.push((p.clone(), SkipReason::OutsideWindow));
+                }
+                keep
+            });
+            logger.info(&format!("{} of {} file(s) changed in the --since/--until window", dir_files.len(), before));
+        }
+        if let Some(author) = &args.author {
+            let before = dir_files.len();
+            dir_files = written_by(dir_files, target, author, args.author_share, skipped);
+            logger.info(&format!(
+                "{} of {} file(s) written by {} ({} share)",
+                dir_files.len(),
+                before,
+                author,
+                args.author_share.as_str()
+            ));
+        }
+        all_files.extend(dir_files);
+    }
+
+    let mut files_to_process = HashSet::new();
+    let mut files_to_scan_deps = VecDeque::new();
+
+    for path in explicit_files {
+        if walk::is_special_path(&path) {
+            skipped.push((path, SkipReason::Special));
+            continue;
+        }
+        tracing::debug!(path = %path.display(), "explicit target");
+        if filter.dep_aware {
+            files_to_scan_deps.push_back(path.clone());
+        }
+        files_to_process.insert(path);
+    }
+
+    // --- Language filter ---
+    let mut selected = Vec::new();
+    for path in all_files {
+        let skip_notebook = args.notebook == NotebookMode::Skip
+            && notebook::is_notebook(path.extension().and_then(|s| s.to_str()).unwrap_or(""));
+        if filter.matches(&path) && !skip_notebook {
+            selected.push(path);
+        } else {
+            skipped.push((path, SkipReason::Filtered));
+        }
+    }
+
+    // --- Lock files, vendored code, generated and minified files ---
+    selected.retain(|path| {
+        let reason = if !args.include_locks && classify::is_lock(path) {
+            Some(SkipReason::Lock)
+        } else if !args.include_vendor && classify::is_vendored(path, root_dir) {
+            Some(SkipReason::Vendored)
+        } else if !args.include_generated && classify::is_generated(path) {
+            Some(SkipReason::Generated)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            skipped.push((path.clone(), reason));
+        }
+        reason.is_none()
+    });
+
+    // --- Content grep ---
+    if let Some(re) = &args.grep {
+        selected.retain(|path| {
+            let hit = documents::read_to_string(path).is_ok_and(|c| re.is_match(&c));
+            if !hit {
+                skipped.push((path.clone(), SkipReason::NoMatch));
+            }
+            hit
+        });
+    }
+
+    for path in selected {
+        tracing::debug!(path = %path.display(), "selected");
+        if filter.dep_aware {
+            files_to_scan_deps.push_back(path.clone());
+        }
+        files_to_process.insert(path);
+    }
+
+    // --- Resolve dependencies ---
+    if filter.dep_aware {
+        let _references_span = tracing::info_span!("references").entered();
+        let compile_db = match &args.compile_commands {
+            Some(path) => {
+                let db = CompileDb::load(path, root_dir).map_err(BoundError::Usage)?;
+                logger.info(&format!(
+                    "Read {} include dir(s) and {} define(s) from {}",
+                    db.include_dir_count(),
+                    db.define_count(),
+                    path.display()
+                ));
+                Some(db)
+            }
+            None => None,
+        };
+        let mut ts_paths = TsPaths::new(root_dir);
+        let mut visited = HashSet::new();
+        while let Some(path) = files_to_scan_deps.pop_front() {
+            if !visited.insert(path.clone()) { continue; }
+            let references = match deps::parse_references(&path) {
+                Ok(r) => r,
+                Err(e) if args.strict && SkipReason::from_io_error(&e) == SkipReason::Unreadable => {
+                    return Err(BoundError::Read(format!("Cannot read {}: {}", path.display(), e)).into());
+                }
+                // Skipped with a warning when the file itself is processed
+                Err(_) => continue,
+            };
+            let mut resolved: Vec<(String, Option<PathBuf>)> = Vec::new();
+            for r in references {
+                let mut candidate = match args.deps_cross_lang {
+                    true => deps::resolve_cross_lang(&path, &r, root_dir),
+                    false => deps::resolve(&path, &r, root_dir),
+                };
+                // Include directories and path aliases only matter for references that resolve nowhere else
+                if !candidate.as_ref().is_some_and(|c| c.exists()) {
+                    let found = compile_db.as_ref().and_then(|db| db.find(&path, &r)).or_else(|| ts_paths.resolve(&path, &r));
+                    candidate = found.or(candidate);
+                }
+                resolved.push((r, candidate));
+            }
+            if let Some(db) = &compile_db {
+                resolved.extend(db.includes(&path).into_iter().map(|(header, found)| (header, Some(found))));
+            }
+            for (r, candidate) in resolved {
+                // References out of the root are reported, never followed
+                let Some(candidate) = candidate.filter(|c| c.exists()) else {
+                    tracing::trace!(from = %path.display(), reference = %r, "unresolved");
+                    dep_report.unresolved.push((path.clone(), r));
+                    continue;
+                };
+                tracing::trace!(from = %path.display(), reference = %r, to = %candidate.display(), "resolved");
+                // An import of a barrel that only re-exports stands for the modules behind it
+                let flattened = args.flatten_barrels.then(|| barrel::flatten(&candidate, root_dir)).flatten();
+                if let Some(modules) = &flattened {
+                    logger.trace(&format!("{} re-exports {} module(s)", candidate.display(), modules.len()));
+                }
+                for candidate in flattened.unwrap_or_else(|| vec![candidate]) {
+                    if walk::is_special_path(&candidate) {
+                        continue;
+                    }
+                    dep_report.add_edge(&path, &candidate);
+                    if !files_to_process.contains(&candidate) {
+                        tracing::debug!(path = %candidate.display(), from = %path.display(), "selected as a dependency");
+                        logger.trace(&format!("{} references {}", path.display(), candidate.display()));
+                        files_to_process.insert(candidate.clone());
+                        files_to_scan_deps.push_back(candidate);
+                    }
+                }
+            }
+        }
+    }
+
+    // --- Sort files for consistent output ---
+    let mut sorted_files: Vec<PathBuf> = files_to_process.into_iter().collect();
+    sorted_files.sort();
+    Ok((sorted_files, dep_report))
+}
+
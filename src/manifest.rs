@@ -0,0 +1,106 @@
+//! manifest.rs
+//! Records where each file's section lives in an `--out` bundle, for `--manifest` consumers and `--incremental` reuse.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+const MANIFEST_VERSION: u32 = 2;
+
+/// One file's section in the bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub mtime_ns: u64,
+    pub size: u64,
+    /// Byte offset of the section in the bundle
+    pub offset: usize,
+    /// Byte length of the section
+    pub length: usize,
+    /// Tokens of the file's content, as every output format reports them
+    pub tokens: usize,
+    /// Tokens of the section around the content: header, metadata, and truncation notice
+    pub wrapper_tokens: usize,
+    /// SHA-256 of the source file
+    pub sha256: String,
+    pub truncated: bool,
+    /// Set for `--dedup` stubs, which are rebuilt rather than reused since the original may change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+    /// Normalized-content hash recorded under `--dedup`, so reused sections still claim their content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_key: Option<String>,
+}
+
+/// Index of the sections written to an `--out` file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// Fingerprint of the options that shape each section; a mismatch invalidates every entry
+    pub options: String,
+    pub bundle_bytes: usize,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn new(options: String) -> Self {
+        Manifest {
+            version: MANIFEST_VERSION,
+            options,
+            bundle_bytes: 0,
+            files: Vec::new(),
+        }
+    }
+
+    /// Write the manifest as pretty JSON
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// Sidecar path for a bundle: `<out>.manifest.json`
+pub fn manifest_path(out: &Path) -> PathBuf {
+    let mut name = out.as_os_str().to_owned();
+    name.push(".manifest.json");
+    PathBuf::from(name)
+}
+
+/// The previous bundle and its manifest, used to reuse sections of unchanged files
+pub struct PreviousBundle {
+    bundle: String,
+    entries: HashMap<String, ManifestEntry>,
+}
+
+impl PreviousBundle {
+    /// Load the last bundle written to `out`; `None` if it is missing, edited, or built with other options
+    pub fn load(out: &Path, options: &str) -> Option<Self> {
+        let manifest: Manifest = serde_json::from_slice(&fs::read(manifest_path(out)).ok()?).ok()?;
+        if manifest.version != MANIFEST_VERSION || manifest.options != options {
+            return None;
+        }
+        let bundle = fs::read_to_string(out).ok()?;
+        // The bundle is written with a trailing newline after the last section
+        if bundle.len() != manifest.bundle_bytes + 1 {
+            return None;
+        }
+        let entries = manifest
+            .files
+            .into_iter()
+            .filter(|e| bundle.get(e.offset..e.offset + e.length).is_some())
+            .map(|e| (e.path.clone(), e))
+            .collect();
+        Some(PreviousBundle { bundle, entries })
+    }
+
+    /// The previous section for `path` if its mtime and size are unchanged
+    pub fn section(&self, path: &str, mtime_ns: u64, size: u64) -> Option<(&str, &ManifestEntry)> {
+        let entry = self.entries.get(path)?;
+        if entry.mtime_ns != mtime_ns || entry.size != size || entry.duplicate_of.is_some() {
+            return None;
+        }
+        Some((&self.bundle[entry.offset..entry.offset + entry.length], entry))
+    }
+}
@@ -2,12 +2,15 @@
 //! metadata.rs
 //! Provides file metadata collection for bound outputs.
 
-use std::fs;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::Serialize;
 
+use crate::git::CommitInfo;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct FileMetadata {
     pub relative_path: String,
@@ -15,28 +18,65 @@ pub struct FileMetadata {
     pub line_count: usize,
     pub modified_unix: u64,
     pub sha256: Option<String>,
+    pub git: Option<CommitInfo>,
 }
 
 impl FileMetadata {
     /// Generate a standardized header string for aggregation
     pub fn to_header(&self) -> String {
         let ts = self.modified_unix;
+        let hash = match &self.sha256 {
+            Some(h) => format!("🔑 SHA-256: {}\n", h),
+            None => String::new(),
+        };
+        let git = match &self.git {
+            Some(c) => format!(
+                "🔖 Commit: {} | 👤 Author: {} | 📅 Date: {}\n",
+                &c.sha[..c.sha.len().min(12)],
+                c.author,
+                c.date
+            ),
+            None => String::new(),
+        };
         format!(
-            "📄 FILE: {} \n📏 Size: {} bytes | 📝 Lines: {} | ⏰ Modified: {}\n----------------------------------------\n",
+            "📄 FILE: {} \n📏 Size: {} bytes | 📝 Lines: {} | ⏰ Modified: {}\n{}{}----------------------------------------\n",
             self.relative_path,
             self.size_bytes,
             self.line_count,
-            ts
+            ts,
+            hash,
+            git
         )
     }
 }
 
-/// Collects file metadata given a file path and root directory
+/// Collects file metadata given a file path and root directory.
+/// The file is streamed, so large files are never held in memory.
 pub fn collect_metadata(path: &Path, root: &Path, hash: bool) -> std::io::Result<FileMetadata> {
-    let content = fs::read_to_string(path)?;
+    use sha2::{Digest, Sha256};
+
     let metadata = fs::metadata(path)?;
 
-    let line_count = content.lines().count();
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = hash.then(Sha256::new);
+    let (mut line_count, mut last_byte) = (0, b'\n');
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        line_count += chunk.iter().filter(|&&b| b == b'\n').count();
+        last_byte = chunk[chunk.len() - 1];
+        if let Some(h) = hasher.as_mut() {
+            h.update(chunk);
+        }
+        let len = chunk.len();
+        reader.consume(len);
+    }
+    // Match str::lines(): a final line without a trailing newline still counts
+    if last_byte != b'\n' {
+        line_count += 1;
+    }
 
     let modified_unix = metadata
         .modified()
@@ -54,11 +94,7 @@ pub fn collect_metadata(path: &Path, root: &Path, hash: bool) -> std::io::Result
         .to_string();
 
     // Optional SHA-256 hash
-    let sha256 = if hash {
-        Some(hash_string(&content))
-    } else {
-        None
-    };
+    let sha256 = hasher.map(|h| format!("{:x}", h.finalize()));
 
     Ok(FileMetadata {
         relative_path,
@@ -66,13 +102,26 @@ pub fn collect_metadata(path: &Path, root: &Path, hash: bool) -> std::io::Result
         line_count,
         modified_unix,
         sha256,
+        git: None,
     })
 }
 
-/// Simple SHA-256 hasher for file content
-fn hash_string(data: &str) -> String {
+/// Hex SHA-256 of in-memory content
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Whole-bundle checksum: SHA-256 of `sha256sum`-style lines (`<hash>  <path>`) in bundle order
+pub fn bundle_checksum<'a>(files: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    let listing: String = files.map(|(hash, path)| format!("{}  {}\n", hash, path)).collect();
+    sha256_hex(listing.as_bytes())
+}
+
+/// Hex SHA-256 of a file, streamed from disk
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
     use sha2::{Digest, Sha256};
-    let mut h = Sha256::new();
-    h.update(data.as_bytes());
-    format!("{:x}", h.finalize())
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
 }
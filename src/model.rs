@@ -0,0 +1,105 @@
+//! model.rs
+//! Model presets: tokenizer choice, context-window budgets, and input pricing.
+
+use clap::ValueEnum;
+use tiktoken_rs::CoreBPE;
+
+/// Tokens left free for the prompt and the model's answer when deriving a default budget
+const RESPONSE_RESERVE: usize = 8_192;
+
+/// Target model, selecting the tokenizer and default `--budget`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Model {
+    #[value(name = "gpt-4o")]
+    Gpt4o,
+    ClaudeSonnet,
+    #[value(name = "llama-70b")]
+    Llama70b,
+}
+
+impl Model {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Model::Gpt4o => "gpt-4o",
+            Model::ClaudeSonnet => "claude-sonnet",
+            Model::Llama70b => "llama-70b",
+        }
+    }
+
+    /// Context window in tokens
+    pub fn context_window(&self) -> usize {
+        match self {
+            Model::Gpt4o => 128_000,
+            Model::ClaudeSonnet => 200_000,
+            Model::Llama70b => 128_000,
+        }
+    }
+
+    /// List price in USD per million input tokens (llama-70b: typical hosted rate)
+    pub fn input_price_per_million(&self) -> f64 {
+        match self {
+            Model::Gpt4o => 2.50,
+            Model::ClaudeSonnet => 3.00,
+            Model::Llama70b => 0.88,
+        }
+    }
+
+    /// One-line input cost estimate, e.g. "~142k tokens ≈ $0.36 at gpt-4o input rates"
+    pub fn cost_summary(&self, tokens: usize) -> String {
+        let cost = tokens as f64 / 1_000_000.0 * self.input_price_per_million();
+        let cost = if cost < 0.01 { "<$0.01".to_string() } else { format!("${:.2}", cost) };
+        format!("~{} tokens ≈ {} at {} input rates", short_count(tokens), cost, self.as_str())
+    }
+
+    /// Context window minus a reserve for the prompt and answer
+    pub fn default_budget(&self) -> usize {
+        self.context_window() - RESPONSE_RESERVE
+    }
+
+    pub fn tokenizer(&self) -> Tokenizer {
+        match self {
+            Model::Gpt4o => Tokenizer::Bpe("o200k_base", tiktoken_rs::o200k_base_singleton()),
+            // Llama 3's 128k vocabulary is tiktoken-derived; cl100k is a close approximation
+            Model::Llama70b => Tokenizer::Bpe("cl100k_base", tiktoken_rs::cl100k_base_singleton()),
+            // No public Claude tokenizer; ~3.5 characters per token on code
+            Model::ClaudeSonnet => Tokenizer::CharRatio("claude-estimate", 3.5),
+        }
+    }
+}
+
+/// Counts tokens the way a model (or the default whitespace splitter) would
+#[derive(Clone, Copy)]
+pub enum Tokenizer {
+    /// Whitespace-delimited words (the default without `--model`)
+    Whitespace,
+    Bpe(&'static str, &'static CoreBPE),
+    CharRatio(&'static str, f64),
+}
+
+impl Tokenizer {
+    /// Short name, used to key the token cache
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tokenizer::Whitespace => "whitespace",
+            Tokenizer::Bpe(name, _) | Tokenizer::CharRatio(name, _) => name,
+        }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Tokenizer::Whitespace => text.split_whitespace().count(),
+            Tokenizer::Bpe(_, bpe) => bpe.encode_ordinary(text).len(),
+            Tokenizer::CharRatio(_, ratio) => (text.chars().count() as f64 / ratio).ceil() as usize,
+        }
+    }
+}
+
+/// Abbreviate a count: 950, 1.5k, 142k, 1.2M
+fn short_count(n: usize) -> String {
+    match n {
+        0..=999 => n.to_string(),
+        1_000..=9_999 => format!("{:.1}k", n as f64 / 1e3),
+        10_000..=999_999 => format!("{:.0}k", n as f64 / 1e3),
+        _ => format!("{:.1}M", n as f64 / 1e6),
+    }
+}
@@ -0,0 +1,29 @@
+//! normalize.rs
+//! Line-ending and whitespace cleanup applied with `--normalize`.
+
+/// Strip a leading BOM, convert CRLF to LF, strip trailing whitespace, and collapse blank-line runs
+pub fn normalize(content: &str) -> String {
+    let content = content.strip_prefix('\u{FEFF}').unwrap_or(content);
+    let mut out = String::with_capacity(content.len());
+    let mut blank_run = 0;
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    // Trailing blank lines collapse to the single final newline
+    let kept = out.trim_end_matches('\n').len();
+    out.truncate(kept);
+    if content.ends_with('\n') && kept > 0 {
+        out.push('\n');
+    }
+    out
+}
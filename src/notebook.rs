@@ -0,0 +1,73 @@
+//! notebook.rs
+//! Extracts the cells of Jupyter notebooks (`.ipynb`) instead of aggregating their raw JSON.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Which notebook cells are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NotebookMode {
+    /// Code cells only
+    Code,
+    /// Code and markdown cells
+    All,
+    /// Leave notebooks out entirely
+    Skip,
+}
+
+impl NotebookMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotebookMode::Code => "code",
+            NotebookMode::All => "all",
+            NotebookMode::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Notebook {
+    cells: Vec<Cell>,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    cell_type: String,
+    source: Source,
+}
+
+/// Cell source is either one string or a list of lines
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Source {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+/// Whether `ext` is a notebook extension
+pub fn is_notebook(ext: &str) -> bool {
+    ext == "ipynb"
+}
+
+/// Cells in order, each under a `# %% [type] cell N` label; `None` if the JSON isn't a notebook.
+/// Outputs are always dropped.
+pub fn extract(content: &str, mode: NotebookMode) -> Option<String> {
+    let notebook: Notebook = serde_json::from_str(content).ok()?;
+    let mut out = Vec::new();
+    for (i, cell) in notebook.cells.iter().enumerate() {
+        let keep = match cell.cell_type.as_str() {
+            "code" => mode != NotebookMode::Skip,
+            "markdown" => mode == NotebookMode::All,
+            _ => false,
+        };
+        if !keep {
+            continue;
+        }
+        let source = match &cell.source {
+            Source::Text(text) => text.clone(),
+            Source::Lines(lines) => lines.concat(),
+        };
+        out.push(format!("# %% [{}] cell {}\n{}", cell.cell_type, i + 1, source.trim_end()));
+    }
+    Some(out.join("\n\n"))
+}
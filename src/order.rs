@@ -0,0 +1,138 @@
+//! order.rs
+//! Bundle ordering for `--order`: orientation material first, most recently changed first, or alphabetical;
+//! `--first`/`--last` then pin single files to either end.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use clap::ValueEnum;
+
+use crate::git;
+
+/// Root files that describe the project's build and dependencies
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "setup.cfg",
+    "requirements.txt",
+    "go.mod",
+    "Gemfile",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "CMakeLists.txt",
+    "Makefile",
+    "composer.json",
+    "mix.exs",
+    "pubspec.yaml",
+    "tsconfig.json",
+    "deno.json",
+];
+
+/// File stems of program and library entry points
+const ENTRY_POINTS: &[&str] = &["main", "lib", "index", "app", "__main__"];
+
+/// Top-level directories that usually hold the core code
+const CORE_DIRS: &[&str] = &["src", "lib", "app", "cmd", "pkg", "internal", "crates", "packages"];
+
+/// Order of files in the bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileOrder {
+    /// Alphabetical by path
+    Path,
+    /// README and root manifests, then entry points, then core directories, then the rest
+    Priority,
+    /// Most recently committed first (modification time for untracked files or outside git)
+    Recent,
+}
+
+impl FileOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileOrder::Path => "path",
+            FileOrder::Priority => "priority",
+            FileOrder::Recent => "recent",
+        }
+    }
+}
+
+/// Reorder `files` in place; ties keep alphabetical order
+pub fn sort(files: &mut [PathBuf], root: &Path, order: FileOrder) {
+    files.sort();
+    match order {
+        FileOrder::Path => {}
+        FileOrder::Priority => files.sort_by_cached_key(|p| tier(p.strip_prefix(root).unwrap_or(p))),
+        FileOrder::Recent => {
+            let changed = LastChanged::new(root);
+            files.sort_by_cached_key(|p| std::cmp::Reverse(changed.secs(p)));
+        }
+    }
+}
+
+/// Move the `first` files to the front and the `last` files to the back, each in the order given.
+/// Returns the pins that name no file in `files`.
+pub fn pin<'a>(files: &mut Vec<PathBuf>, first: &'a [PathBuf], last: &'a [PathBuf]) -> Vec<&'a PathBuf> {
+    let missing: Vec<&PathBuf> = first.iter().chain(last).filter(|p| !files.contains(p)).collect();
+    files.retain(|p| !first.contains(p) && !last.contains(p));
+    let head = first.iter().filter(|p| !missing.contains(p));
+    let tail = last.iter().filter(|p| !missing.contains(p));
+    let rest = std::mem::take(files);
+    *files = head.cloned().chain(rest).chain(tail.cloned()).collect();
+    missing
+}
+
+/// When files under a directory last changed, for `--order recent`, `--since`, and `--until`
+pub struct LastChanged {
+    commits: HashMap<PathBuf, i64>,
+    edited: HashSet<PathBuf>,
+}
+
+impl LastChanged {
+    /// Read the history of the repository at `root`, if there is one
+    pub fn new(root: &Path) -> Self {
+        LastChanged {
+            commits: git::commit_times(root).unwrap_or_default(),
+            edited: git::changed_files(root, "HEAD").unwrap_or_default(),
+        }
+    }
+
+    /// Unix time of the last commit touching `path`; untracked files and files with uncommitted edits use their mtime
+    pub fn secs(&self, path: &Path) -> i64 {
+        match self.commits.get(path) {
+            Some(&secs) if !self.edited.contains(path) => secs,
+            _ => mtime_secs(path),
+        }
+    }
+}
+
+/// Modification time in seconds since the epoch, or 0 when unavailable
+fn mtime_secs(path: &Path) -> i64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Rank for `--order priority`, with depth breaking ties among entry points
+fn tier(relative: &Path) -> (u8, usize) {
+    let depth = relative.components().count().saturating_sub(1);
+    let name = relative.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let stem = relative.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let top_dir = relative.components().next().and_then(|c| c.as_os_str().to_str()).filter(|_| depth > 0);
+    if depth == 0 && stem.eq_ignore_ascii_case("readme") {
+        (0, 0)
+    } else if depth == 0 && MANIFESTS.contains(&name) {
+        (1, 0)
+    } else if ENTRY_POINTS.contains(&stem) {
+        (2, depth)
+    } else if top_dir.is_some_and(|d| CORE_DIRS.contains(&d)) {
+        (3, 0)
+    } else {
+        (4, 0)
+    }
+}
@@ -0,0 +1,199 @@
+//! output.rs
+//! Writes the finished bundle to `--out` (or an `--out-auto` name), guarding existing files unless `--force` or `--append` is given,
+//! optionally through a gzip or zstd encoder.
+
+use std::collections::hash_map::RandomState;
+use std::fs::{self, OpenOptions};
+use std::hash::BuildHasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+
+use crate::cli::OutputFormat;
+
+/// zstd compression level (the library default)
+const ZSTD_LEVEL: i32 = 3;
+
+/// How an existing output file is treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Fail if the file exists
+    CreateNew,
+    /// Replace the file (`--force`, `--incremental`)
+    Overwrite,
+    /// Add to the end of the file (`--append`)
+    Append,
+}
+
+/// Encoder for `--compress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// gzip (`.gz`)
+    Gz,
+    /// Zstandard (`.zst`)
+    Zst,
+}
+
+impl Compression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::Gz => "gz",
+            Compression::Zst => "zst",
+        }
+    }
+}
+
+/// Why writing to `path` in `CreateNew` mode would clobber it, or `None` if it is free
+pub fn clobber_notice(path: &Path) -> Option<String> {
+    let meta = fs::metadata(path).ok()?;
+    let age = meta
+        .modified()
+        .ok()
+        .and_then(|t| SystemTime::now().duration_since(t).ok())
+        .map(|d| format!(", modified {} ago", human_age(d.as_secs())))
+        .unwrap_or_default();
+    Some(format!(
+        "Refusing to overwrite {} ({} bytes{}); pass --force to replace it or --append to add to it",
+        path.display(),
+        meta.len(),
+        age
+    ))
+}
+
+/// Write `content` and a trailing newline to `path`, returning the bytes that reached the disk.
+/// Appending to a compressed file adds a new gzip member or zstd frame, which decoders read as one stream.
+pub fn write(path: &Path, content: &str, mode: WriteMode, compression: Option<Compression>) -> io::Result<u64> {
+    let mut options = OpenOptions::new();
+    match mode {
+        WriteMode::CreateNew => options.write(true).create_new(true),
+        WriteMode::Overwrite => options.write(true).create(true).truncate(true),
+        WriteMode::Append => options.append(true).create(true),
+    };
+    let mut f = options.open(path)?;
+    let data = format!("{}\n", content);
+    let bytes = match compression {
+        None => data.into_bytes(),
+        Some(Compression::Gz) => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data.as_bytes())?;
+            encoder.finish()?
+        }
+        Some(Compression::Zst) => zstd::encode_all(data.as_bytes(), ZSTD_LEVEL)?,
+    };
+    f.write_all(&bytes)?;
+    Ok(bytes.len() as u64)
+}
+
+/// A fresh `bound-<repo>-<yyyymmdd-HHMM>.<ext>[.gz|.zst]` path (UTC) in `$BOUND_OUT_DIR` or the current directory.
+/// Runs within the same minute get a `-2`, `-3`, ... suffix.
+pub fn auto_path(repo: &str, format: OutputFormat, compression: Option<Compression>) -> io::Result<PathBuf> {
+    let dir = std::env::var_os("BOUND_OUT_DIR")
+        .filter(|v| !v.is_empty())
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+    fs::create_dir_all(&dir)?;
+
+    let repo: String = repo
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '-' })
+        .collect();
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let stem = format!("bound-{}-{}", repo, timestamp(secs));
+    let ext = match compression {
+        Some(c) => format!("{}.{}", extension(format), c.as_str()),
+        None => extension(format).to_string(),
+    };
+    let mut path = dir.join(format!("{}.{}", stem, ext));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.{}", stem, n, ext));
+        n += 1;
+    }
+    Ok(path)
+}
+
+/// Save a bundle the clipboard couldn't hold to a new private file in the temp directory, returning its path
+pub fn save_clipboard_fallback(content: &str, format: OutputFormat) -> io::Result<PathBuf> {
+    let (path, mut file) = create_private_temp("bound-clipboard", extension(format))?;
+    file.write_all(format!("{}\n", content).as_bytes())?;
+    Ok(path)
+}
+
+/// Create `{prefix}-{random}.{ext}` in the temp directory, returning its path and the open file.
+/// The name is random and the file must not exist yet, so a planted file or symlink is never written through;
+/// on Unix only the user can read it.
+pub fn create_private_temp(prefix: &str, ext: &str) -> io::Result<(PathBuf, fs::File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut attempts = 0;
+    loop {
+        let random = RandomState::new().hash_one(attempts);
+        let path = std::env::temp_dir().join(format!("{}-{:016x}.{}", prefix, random, ext));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 8 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// File extension for bundles in `format`
+pub fn extension(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Text => "md",
+        OutputFormat::Json => "json",
+        OutputFormat::Jsonl => "jsonl",
+        OutputFormat::Html => "html",
+    }
+}
+
+/// `yyyymmdd-HHMM` in UTC for a Unix time
+fn timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let minutes = secs % 86_400 / 60;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}{:02}{:02}-{:02}{:02}", year, month, day, minutes / 60, minutes % 60)
+}
+
+fn human_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86_399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clipboard_fallback_gets_a_fresh_private_file() {
+        let first = save_clipboard_fallback("one", OutputFormat::Text).unwrap();
+        let second = save_clipboard_fallback("two", OutputFormat::Text).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one\n");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "two\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&first).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+//! pack.rs
+//! Chooses which files to include when they don't all fit in the token budget.
+
+use std::path::{Component, Path, PathBuf};
+
+use clap::ValueEnum;
+
+/// File stems that usually mark an entry point worth keeping
+const ENTRY_POINTS: &[&str] = &["main", "lib", "mod", "index", "app", "__init__", "__main__", "server", "cli"];
+
+/// Knapsack capacity is bucketed to at most this many units to bound the DP table
+const MAX_UNITS: usize = 10_000;
+
+/// Largest knapsack table, in bits (32 MiB); more candidates than fit are packed in priority order instead
+const MAX_TABLE_BITS: usize = 1 << 28;
+
+/// How files are chosen under a budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PackStrategy {
+    /// Take files in order, skipping any that no longer fit
+    Greedy,
+    /// Take the highest-priority files first
+    Priority,
+    /// Maximize total priority with a 0/1 knapsack
+    Knapsack,
+}
+
+impl PackStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackStrategy::Greedy => "greedy",
+            PackStrategy::Priority => "priority",
+            PackStrategy::Knapsack => "knapsack",
+        }
+    }
+}
+
+/// A file competing for budget
+pub struct Candidate {
+    pub path: PathBuf,
+    pub tokens: usize,
+    pub priority: f64,
+}
+
+/// A token allocation for the files under one directory (`--dir-budget tests=2000`)
+#[derive(Debug, Clone)]
+pub struct DirBudget {
+    /// Directory relative to the aggregation root
+    pub dir: PathBuf,
+    pub tokens: usize,
+}
+
+impl DirBudget {
+    /// Parse `DIR=TOKENS`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (dir, tokens) = s.rsplit_once('=').ok_or_else(|| format!("expected DIR=TOKENS, got '{}'", s))?;
+        let tokens = tokens.trim().parse().map_err(|_| format!("invalid token count '{}'", tokens))?;
+        // Compare by components so `./tests/` and `tests` are the same directory
+        let dir: PathBuf = Path::new(dir.trim())
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+        if dir.as_os_str().is_empty() {
+            return Err(format!("'{}' names no directory; use --budget for the whole tree", s));
+        }
+        Ok(DirBudget { dir, tokens })
+    }
+}
+
+/// Index of the most specific budget whose directory contains `relative`
+pub fn dir_budget_for(relative: &Path, budgets: &[DirBudget]) -> Option<usize> {
+    budgets
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| relative.starts_with(&b.dir))
+        .max_by_key(|(_, b)| b.dir.components().count())
+        .map(|(i, _)| i)
+}
+
+/// Priority from relevance, entry-point names, shallow depth, and small size
+pub fn priority(path: &Path, root: &Path, tokens: usize, relevance: f64) -> f64 {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let depth = relative.components().count().saturating_sub(1) as f64;
+    let stem = relative.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let entry = if ENTRY_POINTS.contains(&stem) { 1.0 } else { 0.0 };
+    1.0 + relevance + entry + 1.0 / (1.0 + depth) + 1.0 / (1.0 + tokens as f64 / 1000.0)
+}
+
+/// Select candidates within `budget` tokens; the result keeps the input order
+pub fn select(candidates: Vec<Candidate>, budget: usize, strategy: PackStrategy) -> Vec<PathBuf> {
+    let keep = match strategy {
+        PackStrategy::Greedy => fill(&candidates, (0..candidates.len()).collect(), budget),
+        PackStrategy::Priority => fill(&candidates, by_priority(&candidates), budget),
+        PackStrategy::Knapsack => knapsack(&candidates, budget),
+    };
+    candidates
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(c, k)| k.then_some(c.path))
+        .collect()
+}
+
+/// Candidate indices, highest priority first
+fn by_priority(candidates: &[Candidate]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by(|&a, &b| candidates[b].priority.total_cmp(&candidates[a].priority));
+    order
+}
+
+/// Take candidates in `order` while they fit
+fn fill(candidates: &[Candidate], order: Vec<usize>, budget: usize) -> Vec<bool> {
+    let mut keep = vec![false; candidates.len()];
+    let mut used = 0;
+    for i in order {
+        if used + candidates[i].tokens <= budget {
+            used += candidates[i].tokens;
+            keep[i] = true;
+        }
+    }
+    keep
+}
+
+/// 0/1 knapsack over token weights (rounded up to buckets) maximizing total priority.
+/// Past `MAX_TABLE_BITS` of decisions to remember, candidates are taken in priority order instead.
+fn knapsack(candidates: &[Candidate], budget: usize) -> Vec<bool> {
+    let unit = ceil_div(budget, MAX_UNITS).max(1);
+    let capacity = budget / unit;
+    let row_words = ceil_div(capacity + 1, 64);
+    if candidates.len().saturating_mul(row_words * 64) > MAX_TABLE_BITS {
+        return fill(candidates, by_priority(candidates), budget);
+    }
+    let weights: Vec<usize> = candidates.iter().map(|c| ceil_div(c.tokens, unit)).collect();
+
+    let mut best = vec![0.0f64; capacity + 1];
+    // Bit w of row i: item i improved the best value at capacity w
+    let mut taken = vec![0u64; candidates.len() * row_words];
+    let bit = |i: usize, cap: usize| (i * row_words + cap / 64, 1u64 << (cap % 64));
+    for (i, c) in candidates.iter().enumerate() {
+        let w = weights[i];
+        if w > capacity {
+            continue;
+        }
+        for cap in (w..=capacity).rev() {
+            let with = best[cap - w] + c.priority;
+            if with > best[cap] {
+                best[cap] = with;
+                let (word, mask) = bit(i, cap);
+                taken[word] |= mask;
+            }
+        }
+    }
+
+    let mut keep = vec![false; candidates.len()];
+    let mut cap = capacity;
+    for i in (0..candidates.len()).rev() {
+        let (word, mask) = bit(i, cap);
+        if taken[word] & mask != 0 {
+            keep[i] = true;
+            cap -= weights[i];
+        }
+    }
+    keep
+}
+
+fn ceil_div(a: usize, b: usize) -> usize {
+    a.div_ceil(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(name: &str, tokens: usize, priority: f64) -> Candidate {
+        Candidate { path: PathBuf::from(name), tokens, priority }
+    }
+
+    #[test]
+    fn knapsack_beats_priority_order() {
+        let candidates = || vec![candidate("big", 60, 3.0), candidate("a", 50, 2.0), candidate("b", 50, 2.0)];
+        assert_eq!(select(candidates(), 100, PackStrategy::Priority), vec![PathBuf::from("big")]);
+        assert_eq!(select(candidates(), 100, PackStrategy::Knapsack), vec![PathBuf::from("a"), PathBuf::from("b")]);
+    }
+
+    #[test]
+    fn knapsack_over_many_files_stays_within_budget() {
+        let candidates: Vec<Candidate> = (0..100_000).map(|i| candidate(&i.to_string(), 10 + i % 90, (i % 7) as f64)).collect();
+        let chosen = select(candidates, 1_000_000, PackStrategy::Knapsack);
+        let used: usize = chosen.iter().map(|p| 10 + p.to_str().unwrap().parse::<usize>().unwrap() % 90).sum();
+        assert!(!chosen.is_empty() && used <= 1_000_000);
+    }
+}
@@ -0,0 +1,413 @@
+//! plugins.rs
+//! User-supplied content transforms that rewrite matching files before aggregation: `--filter-cmd` shell commands
+//! and sandboxed WebAssembly modules from a `--plugins` directory.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ignore::overrides::{Override, OverrideBuilder};
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Instructions (roughly) a plugin may execute per file
+const PLUGIN_FUEL: u64 = 1_000_000_000;
+
+/// Largest linear memory a plugin may grow to
+const PLUGIN_MEMORY_BYTES: usize = 256 << 20;
+
+/// Largest transformed file a plugin may return
+const PLUGIN_OUTPUT_BYTES: usize = 64 << 20;
+
+/// A `--filter-cmd` value: a shell command, limited to files matching a glob when one is given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterCmd {
+    pub glob: Option<String>,
+    pub command: String,
+}
+
+impl FilterCmd {
+    /// Parse `[GLOB=]COMMAND`; the glob is the text before the first `=` when it contains no spaces
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (glob, command) = match s.split_once('=') {
+            Some((glob, command)) if !glob.is_empty() && !glob.contains(char::is_whitespace) => (Some(glob.to_string()), command),
+            _ => (None, s),
+        };
+        if command.trim().is_empty() {
+            return Err(format!("'{}' has no command", s));
+        }
+        Ok(FilterCmd { glob, command: command.trim().to_string() })
+    }
+}
+
+/// Why a file couldn't be filtered
+#[derive(Debug)]
+pub enum FilterError {
+    /// Filter commands ran past `--file-timeout` and were killed
+    TimedOut,
+    Failed(String),
+}
+
+/// A compiled WebAssembly plugin
+struct WasmPlugin {
+    name: String,
+    module: Module,
+    matcher: Option<Override>,
+}
+
+/// Sidecar `<plugin>.toml` next to a module
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PluginConfig {
+    /// Globs of the files the plugin transforms (default: all)
+    globs: Vec<String>,
+}
+
+/// The filter commands and WebAssembly plugins of a run, with their globs compiled against the root
+pub struct Plugins {
+    filters: Vec<(Option<Override>, String)>,
+    /// How long a filter command may run on one file before it is killed (`--file-timeout`)
+    filter_timeout: Option<Duration>,
+    engine: Engine,
+    wasm: Vec<WasmPlugin>,
+}
+
+impl Plugins {
+    pub fn new(root: &Path, filters: &[FilterCmd], plugins_dir: Option<&Path>) -> Result<Self, String> {
+        let filters = filters
+            .iter()
+            .map(|f| Ok((matcher(root, f.glob.as_slice())?, f.command.clone())))
+            .collect::<Result<_, String>>()?;
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let wasm = match plugins_dir {
+            Some(dir) => load_plugins(&engine, root, dir)?,
+            None => Vec::new(),
+        };
+        Ok(Plugins { filters, filter_timeout: None, engine, wasm })
+    }
+
+    /// Kill filter commands still running on a file after `limit`; WebAssembly plugins are bounded by their fuel
+    pub fn with_timeout(mut self, limit: Option<Duration>) -> Self {
+        self.filter_timeout = limit;
+        self
+    }
+
+    /// Names of the loaded WebAssembly plugins
+    pub fn wasm_names(&self) -> Vec<&str> {
+        self.wasm.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    /// Pipe `content` through each filter matching `path`, in order; `None` when none match
+    pub fn apply(&self, path: &Path, relative: &str, content: &str) -> Result<Option<String>, FilterError> {
+        let mut filtered: Option<String> = None;
+        // One deadline for the whole chain, so several commands can't each take the full limit
+        let deadline = self.filter_timeout.map(|limit| Instant::now() + limit);
+        for (matcher, command) in &self.filters {
+            if matcher.as_ref().is_some_and(|m| !m.matched(path, false).is_whitelist()) {
+                continue;
+            }
+            let input = filtered.as_deref().unwrap_or(content);
+            let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            let output = run_filter(command, relative, input, remaining).map_err(|e| match e.kind() {
+                io::ErrorKind::TimedOut => FilterError::TimedOut,
+                _ => FilterError::Failed(format!("`{}` failed on {}: {}", command, relative, e)),
+            })?;
+            filtered = Some(output);
+        }
+        for plugin in &self.wasm {
+            if plugin.matcher.as_ref().is_some_and(|m| !m.matched(path, false).is_whitelist()) {
+                continue;
+            }
+            let input = filtered.as_deref().unwrap_or(content);
+            let output = self
+                .run_wasm(&plugin.module, relative, input)
+                .map_err(|e| FilterError::Failed(format!("plugin {} failed on {}: {}", plugin.name, relative, e)))?;
+            if let Some(output) = output {
+                filtered = Some(output);
+            }
+        }
+        Ok(filtered)
+    }
+
+    /// Call a plugin's `transform` in a fresh instance; `None` when it leaves the file unchanged
+    fn run_wasm(&self, module: &Module, relative: &str, input: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let limits = StoreLimitsBuilder::new().memory_size(PLUGIN_MEMORY_BYTES).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(PLUGIN_FUEL)?;
+        // No host functions are linked, so a plugin can't reach files, the network, or the clock
+        let instance = Linker::new(&self.engine).instantiate_and_start(&mut store, module)?;
+        let memory = instance.get_memory(&store, "memory").ok_or("it exports no memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc")?;
+        let transform = instance.get_typed_func::<(i32, i32, i32, i32), i64>(&store, "transform")?;
+
+        let mut pass = |bytes: &[u8]| -> Result<(i32, i32), Box<dyn std::error::Error>> {
+            let len = i32::try_from(bytes.len()).map_err(|_| "input over 2 GiB")?;
+            let ptr = alloc.call(&mut store, len)?;
+            memory.write(&mut store, ptr as u32 as usize, bytes)?;
+            Ok((ptr, len))
+        };
+        let (path_ptr, path_len) = pass(relative.as_bytes())?;
+        let (data_ptr, data_len) = pass(input.as_bytes())?;
+        let packed = transform.call(&mut store, (path_ptr, path_len, data_ptr, data_len))?;
+        if packed < 0 {
+            return Ok(None);
+        }
+        let (ptr, len) = ((packed >> 32) as u32 as usize, packed as u32 as usize);
+        // Both halves come from the plugin, so check them before allocating anything
+        if len > PLUGIN_OUTPUT_BYTES {
+            return Err(format!("output of {} bytes is over the {}-byte limit", len, PLUGIN_OUTPUT_BYTES).into());
+        }
+        if ptr + len > memory.data_size(&store) {
+            return Err(format!("output at {}..{} lies outside its {}-byte memory", ptr, ptr + len, memory.data_size(&store)).into());
+        }
+        let mut output = vec![0; len];
+        memory.read(&store, ptr, &mut output)?;
+        Ok(Some(String::from_utf8(output).map_err(|_| "output is not UTF-8")?))
+    }
+}
+
+/// A matcher for `globs`, or `None` to match every file
+fn matcher(root: &Path, globs: &[String]) -> Result<Option<Override>, String> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = OverrideBuilder::new(root);
+    for glob in globs {
+        builder.add(glob).map_err(|e| format!("invalid glob '{}': {}", glob, e))?;
+    }
+    builder.build().map(Some).map_err(|e| e.to_string())
+}
+
+/// Compile every `.wasm` and `.wat` module in `dir`, in name order
+fn load_plugins(engine: &Engine, root: &Path, dir: &Path) -> Result<Vec<WasmPlugin>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read plugins directory {}: {}", dir.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "wasm" || ext == "wat"))
+        .collect();
+    paths.sort();
+    let mut plugins = Vec::new();
+    for path in paths {
+        let name = path.file_stem().map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+        let bytes = fs::read(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let module = Module::new(engine, bytes).map_err(|e| format!("Cannot load plugin {}: {}", path.display(), e))?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!(
+                "plugin {} imports {}::{}, but plugins get no host functions",
+                name,
+                import.module(),
+                import.name()
+            ));
+        }
+        let config_path = path.with_extension("toml");
+        let config: PluginConfig = match fs::read_to_string(&config_path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("Cannot parse {}: {}", config_path.display(), e))?,
+            Err(_) => PluginConfig::default(),
+        };
+        let matcher = matcher(root, &config.globs).map_err(|e| format!("{}: {}", config_path.display(), e))?;
+        plugins.push(WasmPlugin { name, module, matcher });
+    }
+    Ok(plugins)
+}
+
+/// Run `command` through the shell with `input` on stdin and `BOUND_PATH` set, returning its stdout.
+/// A command still running after `limit` is killed and reaped rather than left behind.
+pub fn run_filter(command: &str, relative: &str, input: &str, limit: Option<Duration>) -> io::Result<String> {
+    let mut child = shell(command)
+        .env("BOUND_PATH", relative)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    // Feed stdin and drain the output on other threads, so a command that writes before reading everything
+    // can't deadlock, and one that times out can be killed while they are blocked
+    let input = input.to_string();
+    thread::spawn(move || {
+        // A command may exit without reading its input
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let (sender, output) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = sender.send(stdout.read_to_end(&mut buf).map(|_| buf));
+    });
+    let stderr = thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+    let deadline = limit.map(|limit| Instant::now() + limit);
+    let timed_out = |child: &mut Child| {
+        kill_group(child);
+        child.wait()?;
+        let limit = limit.unwrap_or_default();
+        Err(io::Error::new(io::ErrorKind::TimedOut, format!("killed after {:.1}s", limit.as_secs_f64())))
+    };
+    let status = match deadline {
+        None => child.wait()?,
+        Some(deadline) => match wait_until(&mut child, deadline)? {
+            Some(status) => status,
+            None => return timed_out(&mut child),
+        },
+    };
+    // Something the shell started may outlive it and keep the output open
+    let stdout = match deadline {
+        None => output.recv().ok(),
+        Some(deadline) => output.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok(),
+    };
+    let Some(stdout) = stdout else {
+        return timed_out(&mut child);
+    };
+    let stdout = stdout?;
+    if !status.success() {
+        let stderr = stderr.join().expect("stderr reader doesn't panic");
+        let stderr = String::from_utf8_lossy(&stderr);
+        let detail = stderr.trim().lines().last().unwrap_or("");
+        return Err(io::Error::other(format!("exited with {} {}", status, detail).trim_end().to_string()));
+    }
+    String::from_utf8(stdout).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "output is not UTF-8"))
+}
+
+/// Wait until `deadline` for `child` to exit; std has no timed wait, so this polls
+fn wait_until(child: &mut Child, deadline: Instant) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// `sh -c command`, leading a process group of its own so `kill_group` reaches everything it starts
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command).process_group(0);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Kill a filter command with every process it started: pipelines and commands run after `;` would otherwise
+/// live on and hold the output pipe open
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    // SAFETY: kill takes no pointers; the negated pid names the group `shell` made the child lead
+    unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a plugin whose `transform` returns `result` (pointer in the high half, length in the low half) on one file
+    fn transform_returning(result: i64) -> Result<Option<String>, String> {
+        let dir = std::env::temp_dir().join(format!("bound-plugin-test-{}-{:x}", std::process::id(), result));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "transform") (param i32 i32 i32 i32) (result i64) i64.const {}))"#,
+            result
+        );
+        fs::write(dir.join("probe.wat"), wat).unwrap();
+        let plugins = Plugins::new(&dir, &[], Some(&dir)).unwrap();
+        let outcome = plugins.apply(&dir.join("a.txt"), "a.txt", "hi");
+        fs::remove_dir_all(&dir).unwrap();
+        outcome.map_err(|e| format!("{:?}", e))
+    }
+
+    /// Whether `pid` is still running a second after a kill; orphans killed with their group linger as zombies
+    /// until init reaps them, which `kill -0` can't tell apart
+    #[cfg(unix)]
+    fn running(pid: &str) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            let output = Command::new("ps").args(["-o", "stat=", "-p", pid]).output().unwrap();
+            let state = String::from_utf8_lossy(&output.stdout);
+            if state.trim().is_empty() || state.trim().starts_with('Z') {
+                return false;
+            }
+            if Instant::now() >= deadline {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timed_out_filter_is_killed() {
+        let pid_file = std::env::temp_dir().join(format!("bound-filter-test-{}.pid", std::process::id()));
+        let command = format!("echo $$ > '{}'; exec sleep 30", pid_file.display());
+        let started = Instant::now();
+        let err = run_filter(&command, "a.txt", "", Some(Duration::from_millis(300))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        fs::remove_file(&pid_file).unwrap();
+        assert!(!running(pid.trim()), "filter process {} is still running", pid.trim());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timed_out_filter_takes_its_children_along() {
+        let pid_file = std::env::temp_dir().join(format!("bound-filter-group-test-{}.pid", std::process::id()));
+        let commands = [
+            "sleep 30; cat".to_string(),
+            format!("sleep 30 | cat & echo $! > '{}'; wait", pid_file.display()),
+            format!("sleep 30 & echo $! > '{}'; wait; cat", pid_file.display()),
+        ];
+        for command in &commands {
+            let _ = fs::remove_file(&pid_file);
+            let started = Instant::now();
+            let err = run_filter(command, "a.txt", "", Some(Duration::from_millis(300))).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::TimedOut, "{}", command);
+            assert!(started.elapsed() < Duration::from_secs(10), "{} outlived its limit", command);
+            let Ok(pid) = fs::read_to_string(&pid_file) else { continue };
+            assert!(!running(pid.trim()), "{} left process {} running", command, pid.trim());
+        }
+        let _ = fs::remove_file(&pid_file);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filter_output_is_returned_within_its_limit() {
+        assert_eq!(run_filter("tr a-z A-Z", "a.txt", "hi", Some(Duration::from_secs(10))).unwrap(), "HI");
+    }
+
+    #[test]
+    fn plugin_output_is_bounds_checked_before_allocating() {
+        assert_eq!(transform_returning(2).unwrap().as_deref(), Some("hi"));
+        let huge = transform_returning(0xffff_ffff).unwrap_err();
+        assert!(huge.contains("over the"), "{}", huge);
+        let outside = transform_returning((65_000 << 32) | 1_000).unwrap_err();
+        assert!(outside.contains("outside its 65536-byte memory"), "{}", outside);
+    }
+}
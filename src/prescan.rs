@@ -0,0 +1,45 @@
+//! prescan.rs
+//! Sizes the files of a run before processing, so the ETA weighs files by bytes; `--estimate` samples directories instead.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories whose files are measured with `--estimate`
+const SAMPLE_DIRS: usize = 64;
+
+/// The size of every file, read in parallel (0 for files that can't be read)
+pub fn sizes(files: &[PathBuf]) -> Vec<u64> {
+    parallel_sizes(files.iter().map(PathBuf::as_path).collect())
+}
+
+/// A size for every file from the mean of an evenly spread sample of directories, and the number of directories sampled.
+/// Much faster than `sizes` on huge trees, at the cost of a rougher ETA.
+pub fn estimate(files: &[PathBuf]) -> (Vec<u64>, usize) {
+    let mut by_dir: BTreeMap<&Path, Vec<&Path>> = BTreeMap::new();
+    for file in files {
+        by_dir.entry(file.parent().unwrap_or(Path::new(""))).or_default().push(file);
+    }
+    let step = by_dir.len().div_ceil(SAMPLE_DIRS).max(1);
+    let sample: Vec<&Path> = by_dir.values().step_by(step).flatten().copied().collect();
+    let sampled_dirs = by_dir.len().div_ceil(step);
+    let measured = parallel_sizes(sample);
+    let mean = match measured.len() {
+        0 => 0,
+        n => measured.iter().sum::<u64>() / n as u64,
+    };
+    (vec![mean; files.len()], sampled_dirs)
+}
+
+/// Stat `files` across the available cores, keeping their order
+fn parallel_sizes(files: Vec<&Path>) -> Vec<u64> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_len = files.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|p| fs::metadata(p).map_or(0, |m| m.len())).collect::<Vec<_>>()))
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().expect("pre-scan thread panicked")).collect()
+    })
+}
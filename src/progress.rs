@@ -0,0 +1,201 @@
+//! progress.rs
+//! Renders Telemetry as an in-place progress bar or periodic log lines on stderr.
+
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::logging::Logger;
+use crate::telemetry::Telemetry;
+
+const BAR_WIDTH: usize = 24;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How progress is reported while files are processed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// Single progress bar updated in place
+    Bar,
+    /// A log line every 10 files
+    Plain,
+    /// One JSON object per event, for editor plugins and wrappers
+    Json,
+    /// No progress output
+    None,
+}
+
+/// Machine-readable progress events emitted in `json` mode
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    FileStarted {
+        path: &'a str,
+        index: usize,
+        total: usize,
+    },
+    FileDone {
+        path: &'a str,
+        bytes: usize,
+        tokens: usize,
+        files_processed: usize,
+        total: usize,
+    },
+    RunComplete {
+        files: usize,
+        bytes: usize,
+        tokens: usize,
+        redactions: usize,
+        skipped: BTreeMap<String, usize>,
+        elapsed_secs: f64,
+    },
+}
+
+/// Write one event as a JSON line on stderr
+fn emit(event: &Event) {
+    if let Ok(line) = serde_json::to_string(event) {
+        eprintln!("{}", line);
+    }
+}
+
+pub struct Progress {
+    mode: ProgressMode,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    /// Create a reporter; the bar falls back to plain lines when stderr is not a terminal
+    pub fn new(mode: ProgressMode) -> Self {
+        let mode = if mode == ProgressMode::Bar && !io::stderr().is_terminal() {
+            ProgressMode::Plain
+        } else {
+            mode
+        };
+        Progress { mode, last_draw: None }
+    }
+
+    /// Report that a file is about to be processed
+    pub fn file_started(&mut self, path: &str, index: usize, total_files: usize) {
+        if self.mode == ProgressMode::Json {
+            emit(&Event::FileStarted { path, index, total: total_files });
+        }
+    }
+
+    /// Report progress after a file has been processed
+    pub fn file_done(
+        &mut self,
+        path: &str,
+        bytes: usize,
+        tokens: usize,
+        telemetry: &Telemetry,
+        total_files: usize,
+        logger: &Logger,
+    ) {
+        match self.mode {
+            ProgressMode::Json => emit(&Event::FileDone {
+                path,
+                bytes,
+                tokens,
+                files_processed: telemetry.files_processed,
+                total: total_files,
+            }),
+            ProgressMode::None => {}
+            ProgressMode::Plain => {
+                if telemetry.files_processed.is_multiple_of(10) && telemetry.files_processed < total_files {
+                    logger.info(&telemetry.report(total_files));
+                }
+            }
+            ProgressMode::Bar => {
+                if self.last_draw.is_some_and(|t| t.elapsed() < REDRAW_INTERVAL) {
+                    return;
+                }
+                self.last_draw = Some(Instant::now());
+                let mut stderr = io::stderr();
+                let _ = write!(stderr, "\r\x1b[2K{}", render_bar(telemetry, total_files));
+                let _ = stderr.flush();
+            }
+        }
+    }
+
+    /// Clear any bar and report the final totals
+    pub fn finish(&mut self, telemetry: &Telemetry, total_files: usize, logger: &Logger) {
+        if self.mode == ProgressMode::None {
+            return;
+        }
+        if self.mode == ProgressMode::Json {
+            emit(&run_complete(telemetry));
+            return;
+        }
+        if self.mode == ProgressMode::Bar && self.last_draw.is_some() {
+            let _ = write!(io::stderr(), "\r\x1b[2K");
+        }
+        logger.info(&telemetry.report(total_files));
+        if let Some(breakdown) = telemetry.skip_breakdown() {
+            logger.info(&breakdown);
+        }
+    }
+}
+
+fn run_complete(telemetry: &Telemetry) -> Event<'static> {
+    Event::RunComplete {
+        files: telemetry.files_processed,
+        bytes: telemetry.bytes_read,
+        tokens: telemetry.tokens_aggregated,
+        redactions: telemetry.redactions,
+        skipped: telemetry.skipped.iter().map(|(reason, n)| (reason.to_string(), *n)).collect(),
+        elapsed_secs: telemetry.elapsed().as_secs_f64(),
+    }
+}
+
+/// The totals a `run_complete` event reports, without its `event` tag
+pub fn summary(telemetry: &Telemetry) -> serde_json::Value {
+    let mut event = serde_json::to_value(run_complete(telemetry)).unwrap_or_default();
+    if let Some(fields) = event.as_object_mut() {
+        fields.remove("event");
+    }
+    event
+}
+
+/// Render a one-line bar with percent, files, throughput, tokens, and ETA
+fn render_bar(telemetry: &Telemetry, total_files: usize) -> String {
+    let fraction = if total_files > 0 {
+        telemetry.files_processed as f64 / total_files as f64
+    } else {
+        0.0
+    };
+    let filled = ((fraction * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    let bar = format!("{}{}", "█".repeat(filled).green(), "░".repeat(BAR_WIDTH - filled).dimmed());
+
+    let (rate, tokens_per_sec) = telemetry.throughput();
+    let eta = telemetry
+        .estimate_remaining(total_files)
+        .map(|d| format!("{:.1}s", d.as_secs_f64()))
+        .unwrap_or_else(|| "--".to_string());
+
+    format!(
+        "{} {:>3.0}% | 📁 {}/{} | 📏 {}/s | 🔢 {} ({:.0}/s) | ⏳ ETA: {}",
+        bar,
+        fraction * 100.0,
+        telemetry.files_processed,
+        total_files,
+        human_bytes(rate),
+        telemetry.tokens_aggregated,
+        tokens_per_sec,
+        eta
+    )
+}
+
+/// Format a byte count with a binary unit suffix
+pub fn human_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
@@ -0,0 +1,110 @@
+//! rank.rs
+//! Scores files against a keyword query with BM25 (a TF-IDF variant) over content and path terms.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::documents;
+
+/// BM25 term-frequency saturation
+const K1: f64 = 1.2;
+/// BM25 document-length normalization
+const B: f64 = 0.75;
+/// Extra weight for a query term that appears in the file's path
+const PATH_BOOST: f64 = 2.0;
+
+/// Per-file statistics needed for scoring
+struct Document {
+    path: PathBuf,
+    length: usize,
+    /// Occurrences of each query term in the content
+    term_counts: HashMap<String, usize>,
+    /// Query terms found in the relative path
+    path_terms: Vec<String>,
+}
+
+/// Score `files` against `query`, most relevant first; files with no matching term are dropped
+pub fn rank(files: &[PathBuf], root: &Path, query: &str) -> Vec<(PathBuf, f64)> {
+    let mut query_terms = terms(query);
+    query_terms.sort();
+    query_terms.dedup();
+    if query_terms.is_empty() {
+        return Vec::new();
+    }
+
+    let docs: Vec<Document> = files
+        .iter()
+        .filter_map(|path| {
+            let content = documents::read_to_string(path).ok()?;
+            let mut term_counts = HashMap::new();
+            let mut length = 0;
+            for term in terms(&content) {
+                length += 1;
+                if query_terms.contains(&term) {
+                    *term_counts.entry(term).or_insert(0) += 1;
+                }
+            }
+            let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy().to_string();
+            let path_terms = terms(&relative).into_iter().filter(|t| query_terms.contains(t)).collect();
+            Some(Document { path: path.clone(), length, term_counts, path_terms })
+        })
+        .collect();
+    if docs.is_empty() {
+        return Vec::new();
+    }
+
+    let n = docs.len() as f64;
+    let avg_length = docs.iter().map(|d| d.length).sum::<usize>() as f64 / n;
+    let idf: HashMap<&str, f64> = query_terms
+        .iter()
+        .map(|t| {
+            let df = docs.iter().filter(|d| d.term_counts.contains_key(t) || d.path_terms.contains(t)).count() as f64;
+            (t.as_str(), ((n - df + 0.5) / (df + 0.5) + 1.0).ln())
+        })
+        .collect();
+
+    let mut scored: Vec<(PathBuf, f64)> = docs
+        .into_iter()
+        .map(|d| {
+            let norm = K1 * (1.0 - B + B * d.length as f64 / avg_length.max(1.0));
+            let mut score = 0.0;
+            for (term, &count) in &d.term_counts {
+                let tf = count as f64;
+                score += idf[term.as_str()] * tf * (K1 + 1.0) / (tf + norm);
+            }
+            for term in &d.path_terms {
+                score += idf[term.as_str()] * PATH_BOOST;
+            }
+            (d.path, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scored
+}
+
+/// Lowercased word terms; identifiers are split on `_`, `-`, and camelCase boundaries
+fn terms(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for c in word.chars() {
+            if c.is_uppercase() && prev_lower {
+                push_term(&mut out, &mut current);
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            current.extend(c.to_lowercase());
+        }
+        push_term(&mut out, &mut current);
+    }
+    out
+}
+
+fn push_term(out: &mut Vec<String>, current: &mut String) {
+    if current.chars().count() >= 2 {
+        out.push(std::mem::take(current));
+    } else {
+        current.clear();
+    }
+}
@@ -0,0 +1,58 @@
+//! redact.rs
+//! Detects credentials in file contents and replaces them with a placeholder.
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Secret patterns; when a pattern has a capture group only that group is replaced
+static SECRET_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    vec![
+        // PEM private key blocks
+        Regex::new(r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----").unwrap(),
+        // AWS access key IDs and secret access keys
+        Regex::new(r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b").unwrap(),
+        Regex::new(r#"(?i)aws_secret_access_key\s*[=:]\s*["']?([A-Za-z0-9/+=]{40})"#).unwrap(),
+        // Vendor-issued tokens (GitHub, Slack, Google, Stripe, OpenAI/Anthropic)
+        Regex::new(r"\bgh[pousr]_[A-Za-z0-9]{36,}\b").unwrap(),
+        Regex::new(r"\bgithub_pat_[A-Za-z0-9_]{22,}\b").unwrap(),
+        Regex::new(r"\bxox[abprs]-[A-Za-z0-9-]{10,}").unwrap(),
+        Regex::new(r"\bAIza[0-9A-Za-z_\-]{35}\b").unwrap(),
+        Regex::new(r"\b[sr]k_live_[0-9A-Za-z]{24,}\b").unwrap(),
+        Regex::new(r"\bsk-[A-Za-z0-9_\-]{20,}").unwrap(),
+        // JSON Web Tokens
+        Regex::new(r"\beyJ[A-Za-z0-9_\-]+\.eyJ[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+").unwrap(),
+        // .env-style assignments to secret-looking keys
+        Regex::new(r#"(?m)^\s*(?:export\s+)?[A-Z0-9_]*(?:SECRET|TOKEN|PASSWORD|PASSWD|API_?KEY|PRIVATE_KEY|ACCESS_KEY)[A-Z0-9_]*\s*[=:]\s*["']?([^\s"'#\[(),;]{8,})"#).unwrap(),
+    ]
+});
+
+/// Replace detected secrets with `[REDACTED]`, returning the new content and the number of redactions
+pub fn redact_secrets(content: &str) -> (String, usize) {
+    let mut out = content.to_string();
+    let mut count = 0;
+
+    for re in SECRET_PATTERNS.iter() {
+        if !re.is_match(&out) {
+            continue;
+        }
+        out = re
+            .replace_all(&out, |caps: &Captures| {
+                count += 1;
+                let whole = caps.get(0).unwrap();
+                match caps.get(1) {
+                    Some(secret) => {
+                        let mut s = whole.as_str().to_string();
+                        let start = secret.start() - whole.start();
+                        s.replace_range(start..start + secret.len(), REDACTED);
+                        s
+                    }
+                    None => REDACTED.to_string(),
+                }
+            })
+            .into_owned();
+    }
+
+    (out, count)
+}
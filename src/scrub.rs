@@ -0,0 +1,54 @@
+//! scrub.rs
+//! `--scrub-file`: replaces deny-listed literals and regexes (customer names, internal hostnames) with a placeholder.
+
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+const SCRUBBED: &str = "[SCRUBBED]";
+
+/// The deny-list of a run, compiled into one pattern
+pub struct Scrubber {
+    pattern: Regex,
+}
+
+impl Scrubber {
+    /// Read a scrub file: one literal per line, or a regex written as `/PATTERN/`; blank lines and `#` comments are skipped.
+    /// `None` when the file lists nothing.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let mut alternatives = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_prefix('/').and_then(|l| l.strip_suffix('/')).filter(|p| !p.is_empty()) {
+                Some(pattern) => {
+                    Regex::new(pattern).map_err(|e| format!("{}:{}: invalid regex: {}", path.display(), n + 1, e))?;
+                    alternatives.push(format!("(?:{})", pattern));
+                }
+                None => alternatives.push(regex::escape(line)),
+            }
+        }
+        if alternatives.is_empty() {
+            return Ok(None);
+        }
+        let pattern = Regex::new(&alternatives.join("|")).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Some(Scrubber { pattern }))
+    }
+
+    /// Replace every match with `[SCRUBBED]`, returning the new text and the number of replacements
+    pub fn scrub(&self, text: &str) -> (String, usize) {
+        let count = self.pattern.find_iter(text).filter(|m| !m.is_empty()).count();
+        if count == 0 {
+            return (text.to_string(), 0);
+        }
+        let out = self.pattern.replace_all(text, |caps: &regex::Captures| match caps[0].is_empty() {
+            true => String::new(),
+            false => SCRUBBED.to_string(),
+        });
+        (out.into_owned(), count)
+    }
+}
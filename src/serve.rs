@@ -0,0 +1,374 @@
+//! serve.rs
+//! `bound serve`: a Model Context Protocol server on stdin/stdout (`--mcp`) or an HTTP `POST /pack` endpoint (`--http`).
+//! Requests run in this process, confined to the directory the server was started in, and share its tokenizers and token caches.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::cli::Cli;
+use crate::logging::Logger;
+use crate::{cache, walk, Captured};
+
+/// MCP revision spoken when the client asks for one we don't know
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const KNOWN_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+/// Largest `POST /pack` body accepted
+const MAX_REQUEST_BYTES: usize = 1 << 20;
+
+/// HTTP connections answered at once; later ones wait in the listen backlog
+const WORKERS: usize = 4;
+
+/// How long a client may stall while sending its request or reading the reply
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Arguments accepted by every tool and by `POST /pack`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolArgs {
+    /// Directory or file to pack, inside the server's working directory (default: all of it)
+    #[serde(alias = "root")]
+    pub path: Option<String>,
+    /// Language filter such as `[rs,toml]` or `{py}`
+    #[serde(alias = "filters")]
+    pub filter: Option<String>,
+    pub budget: Option<usize>,
+    pub model: Option<String>,
+    /// Bundle format: text, json, jsonl, or html
+    pub format: Option<String>,
+}
+
+impl ToolArgs {
+    /// Options, then `--` and the filter and path, so neither can be read as a flag
+    fn command_line(&self, options: &[&str], root: &Path) -> Result<Vec<String>, String> {
+        let mut args: Vec<String> = options.iter().map(|s| s.to_string()).collect();
+        if let Some(budget) = self.budget {
+            args.extend(["--budget".to_string(), budget.to_string()]);
+        }
+        if let Some(model) = &self.model {
+            args.extend(["--model".to_string(), model.clone()]);
+        }
+        args.push("--".to_string());
+        if let Some(filter) = &self.filter {
+            if !(filter.starts_with('[') || filter.starts_with('{')) {
+                return Err(format!("filter {:?} should look like [rs,py] or {{rs}}", filter));
+            }
+            args.push(filter.clone());
+        }
+        args.push(self.confined_path(root)?.to_string_lossy().into_owned());
+        Ok(args)
+    }
+
+    /// `path` resolved against `root`, refused when it (or a symlink in it) leads outside
+    fn confined_path(&self, root: &Path) -> Result<PathBuf, String> {
+        let path = self.path.as_deref().unwrap_or(".");
+        let resolved = walk::canonicalize(&root.join(path)).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        if !resolved.starts_with(root) {
+            return Err(format!("{} is outside the served directory {}", path, root.display()));
+        }
+        Ok(resolved)
+    }
+}
+
+/// The directory requests are confined to, and where the shared caches are turned on
+fn served_root() -> io::Result<PathBuf> {
+    cache::share_across_runs();
+    walk::canonicalize(&std::env::current_dir()?)
+}
+
+/// Serve MCP requests, one JSON-RPC message per line, until stdin closes
+pub fn mcp(logger: &Logger) -> io::Result<()> {
+    let root = served_root()?;
+    logger.info(&format!("Serving MCP on stdin/stdout for {}", root.display()));
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(&message, &root, logger),
+            Err(e) => Some(error(Value::Null, -32700, &format!("Parse error: {}", e))),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Response to one message; notifications get none
+fn handle(message: &Value, root: &Path, logger: &Logger) -> Option<Value> {
+    let method = message["method"].as_str().unwrap_or("");
+    let id = message.get("id").cloned()?;
+    let params = &message["params"];
+    logger.debug(&format!("MCP request {}", method));
+    let result = match method {
+        "initialize" => {
+            let requested = params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION);
+            let version = if KNOWN_VERSIONS.contains(&requested) { requested } else { PROTOCOL_VERSION };
+            json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "bound", "version": env!("CARGO_PKG_VERSION") },
+            })
+        }
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let name = params["name"].as_str().unwrap_or("");
+            let outcome = serde_json::from_value::<ToolArgs>(params.get("arguments").cloned().unwrap_or(json!({})))
+                .map_err(|e| format!("Invalid arguments: {}", e))
+                .and_then(|args| call(name, &args, root));
+            if let Err(e) = &outcome {
+                logger.warn(&format!("{} failed: {}", name, e));
+            }
+            let (text, is_error) = match outcome {
+                Ok(text) => (text, false),
+                Err(e) => (e, true),
+            };
+            json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+        }
+        _ => return Some(error(id, -32601, &format!("Method not found: {}", method))),
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Tool descriptions for `tools/list`
+fn tools() -> Value {
+    let path = json!({ "type": "string", "description": "Directory or file to pack, inside the server's working directory (default: .)" });
+    let filter = json!({ "type": "string", "description": "Language filter: [rs,py] keeps those extensions, {rs} also follows imports" });
+    let model = json!({ "type": "string", "enum": ["gpt-4o", "claude-sonnet", "llama-70b"], "description": "Model whose tokenizer to count with" });
+    json!([
+        {
+            "name": "pack_directory",
+            "description": "Aggregate a directory's files into one bundle with a file tree, as bound would write it",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": path,
+                    "filter": filter,
+                    "budget": { "type": "integer", "minimum": 1, "description": "Token budget for the bundle (default: the model's context window)" },
+                    "model": model,
+                    "format": { "type": "string", "enum": ["text", "json", "jsonl", "html"], "description": "Bundle format (default: text)" },
+                },
+            },
+        },
+        {
+            "name": "count_tokens",
+            "description": "Total tokens of the selected files, broken down by directory and extension, as JSON",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "path": path, "filter": filter, "model": model },
+            },
+        },
+        {
+            "name": "list_files",
+            "description": "The files bound would pack, with their token counts, as JSON",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "path": path, "filter": filter, "model": model },
+            },
+        },
+    ])
+}
+
+/// Run a tool, returning its text or an error message
+fn call(name: &str, args: &ToolArgs, root: &Path) -> Result<String, String> {
+    let printed = |options: &[&str]| {
+        let captured = run_in_process(&args.command_line(options, root)?)?;
+        Ok(String::from_utf8_lossy(&captured.stdout).into_owned())
+    };
+    match name {
+        "pack_directory" => pack(args, root).map(|captured| captured.bundle),
+        "count_tokens" => printed(&["count", "--quiet", "--yes", "--json"]),
+        "list_files" => printed(&["--quiet", "--yes", "--list", "--json"]),
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+/// Pack in-process, returning the bundle with the run's statistics
+fn pack(args: &ToolArgs, root: &Path) -> Result<Captured, String> {
+    let mut options = vec!["--quiet", "--yes", "--tree"];
+    if let Some(format) = &args.format {
+        options.extend(["--format", format.as_str()]);
+    }
+    run_in_process(&args.command_line(&options, root)?)
+}
+
+/// Run bound with `args` in this process, collecting what it would print or write
+fn run_in_process(args: &[String]) -> Result<Captured, String> {
+    let cli = Cli::try_parse_from(std::iter::once("bound").chain(args.iter().map(String::as_str))).map_err(|e| {
+        // The first line of clap's report, without its `error: ` prefix and usage block
+        let rendered = e.to_string();
+        let line = rendered.lines().next().unwrap_or_default();
+        line.strip_prefix("error: ").unwrap_or(line).to_string()
+    })?;
+    let mut captured = Captured::default();
+    crate::run(cli, Some(&mut captured)).map_err(|e| e.to_string())?;
+    Ok(captured)
+}
+
+/// Serve `POST /pack` on `addr` from a fixed pool of worker threads
+pub fn http(addr: &str, logger: &Logger) -> io::Result<()> {
+    let root = served_root()?;
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    if !local.ip().is_loopback() {
+        logger.warn(&format!("{} is reachable from other machines; anyone who can connect can read files bound can see", local.ip()));
+    }
+    logger.info(&format!("Serving POST http://{}/pack for {}", local, root.display()));
+    std::thread::scope(|scope| {
+        for _ in 0..WORKERS {
+            scope.spawn(|| {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            if let Err(e) = answer(stream, &root, logger) {
+                                logger.warn(&format!("HTTP connection failed: {}", e));
+                            }
+                        }
+                        Err(e) => logger.warn(&format!("HTTP accept failed: {}", e)),
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// Whether a `Host` header names this machine as `localhost` or by address. Any other name may be an
+/// attacker's domain rebound to a local address, letting a web page read the reply.
+fn is_local_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(""),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok()
+}
+
+/// Read one request from `stream` and reply to it
+fn answer(stream: TcpStream, root: &Path, logger: &Logger) -> io::Result<()> {
+    let started = Instant::now();
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = 0;
+    let (mut host, mut origin) = (String::new(), None);
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let (name, value) = (name.trim(), value.trim());
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("host") {
+                host = value.to_string();
+            } else if name.eq_ignore_ascii_case("origin") {
+                origin = Some(value.to_string());
+            }
+        }
+    }
+
+    // A browser sends `Origin` with cross-site requests; only pages served from this same host may call in
+    let cross_site = origin.is_some_and(|o| !o.split_once("://").is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(&host)));
+    let (status, body) = if !is_local_host(&host) {
+        (403, json!({ "error": format!("Host {:?} is not allowed; connect by localhost or IP address", host) }))
+    } else if cross_site {
+        (403, json!({ "error": "cross-origin requests are not allowed" }))
+    } else if target.split('?').next() != Some("/pack") {
+        (404, json!({ "error": format!("no route for {}; use POST /pack", target) }))
+    } else if method != "POST" {
+        (405, json!({ "error": "use POST /pack" }))
+    } else if content_length > MAX_REQUEST_BYTES {
+        (413, json!({ "error": format!("request body over {} bytes", MAX_REQUEST_BYTES) }))
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        let args = match content_length {
+            0 => Ok(ToolArgs::default()),
+            _ => serde_json::from_slice::<ToolArgs>(&body).map_err(|e| e.to_string()),
+        };
+        match args {
+            Err(e) => (400, json!({ "error": format!("invalid request: {}", e) })),
+            Ok(args) => match pack(&args, root) {
+                Ok(packed) => (200, json!({ "bundle": packed.bundle, "stats": packed.stats })),
+                Err(e) => (422, json!({ "error": e })),
+            },
+        }
+    };
+    logger.info(&format!("{} {} -> {} in {:.2}s", method, target, status, started.elapsed().as_secs_f64()));
+
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Unprocessable Entity",
+    };
+    let body = body.to_string();
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn host_must_be_localhost_or_an_address() {
+        for host in ["localhost", "LOCALHOST:7878", "127.0.0.1:7878", "[::1]:7878", "192.168.1.20"] {
+            assert!(is_local_host(host), "{}", host);
+        }
+        for host in ["", "evil.example", "evil.example:7878", "localhost.evil.example", "[evil]:7878"] {
+            assert!(!is_local_host(host), "{}", host);
+        }
+    }
+
+    #[test]
+    fn pack_directory_bundles_a_file_tree() {
+        let description = tools()[0]["description"].as_str().unwrap().to_string();
+        assert!(description.contains("file tree"), "{}", description);
+
+        let root = std::env::temp_dir().join(format!("bound-serve-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        let root = walk::canonicalize(&root).unwrap();
+        let bundle = pack(&ToolArgs::default(), &root).map(|captured| captured.bundle);
+        fs::remove_dir_all(&root).unwrap();
+        let bundle = bundle.unwrap();
+        assert!(bundle.contains("type: tree"), "{}", bundle);
+        assert!(bundle.contains("fn main()") && bundle.contains("pub fn lib()"), "{}", bundle);
+    }
+}
@@ -0,0 +1,108 @@
+//! share.rs
+//! Sends the bundle over HTTP through `curl`, so no HTTP stack is linked in: as a secret GitHub gist (`--out gist`)
+//! or as the body of a `--post` request.
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use serde_json::json;
+
+use crate::cli::OutputFormat;
+use crate::output;
+
+const GIST_API: &str = "https://api.github.com/gists";
+
+/// A GitHub token from `$GITHUB_TOKEN`, `$GH_TOKEN`, or the GitHub CLI's login
+pub fn github_token() -> Option<String> {
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|t| t.trim().to_string())
+        .find(|t| !t.is_empty())
+        .or_else(|| {
+            let output = Command::new("gh").args(["auth", "token"]).stderr(Stdio::null()).output().ok()?;
+            let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            (output.status.success() && !token.is_empty()).then_some(token)
+        })
+}
+
+/// Upload `content` as a secret gist holding one file, returning the gist's URL
+pub fn upload_gist(token: &str, file_name: &str, description: &str, content: &str) -> Result<String, Box<dyn Error>> {
+    let body = json!({
+        "description": description,
+        "public": false,
+        "files": { file_name: { "content": content } },
+    });
+    let headers = [
+        format!("Authorization: Bearer {}", token),
+        "Accept: application/vnd.github+json".to_string(),
+        "Content-Type: application/json".to_string(),
+    ];
+    let (status, response) = post(GIST_API, &headers, body.to_string().as_bytes())?;
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap_or_default();
+    if !(200..300).contains(&status) {
+        let message = response["message"].as_str().unwrap_or("no message");
+        return Err(format!("GitHub rejected the gist (HTTP {}): {}", status, message).into());
+    }
+    response["html_url"].as_str().map(str::to_string).ok_or_else(|| "GitHub's response had no gist URL".into())
+}
+
+/// POST `body` to `url` with extra `Name: value` headers, returning the status code and response body.
+/// The body is streamed to curl's stdin, so the bundle never touches the disk; headers go through a private config
+/// file rather than curl's arguments, keeping tokens out of `ps`.
+pub fn post(url: &str, headers: &[String], body: &[u8]) -> io::Result<(u16, String)> {
+    let mut config = format!("url = {}\ndata-binary = \"@-\"\n", quote(url));
+    for header in headers {
+        config.push_str(&format!("header = {}\n", quote(header)));
+    }
+    let (config_path, mut file) = output::create_private_temp("bound-curl", "conf")?;
+    let written = file.write_all(config.as_bytes());
+    drop(file);
+
+    let result = written.and_then(|()| {
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--request", "POST", "--write-out", "\n%{http_code}", "--config"])
+            .arg(&config_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        std::thread::scope(|scope| {
+            // Fed from another thread so a response curl prints early can't fill its stdout and stall the upload;
+            // a curl that exits early breaks the pipe, and its exit status says why
+            scope.spawn(move || stdin.write_all(body));
+            child.wait_with_output()
+        })
+    });
+    let _ = fs::remove_file(&config_path);
+    let output = result.map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => io::Error::new(e.kind(), "curl is not installed"),
+        _ => e,
+    })?;
+    if !output.status.success() {
+        return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (response, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let status = status.trim().parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "curl reported no HTTP status"))?;
+    Ok((status, response.to_string()))
+}
+
+/// Default `Content-Type` for a bundle in `format`
+pub fn content_type(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Text => "text/markdown; charset=utf-8",
+        OutputFormat::Json => "application/json",
+        OutputFormat::Jsonl => "application/x-ndjson",
+        OutputFormat::Html => "text/html; charset=utf-8",
+    }
+}
+
+/// A double-quoted curl config value
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
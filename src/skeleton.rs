@@ -0,0 +1,122 @@
+//! skeleton.rs
+//! Reduces source files to imports, type definitions, and signatures by eliding function bodies (tree-sitter).
+
+use std::ops::Range;
+
+use tree_sitter::{Language, Node, Parser, Tree};
+
+/// How function bodies are replaced for a language
+#[derive(Clone, Copy)]
+enum BodyStyle {
+    /// `{ ... }`
+    Braces,
+    /// `...` on the body's own line, keeping a leading docstring
+    Indented,
+}
+
+struct Grammar {
+    language: Language,
+    /// Node kinds whose `body` field is elided
+    functions: &'static [&'static str],
+    style: BodyStyle,
+}
+
+const JS_FUNCTIONS: &[&str] = &[
+    "function_declaration",
+    "function_expression",
+    "generator_function_declaration",
+    "method_definition",
+    "arrow_function",
+];
+
+fn grammar_for(ext: &str) -> Option<Grammar> {
+    let (language, functions, style): (Language, &'static [&'static str], BodyStyle) = match ext {
+        "rs" => (tree_sitter_rust::LANGUAGE.into(), &["function_item"], BodyStyle::Braces),
+        "py" | "pyi" => (tree_sitter_python::LANGUAGE.into(), &["function_definition"], BodyStyle::Indented),
+        "js" | "jsx" | "mjs" | "cjs" => (tree_sitter_javascript::LANGUAGE.into(), JS_FUNCTIONS, BodyStyle::Braces),
+        "ts" => (tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(), JS_FUNCTIONS, BodyStyle::Braces),
+        "tsx" => (tree_sitter_typescript::LANGUAGE_TSX.into(), JS_FUNCTIONS, BodyStyle::Braces),
+        "go" => (
+            tree_sitter_go::LANGUAGE.into(),
+            &["function_declaration", "method_declaration", "func_literal"],
+            BodyStyle::Braces,
+        ),
+        _ => return None,
+    };
+    Some(Grammar { language, functions, style })
+}
+
+/// Parse `content` with the grammar for `ext`, if bound supports it
+pub fn parse(content: &str, ext: &str) -> Option<Tree> {
+    let grammar = grammar_for(ext)?;
+    let mut parser = Parser::new();
+    parser.set_language(&grammar.language).ok()?;
+    parser.parse(content, None)
+}
+
+/// Replace every function body with `...`; `None` if the language is unsupported
+pub fn skeletonize(content: &str, ext: &str) -> Option<String> {
+    let tree = parse(content, ext)?;
+    Some(splice(content, 0..content.len(), &body_elisions(tree.root_node(), ext)))
+}
+
+/// Body replacements for every function under `node`
+pub fn body_elisions(node: Node, ext: &str) -> Vec<(usize, usize, String)> {
+    let mut elisions = Vec::new();
+    if let Some(grammar) = grammar_for(ext) {
+        collect_bodies(node, &grammar, &mut elisions);
+    }
+    elisions
+}
+
+/// The text of `range` with byte ranges (sorted, non-overlapping, inside `range`) replaced
+pub fn splice(content: &str, range: Range<usize>, edits: &[(usize, usize, String)]) -> String {
+    let mut out = String::with_capacity(range.len());
+    let mut pos = range.start;
+    for (start, end, replacement) in edits {
+        out.push_str(&content[pos..*start]);
+        out.push_str(replacement);
+        pos = *end;
+    }
+    out.push_str(&content[pos..range.end]);
+    out
+}
+
+/// Find function bodies in document order without descending into elided ones
+fn collect_bodies(node: Node, grammar: &Grammar, out: &mut Vec<(usize, usize, String)>) {
+    if grammar.functions.contains(&node.kind()) {
+        if let Some(body) = node.child_by_field_name("body") {
+            if let Some(edit) = elide(body, grammar.style) {
+                out.push(edit);
+                return;
+            }
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_bodies(child, grammar, out);
+    }
+}
+
+fn elide(body: Node, style: BodyStyle) -> Option<(usize, usize, String)> {
+    match style {
+        // Arrow functions with expression bodies are already signature-sized
+        BodyStyle::Braces if body.kind() == "block" || body.kind() == "statement_block" => {
+            Some((body.start_byte(), body.end_byte(), "{ ... }".to_string()))
+        }
+        BodyStyle::Braces => None,
+        BodyStyle::Indented => {
+            let indent = " ".repeat(body.start_position().column);
+            let first = body.named_child(0)?;
+            let docstring = first.kind() == "expression_statement"
+                && first.named_child(0).is_some_and(|n| n.kind() == "string");
+            if !docstring {
+                return Some((body.start_byte(), body.end_byte(), "...".to_string()));
+            }
+            if body.named_child_count() == 1 {
+                return None;
+            }
+            Some((first.end_byte(), body.end_byte(), format!("\n{}...", indent)))
+        }
+    }
+}
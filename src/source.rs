@@ -0,0 +1,155 @@
+//! source.rs
+//! Resolves the aggregation target, shallow-cloning remote git repositories and extracting archives to a temp directory.
+
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::walk;
+
+/// Archive suffixes extracted with `tar`; `.zip` goes to `unzip`
+const TAR_SUFFIXES: &[&str] = &[".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.xz", ".txz", ".tar.zst"];
+
+/// A directory to aggregate; temporary checkouts and extractions are deleted when dropped
+pub struct Source {
+    pub path: PathBuf,
+    temporary: Option<PathBuf>,
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.temporary {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Whether the target looks like a git remote rather than a local path
+pub fn is_remote(target: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "file://", "git@"]
+        .iter()
+        .any(|prefix| target.starts_with(prefix))
+}
+
+/// Repository name from a git URL (`https://host/org/repo.git` -> `repo`)
+pub fn remote_name(target: &str) -> Option<&str> {
+    let last = target.trim_end_matches('/').rsplit(['/', ':']).next()?;
+    let name = last.strip_suffix(".git").unwrap_or(last);
+    (!name.is_empty()).then_some(name)
+}
+
+/// Whether the target is an archive file to extract
+pub fn is_archive(target: &Path) -> bool {
+    let name = target.to_string_lossy().to_lowercase();
+    target.is_file() && (name.ends_with(".zip") || TAR_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)))
+}
+
+/// Resolve `target` to a local directory, cloning it first if it is a git URL or extracting it if it is an archive
+pub fn acquire(target: &Path) -> io::Result<Source> {
+    let target_str = target.to_string_lossy();
+    if is_archive(target) {
+        return extract(target);
+    }
+    if !is_remote(&target_str) {
+        return Ok(Source {
+            path: walk::canonicalize(target)?,
+            temporary: None,
+        });
+    }
+
+    // git clones into an existing directory as long as it is empty
+    let dest = create_temp_dir()?;
+    // Construct the guard first so a partial clone is still cleaned up
+    let mut source = Source {
+        path: dest.clone(),
+        temporary: Some(dest.clone()),
+    };
+    let status = Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet"])
+        .arg(target)
+        .arg(&dest)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("git clone {} failed", target_str)));
+    }
+
+    source.path = walk::canonicalize(&source.path)?;
+    Ok(source)
+}
+
+/// Extract an archive to a temp directory, descending into its top directory when it has just one
+fn extract(archive: &Path) -> io::Result<Source> {
+    let dest = create_temp_dir()?;
+    let mut source = Source {
+        path: dest.clone(),
+        temporary: Some(dest.clone()),
+    };
+    let mut command = if archive.to_string_lossy().to_lowercase().ends_with(".zip") {
+        let mut command = Command::new("unzip");
+        command.arg("-q").arg(archive).arg("-d").arg(&dest);
+        command
+    } else {
+        let mut command = Command::new("tar");
+        command.arg("-xf").arg(archive).arg("-C").arg(&dest);
+        command
+    };
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command.status().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => io::Error::new(e.kind(), format!("{} is not installed", program)),
+        _ => e,
+    })?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} could not extract {}", program, archive.display())));
+    }
+
+    let entries: Vec<PathBuf> = fs::read_dir(&dest)?.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    if let [only] = entries.as_slice() {
+        if only.is_dir() {
+            source.path = only.clone();
+        }
+    }
+    source.path = walk::canonicalize(&source.path)?;
+    Ok(source)
+}
+
+/// A new directory under the temp directory, private to this user. Its name is random and `create_dir` fails on an
+/// existing path, so a directory planted at a guessed name is never used.
+fn create_temp_dir() -> io::Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let mut attempts = 0;
+    loop {
+        let random = RandomState::new().hash_one(attempts);
+        let path = std::env::temp_dir().join(format!("bound-{:016x}", random));
+        match builder.create(&path) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 8 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_dirs_are_fresh_and_private() {
+        let (a, b) = (create_temp_dir().unwrap(), create_temp_dir().unwrap());
+        assert_ne!(a, b);
+        assert!(a.is_dir() && fs::read_dir(&a).unwrap().next().is_none());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&a).unwrap().permissions().mode() & 0o777, 0o700);
+        }
+        // A path that already exists is refused rather than reused
+        assert_eq!(fs::DirBuilder::new().create(&a).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        fs::remove_dir(&a).unwrap();
+        fs::remove_dir(&b).unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+//! summarize.rs
+//! `--summarize-over`/`--summarize-cmd`: replaces files over a token threshold with a summary from an external
+//! command, cached on disk by a hash of the command and the content.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::cache;
+use crate::metadata::sha256_hex;
+use crate::model::Tokenizer;
+use crate::plugins::run_filter;
+
+/// The summarizer command, the threshold that triggers it, and where summaries are cached
+pub struct Summarizer {
+    command: String,
+    threshold: usize,
+    dir: Option<PathBuf>,
+}
+
+impl Summarizer {
+    /// With `cached` false (for `--no-cache`), summaries are neither read from nor written to disk
+    pub fn new(command: &str, threshold: usize, cached: bool) -> Self {
+        let dir = cached.then(cache::cache_dir).flatten().map(|dir| dir.join("summaries"));
+        Summarizer { command: command.to_string(), threshold, dir }
+    }
+
+    /// A summary of `content` when it has more than `threshold` tokens, else `None`
+    pub fn summarize(&self, relative: &str, content: &str, tokenizer: &Tokenizer) -> Result<Option<String>, String> {
+        if tokenizer.count(content) <= self.threshold {
+            return Ok(None);
+        }
+        let key = sha256_hex(format!("{}\0{}", self.command, content).as_bytes());
+        let cached = self.dir.as_ref().map(|dir| dir.join(format!("{}.txt", key)));
+        if let Some(summary) = cached.as_ref().and_then(|file| fs::read_to_string(file).ok()) {
+            return Ok(Some(summary));
+        }
+        let summary = run_filter(&self.command, relative, content, None)
+            .map_err(|e| format!("`{}` failed on {}: {}", self.command, relative, e))?;
+        let summary = summary.trim().to_string();
+        if summary.is_empty() {
+            return Err(format!("`{}` printed no summary for {}", self.command, relative));
+        }
+        if let Some(file) = cached {
+            // A summary that can't be cached is still used
+            let _ = file.parent().map(fs::create_dir_all);
+            let _ = fs::write(&file, &summary);
+        }
+        Ok(Some(summary))
+    }
+}
@@ -0,0 +1,62 @@
+//! tabular.rs
+//! `--csv-rows`: shortens CSV and TSV files to their header and first rows, with a row and column count.
+
+/// The field delimiter of a tabular extension, or `None` for other files
+pub fn delimiter(ext: &str) -> Option<char> {
+    match ext.to_ascii_lowercase().as_str() {
+        "csv" => Some(','),
+        "tsv" | "tab" => Some('\t'),
+        _ => None,
+    }
+}
+
+/// The header and first `rows` records of `content`, followed by a summary line when records were dropped.
+/// Quoted fields may hold delimiters and newlines.
+pub fn preview(content: &str, delimiter: char, rows: usize) -> String {
+    let records = records(content, delimiter);
+    let Some((header, columns)) = records.first() else {
+        return content.to_string();
+    };
+    let data_rows = records.len() - 1;
+    if data_rows <= rows {
+        return content.to_string();
+    }
+    let mut out = content[header.start..records[rows].0.end].to_string();
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "... [{} more rows omitted; {} rows x {} columns in total] ...\n",
+        data_rows - rows,
+        data_rows,
+        columns
+    ));
+    out
+}
+
+/// Byte span (including the line break) and field count of each non-empty record
+fn records(content: &str, delimiter: char) -> Vec<(std::ops::Range<usize>, usize)> {
+    let mut records = Vec::new();
+    let (mut start, mut fields, mut quoted, mut blank) = (0, 1, false, true);
+    for (i, c) in content.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields += 1,
+            '\n' if !quoted => {
+                if !blank {
+                    records.push((start..i + 1, fields));
+                }
+                (start, fields, blank) = (i + 1, 1, true);
+                continue;
+            }
+            _ => {}
+        }
+        if c != '\r' {
+            blank = false;
+        }
+    }
+    if !blank {
+        records.push((start..content.len(), fields));
+    }
+    records
+}
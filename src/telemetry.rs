@@ -1,15 +1,102 @@
 
 //! telemetry.rs
-//! Tracks file processing progress, bytes read, tokens aggregated, and estimated remaining time.
+//! Tracks file processing progress, bytes read, tokens aggregated, throughput, and estimated remaining time.
 
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
 use std::time::{Duration, Instant};
 use colored::Colorize;
 
+use crate::documents::DocumentFormat;
+use crate::encoding::UnknownEncoding;
+
+/// Why a file was left out of the aggregation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SkipReason {
+    Filtered,
+    Binary,
+    TooDeep,
+    Ignored,
+    Unreadable,
+    TooLarge,
+    NoMatch,
+    Generated,
+    Lock,
+    Vendored,
+    UnknownEncoding,
+    OutsideWindow,
+    OtherAuthor,
+    Unchanged,
+    TimedOut,
+    Special,
+    HardLink,
+}
+
+impl SkipReason {
+    /// Classify a read error: undecodable data is binary or of unknown encoding, anything else is unreadable
+    pub fn from_io_error(err: &io::Error) -> Self {
+        if err.kind() != io::ErrorKind::InvalidData {
+            SkipReason::Unreadable
+        } else if err.get_ref().is_some_and(|e| e.is::<UnknownEncoding>()) {
+            SkipReason::UnknownEncoding
+        } else {
+            SkipReason::Binary
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            SkipReason::Filtered => "filtered",
+            SkipReason::Binary => "binary",
+            SkipReason::TooDeep => "too deep",
+            SkipReason::Ignored => "ignored",
+            SkipReason::Unreadable => "unreadable",
+            SkipReason::TooLarge => "too large",
+            SkipReason::NoMatch => "no grep match",
+            SkipReason::Generated => "generated",
+            SkipReason::Lock => "lock file",
+            SkipReason::Vendored => "vendored",
+            SkipReason::UnknownEncoding => "unknown encoding",
+            SkipReason::OutsideWindow => "outside --since/--until",
+            SkipReason::OtherAuthor => "not by --author",
+            SkipReason::Unchanged => "unchanged",
+            SkipReason::TimedOut => "timed out",
+            SkipReason::Special => "special file",
+            SkipReason::HardLink => "hard link",
+        };
+        f.write_str(s)
+    }
+}
+
 #[derive(Debug)]
 pub struct Telemetry {
     pub files_processed: usize,
     pub bytes_read: usize,
     pub tokens_aggregated: usize,
+    /// Source bytes of every file to process, from the pre-scan
+    pub total_bytes: u64,
+    /// Source bytes of the files handled so far, including skipped ones
+    pub bytes_done: u64,
+    pub redactions: usize,
+    /// Files and directories left out, by reason
+    pub skipped: BTreeMap<SkipReason, usize>,
+    /// Files replaced by an "identical to" stub (`--dedup`) and the tokens that saved
+    pub duplicates: usize,
+    pub dedup_tokens_saved: usize,
+    /// Files and tokens of text extracted from documents, by format
+    pub documents: BTreeMap<DocumentFormat, (usize, usize)>,
+    /// Files replaced by a summary (`--summarize-over`) and the tokens that saved
+    pub summarized: usize,
+    pub summary_tokens_saved: usize,
+    /// Files read from another encoding, and images listed as placeholders
+    pub transcoded: usize,
+    pub image_placeholders: usize,
+    /// Deny-listed occurrences removed (`--scrub-file`) and paths or terms masked (`--anonymize`)
+    pub scrubbed: usize,
+    pub anonymized: usize,
     pub start_time: Instant,
 }
 
@@ -20,25 +107,72 @@ impl Telemetry {
             files_processed: 0,
             bytes_read: 0,
             tokens_aggregated: 0,
+            total_bytes: 0,
+            bytes_done: 0,
+            redactions: 0,
+            skipped: BTreeMap::new(),
+            duplicates: 0,
+            dedup_tokens_saved: 0,
+            documents: BTreeMap::new(),
+            summarized: 0,
+            summary_tokens_saved: 0,
+            transcoded: 0,
+            image_placeholders: 0,
+            scrubbed: 0,
+            anonymized: 0,
             start_time: Instant::now(),
         }
     }
 
+    /// Count a file or directory left out of the bundle
+    pub fn record_skip(&mut self, reason: SkipReason) {
+        *self.skipped.entry(reason).or_default() += 1;
+    }
+
+    /// Total files and directories left out
+    pub fn skipped_total(&self) -> usize {
+        self.skipped.values().sum()
+    }
+
+    /// "Skipped N path(s): 8 filtered, 2 binary, ..." or `None` when nothing was skipped
+    pub fn skip_breakdown(&self) -> Option<String> {
+        if self.skipped.is_empty() {
+            return None;
+        }
+        let parts: Vec<String> = self.skipped.iter().map(|(reason, n)| format!("{} {}", n, reason)).collect();
+        Some(format!("Skipped {} path(s): {}", self.skipped_total(), parts.join(", ")))
+    }
+
     /// Returns elapsed time since start
     pub fn elapsed(&self) -> Duration {
         self.start_time.elapsed()
     }
 
-    /// Estimate remaining time based on files processed
+    /// Estimate remaining time from the source bytes left, falling back to the file count
+    /// when sizes are unknown (e.g. all files are empty)
     pub fn estimate_remaining(&self, total_files: usize) -> Option<Duration> {
         if self.files_processed == 0 || self.files_processed >= total_files {
             return None;
         }
+        let elapsed = self.elapsed().as_secs_f64();
+        if self.total_bytes > 0 && self.bytes_done > 0 {
+            let remaining_bytes = self.total_bytes.saturating_sub(self.bytes_done);
+            return Some(Duration::from_secs_f64(elapsed / self.bytes_done as f64 * remaining_bytes as f64));
+        }
         let remaining_files = total_files.saturating_sub(self.files_processed);
-        let avg_per_file = self.elapsed().as_secs_f64() / self.files_processed as f64;
+        let avg_per_file = elapsed / self.files_processed as f64;
         Some(Duration::from_secs_f64(avg_per_file * remaining_files as f64))
     }
 
+    /// Bytes read and tokens aggregated per second so far
+    pub fn throughput(&self) -> (f64, f64) {
+        let secs = self.elapsed().as_secs_f64();
+        if secs <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (self.bytes_read as f64 / secs, self.tokens_aggregated as f64 / secs)
+    }
+
     /// Generate a progress report string
     pub fn report(&self, total_files: usize) -> String {
         let ebt_str = self.estimate_remaining(total_files)
@@ -52,12 +186,44 @@ impl Telemetry {
             "--%".to_string()
         };
 
+        let redacted = if self.redactions > 0 {
+            format!(" | 🔒 Redacted: {}", self.redactions)
+        } else {
+            String::new()
+        };
+
+        let skipped = if !self.skipped.is_empty() {
+            format!(" | 🚫 Skipped: {}", self.skipped_total())
+        } else {
+            String::new()
+        };
+
+        let deduplicated = if self.duplicates > 0 {
+            format!(" | ♻️ Duplicates: {} (-{} tokens)", self.duplicates, self.dedup_tokens_saved)
+        } else {
+            String::new()
+        };
+
+        let summarized = if self.summarized > 0 {
+            format!(" | 📝 Summarized: {} (-{} tokens)", self.summarized, self.summary_tokens_saved)
+        } else {
+            String::new()
+        };
+
+        let (bytes_per_sec, tokens_per_sec) = self.throughput();
+
         format!(
-            "[{} | 📁 Files: {} | 📏 Bytes: {} | 🔢 Tokens: {} | ⏳ EBT: {}]",
+            "[{} | 📁 Files: {} | 📏 Bytes: {} | 🔢 Tokens: {}{}{}{}{} | ⚡ {:.1} MB/s, {:.0} tok/s | ⏳ EBT: {}]",
             progress,
             self.files_processed,
             self.bytes_read,
             self.tokens_aggregated,
+            redacted,
+            skipped,
+            deduplicated,
+            summarized,
+            bytes_per_sec / 1e6,
+            tokens_per_sec,
             ebt_str
         )
     }
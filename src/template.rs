@@ -0,0 +1,81 @@
+//! template.rs
+//! User-defined per-file wrappers (`--template`) replacing the default `expandable{}` file block.
+
+/// Placeholders a template may use
+const VARIABLES: &[&str] = &["path", "ext", "size", "tokens", "hash", "truncated", "content"];
+
+/// A parsed `--template` string
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Var(&'static str),
+}
+
+impl Template {
+    /// Parse a template: `{name}` placeholders, `{{`/`}}` for literal braces, and `\n`/`\t` escapes
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    let var = VARIABLES
+                        .iter()
+                        .find(|v| **v == name)
+                        .ok_or_else(|| format!("unknown template variable '{{{}}}' (expected one of {})", name, VARIABLES.join(", ")))?;
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                    parts.push(Part::Var(var));
+                }
+                _ => text.push(c),
+            }
+        }
+        parts.push(Part::Text(text));
+        Ok(Template { parts })
+    }
+
+    /// Whether the template refers to `name`
+    pub fn uses(&self, name: &str) -> bool {
+        self.parts.iter().any(|p| matches!(p, Part::Var(v) if *v == name))
+    }
+
+    /// Fill in the placeholders; missing values render empty
+    pub fn render(&self, values: &[(&str, &str)]) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Var(name) => {
+                    if let Some((_, value)) = values.iter().find(|(k, _)| k == name) {
+                        out.push_str(value);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
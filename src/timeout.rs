@@ -0,0 +1,28 @@
+//! timeout.rs
+//! `--file-timeout`: runs a file's read or transform on a worker thread and gives up on it after a limit.
+
+use std::panic;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Run `work` and return its result, or `None` if it didn't finish within `limit` (no limit runs it inline).
+/// A timed-out worker can't be cancelled (a read from a FIFO blocks in the kernel); it is left behind and ends with the process.
+pub fn run<T: Send + 'static>(limit: Option<Duration>, work: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let Some(limit) = limit else {
+        return Some(work());
+    };
+    let (tx, rx) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        let _ = tx.send(work());
+    });
+    match rx.recv_timeout(limit) {
+        Ok(result) => Some(result),
+        Err(RecvTimeoutError::Timeout) => None,
+        // The worker panicked before sending; surface the panic as if the work had run inline
+        Err(RecvTimeoutError::Disconnected) => match worker.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => None,
+        },
+    }
+}
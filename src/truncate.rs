@@ -0,0 +1,268 @@
+//! truncate.rs
+//! Applies per-file token/size limits using a configurable truncation strategy.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use clap::ValueEnum;
+use memmap2::Mmap;
+use serde::Serialize;
+
+use crate::documents::{self, DocumentFormat};
+use crate::encoding::{self, Encoding};
+use crate::expandable::ExpandableBlock;
+
+/// Bytes reserved for the omission marker when splitting a size budget
+const MARKER_RESERVE: usize = 40;
+
+/// Files at least this large are memory-mapped when a size limit keeps only part of them
+const MMAP_THRESHOLD: u64 = 16 << 20;
+
+/// Which part of a file survives when it exceeds a limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TruncateStrategy {
+    /// Keep the beginning of the file
+    End,
+    /// Keep the beginning and end, omitting the middle
+    Middle,
+}
+
+impl TruncateStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TruncateStrategy::End => "end",
+            TruncateStrategy::Middle => "middle",
+        }
+    }
+}
+
+/// Records how a file was shortened by the limits
+#[derive(Debug, Clone, Serialize)]
+pub struct TruncationNotice {
+    pub strategy: TruncateStrategy,
+    /// `None` when only part of the file was read
+    pub original_tokens: Option<usize>,
+    pub kept_tokens: usize,
+    pub original_bytes: usize,
+    pub kept_bytes: usize,
+}
+
+impl TruncationNotice {
+    /// Render the notice as an expandable{} block
+    pub fn render(&self) -> String {
+        ExpandableBlock::new("truncated", "")
+            .add_attr("strategy", self.strategy.as_str())
+            .add_attr(
+                "tokens",
+                &match self.original_tokens {
+                    Some(t) => format!("{} of {}", self.kept_tokens, t),
+                    None => format!("{} of unknown", self.kept_tokens),
+                },
+            )
+            .add_attr("bytes", &format!("{} of {}", self.kept_bytes, self.original_bytes))
+            .render()
+    }
+}
+
+/// A file read with [`read_limited`]
+pub struct LimitedRead {
+    pub content: String,
+    /// Size of the whole file when `content` holds only the parts a size limit can keep
+    pub partial_of: Option<usize>,
+    /// Encoding the file was transcoded from
+    pub encoding: Encoding,
+}
+
+/// Read a file as UTF-8 text, loading only what the size limit and strategy can keep when it is over the limit.
+/// `file_bytes` is the size reported by `fs::metadata`. PDF and DOCX files are read whole and their text extracted.
+pub fn read_limited(
+    path: &Path,
+    file_bytes: u64,
+    size_limit: Option<usize>,
+    strategy: TruncateStrategy,
+) -> io::Result<LimitedRead> {
+    if let Some(format) = DocumentFormat::of(path) {
+        return Ok(LimitedRead {
+            content: documents::extract(path, format)?,
+            partial_of: None,
+            encoding: Encoding::Utf8,
+        });
+    }
+
+    // Middle keeps the head and tail, so it needs twice the limit to skip anything
+    let span = match strategy {
+        TruncateStrategy::End => 1,
+        TruncateStrategy::Middle => 2,
+    };
+    let Some(sl) = size_limit.filter(|&sl| (sl as u64).saturating_mul(span) < file_bytes) else {
+        let (content, encoding) = encoding::read_text(path)?;
+        return Ok(LimitedRead {
+            content,
+            partial_of: None,
+            encoding,
+        });
+    };
+
+    let mut file = File::open(path)?;
+    // Huge files are mapped so the kept parts are decoded in place instead of being copied into buffers first.
+    // SAFETY: the map is only read here; like any reader, we may see inconsistent bytes if another process
+    // rewrites the file meanwhile, and mapping fails cleanly (falling back to reads) where it isn't supported.
+    let map = if file_bytes >= MMAP_THRESHOLD { unsafe { Mmap::map(&file) }.ok() } else { None };
+    let head: Cow<[u8]> = match &map {
+        Some(map) => Cow::Borrowed(&map[..sl.min(map.len())]),
+        None => {
+            let mut head = Vec::with_capacity(sl);
+            (&mut file).take(sl as u64).read_to_end(&mut head)?;
+            Cow::Owned(head)
+        }
+    };
+    let encoding = encoding::detect(&head)?;
+    let mut content = encoding.decode(&head);
+    if strategy == TruncateStrategy::Middle {
+        // Keep UTF-16 code units aligned
+        let wide = matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be);
+        let back = if wide && (file_bytes - sl as u64) % 2 == 1 { sl + 1 } else { sl };
+        let tail: Cow<[u8]> = match &map {
+            Some(map) => Cow::Borrowed(&map[map.len().saturating_sub(back)..]),
+            None => {
+                file.seek(SeekFrom::End(-(back as i64)))?;
+                let mut tail = Vec::with_capacity(back);
+                file.read_to_end(&mut tail)?;
+                Cow::Owned(tail)
+            }
+        };
+        content.push_str(&encoding.decode(&tail));
+    }
+
+    Ok(LimitedRead {
+        content,
+        partial_of: Some(file_bytes as usize),
+        encoding,
+    })
+}
+
+/// Apply token and size limits, returning the kept content, a notice if anything was cut,
+/// and the whitespace tokens of the whole `content` (counted in the same pass that finds the cut).
+/// `partial_of` is the full file size when `content` came from a partial [`read_limited`].
+pub fn apply_limits(
+    content: &str,
+    partial_of: Option<usize>,
+    token_limit: Option<usize>,
+    size_limit: Option<usize>,
+    strategy: TruncateStrategy,
+) -> (String, Option<TruncationNotice>, usize) {
+    let (original_tokens, token_cut) = scan_tokens(content, token_limit);
+    let over_tokens = token_cut.is_some();
+    let over_size = partial_of.is_some() || size_limit.is_some_and(|sl| content.len() > sl);
+    if !over_tokens && !over_size {
+        return (content.to_string(), None, original_tokens);
+    }
+
+    let kept = match strategy {
+        TruncateStrategy::End => truncate_end(content, token_cut, size_limit),
+        TruncateStrategy::Middle => {
+            truncate_middle(content, token_limit, size_limit, partial_of.is_none())
+        }
+    };
+
+    let notice = TruncationNotice {
+        strategy,
+        original_tokens: if partial_of.is_some() { None } else { Some(original_tokens) },
+        kept_tokens: scan_tokens(&kept, None).0,
+        original_bytes: partial_of.unwrap_or(content.len()),
+        kept_bytes: kept.len(),
+    };
+    (kept, Some(notice), original_tokens)
+}
+
+/// Count whitespace-delimited tokens in one pass over `text`, without collecting them.
+/// With more than `limit` tokens, also returns the byte offset just past token `limit`.
+fn scan_tokens(text: &str, limit: Option<usize>) -> (usize, Option<usize>) {
+    let (mut count, mut cut, mut token_end, mut in_token) = (0, None, 0, false);
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            in_token = false;
+            continue;
+        }
+        if !in_token {
+            // Token `limit + 1` starts here, so the kept text ends with the previous one
+            if cut.is_none() && limit == Some(count) {
+                cut = Some(token_end);
+            }
+            count += 1;
+            in_token = true;
+        }
+        token_end = i + c.len_utf8();
+    }
+    (count, cut)
+}
+
+/// Keep the text before the token cut and within the size limit, preserving its layout
+fn truncate_end(content: &str, token_cut: Option<usize>, size_limit: Option<usize>) -> String {
+    let end = token_cut.unwrap_or(content.len());
+    let end = size_limit.map_or(end, |sl| floor_char_boundary(content, end.min(sl)));
+    content[..end].to_string()
+}
+
+/// Keep whole lines from the head and tail, each within half of the budget.
+/// Without `count_lines` the content is a partial read and the omitted line count is unknown.
+fn truncate_middle(
+    content: &str,
+    token_limit: Option<usize>,
+    size_limit: Option<usize>,
+    count_lines: bool,
+) -> String {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let size_budget = size_limit.map(|sl| sl.saturating_sub(MARKER_RESERVE));
+
+    let head_tokens = token_limit.map(|tl| tl / 2);
+    let head_bytes = size_budget.map(|sl| sl / 2);
+    let head = take_lines(lines.iter(), head_tokens, head_bytes);
+
+    let tail_tokens = token_limit.map(|tl| tl - tl / 2);
+    let tail_bytes = size_budget.map(|sl| sl - sl / 2);
+    let tail = take_lines(lines[head..].iter().rev(), tail_tokens, tail_bytes);
+
+    let omitted = lines.len() - head - tail;
+    let mut out = lines[..head].concat();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    if count_lines {
+        out.push_str(&format!("... [{} lines omitted] ...\n", omitted));
+    } else {
+        out.push_str("... [middle omitted] ...\n");
+    }
+    out.push_str(&lines[lines.len() - tail..].concat());
+    out
+}
+
+/// Count how many lines fit within the given token and byte budgets
+fn take_lines<'a>(
+    lines: impl Iterator<Item = &'a &'a str>,
+    tokens: Option<usize>,
+    bytes: Option<usize>,
+) -> usize {
+    let (mut used_tokens, mut used_bytes, mut taken) = (0, 0, 0);
+    for line in lines {
+        used_tokens += line.split_whitespace().count();
+        used_bytes += line.len();
+        if tokens.is_some_and(|t| used_tokens > t) || bytes.is_some_and(|b| used_bytes > b) {
+            break;
+        }
+        taken += 1;
+    }
+    taken
+}
+
+/// Largest index <= `index` that lies on a UTF-8 character boundary
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut i = index.min(s.len());
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
@@ -0,0 +1,196 @@
+//! tsconfig.rs
+//! JS/TS module resolution for `{ext}`: extension and `index` probing, and path aliases from
+//! `tsconfig.json`/`jsconfig.json` (`compilerOptions.paths` and `baseUrl`) so imports like `@app/utils/date` resolve.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde_json::Value as Json;
+
+use crate::{deps, lang, walk};
+
+/// Config files looked up from a file's directory upwards, nearest first
+const CONFIG_NAMES: &[&str] = &["tsconfig.json", "jsconfig.json"];
+
+/// How deep an `extends` chain is followed
+const MAX_EXTENDS: usize = 8;
+
+/// Suffixes tried after an alias is substituted, in TypeScript's order
+const PROBES: &[&str] = &[".ts", ".tsx", ".d.ts", ".js", ".jsx", ".mjs", ".cjs", "/index.ts", "/index.tsx", "/index.js", "/index.jsx"];
+
+/// The module resolution options of one config file, with `extends` applied
+#[derive(Default)]
+struct Options {
+    /// Absolute `baseUrl`
+    base_url: Option<PathBuf>,
+    /// `paths` patterns and their targets
+    paths: Vec<(String, Vec<String>)>,
+    /// Directory of the config that set `paths`, which they are relative to when there is no `baseUrl`
+    paths_dir: PathBuf,
+}
+
+/// Alias resolution for the JS and TS files of a run; config files are read once per directory
+pub struct TsPaths {
+    root: PathBuf,
+    by_dir: HashMap<PathBuf, Option<Rc<Options>>>,
+}
+
+impl TsPaths {
+    pub fn new(root: &Path) -> Self {
+        TsPaths { root: root.to_path_buf(), by_dir: HashMap::new() }
+    }
+
+    /// The file an import `reference` in the JS or TS file `source` names: a relative import with another extension
+    /// or naming a directory's index file, or a bare import through the nearest config's `paths` or `baseUrl`.
+    /// References get the source's extension appended when parsed, so both forms are tried.
+    pub fn resolve(&mut self, source: &Path, reference: &str) -> Option<PathBuf> {
+        if !matches!(lang::language(source), Some("javascript" | "typescript")) || reference.starts_with('/') {
+            return None;
+        }
+        let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let stripped = reference.strip_suffix(ext).and_then(|r| r.strip_suffix('.'));
+        if reference.starts_with('.') {
+            let found = std::iter::once(reference).chain(stripped).find_map(|spec| probe(&deps::resolve(source, spec, &self.root)?));
+            return found.filter(|p| p.starts_with(&self.root));
+        }
+        let options = self.options(source.parent()?)?;
+        for spec in std::iter::once(reference).chain(stripped) {
+            if !options.paths.is_empty() {
+                let base = options.base_url.as_deref().unwrap_or(&options.paths_dir);
+                // The pattern with the longest prefix before its `*` wins; an exact pattern beats any wildcard
+                let best = options
+                    .paths
+                    .iter()
+                    .filter_map(|(pattern, targets)| Some((wildcard(pattern, spec)?, pattern, targets)))
+                    .max_by_key(|(_, pattern, _)| pattern.find('*').unwrap_or(usize::MAX));
+                if let Some((star, _, targets)) = best {
+                    let found = targets.iter().find_map(|t| probe(&base.join(t.replacen('*', star, 1))));
+                    if found.is_some() {
+                        return found.filter(|p| p.starts_with(&self.root));
+                    }
+                }
+            }
+            if let Some(found) = options.base_url.as_ref().and_then(|base| probe(&base.join(spec))) {
+                return Some(found).filter(|p| p.starts_with(&self.root));
+            }
+        }
+        None
+    }
+
+    /// Options of the config file nearest `dir`, at or below the root
+    fn options(&mut self, dir: &Path) -> Option<Rc<Options>> {
+        if let Some(options) = self.by_dir.get(dir) {
+            return options.clone();
+        }
+        let own = CONFIG_NAMES.iter().map(|name| dir.join(name)).find(|p| p.is_file());
+        let options = match own {
+            Some(file) => Some(Rc::new(load(&file, MAX_EXTENDS))),
+            None if dir != self.root => dir.parent().filter(|p| p.starts_with(&self.root)).and_then(|p| self.options(p)),
+            None => None,
+        };
+        self.by_dir.insert(dir.to_path_buf(), options.clone());
+        options
+    }
+}
+
+/// Read `file` and the chain of relative configs it `extends`; unreadable or malformed files contribute nothing
+fn load(file: &Path, depth: usize) -> Options {
+    let Some(json) = fs::read_to_string(file).ok().and_then(|text| serde_json::from_str::<Json>(&strip_jsonc(&text)).ok()) else {
+        return Options::default();
+    };
+    let dir = file.parent().unwrap_or(Path::new(""));
+    let mut options = match json["extends"].as_str() {
+        Some(base) if depth > 0 && base.starts_with('.') => {
+            let base = match base.ends_with(".json") {
+                true => dir.join(base),
+                false => dir.join(format!("{}.json", base)),
+            };
+            load(&base, depth - 1)
+        }
+        _ => Options::default(),
+    };
+    let compiler = &json["compilerOptions"];
+    if let Some(base_url) = compiler["baseUrl"].as_str() {
+        options.base_url = Some(dir.join(base_url));
+    }
+    if let Some(paths) = compiler["paths"].as_object() {
+        let patterns = paths
+            .iter()
+            .map(|(pattern, targets)| {
+                let targets = targets.as_array().into_iter().flatten().filter_map(|t| t.as_str().map(String::from));
+                (pattern.clone(), targets.collect())
+            })
+            .collect();
+        options.paths = patterns;
+        options.paths_dir = dir.to_path_buf();
+    }
+    options
+}
+
+/// What the `*` in `pattern` stands for in `spec` (empty for an exact match), or `None` if it doesn't match
+fn wildcard<'a>(pattern: &str, spec: &'a str) -> Option<&'a str> {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) if spec.len() >= prefix.len() + suffix.len() => spec.strip_prefix(prefix)?.strip_suffix(suffix),
+        Some(_) => None,
+        None => (pattern == spec).then_some(""),
+    }
+}
+
+/// `path` itself, or with the extension (or `/index` file) TypeScript would try
+pub fn probe(path: &Path) -> Option<PathBuf> {
+    let bare = path.with_extension("");
+    let bare = bare.to_string_lossy();
+    std::iter::once(path.to_path_buf())
+        .chain(PROBES.iter().map(|suffix| PathBuf::from(format!("{}{}", bare, suffix))))
+        .find(|p| p.is_file())
+        .and_then(|p| walk::canonicalize(&p).ok())
+}
+
+/// tsconfig files are JSON with comments and trailing commas; drop both so serde_json can read them
+fn strip_jsonc(text: &str) -> String {
+    let mut bare = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', _) if in_string => {
+                bare.push(c);
+                bare.extend(chars.next());
+            }
+            ('"', _) => {
+                in_string = !in_string;
+                bare.push(c);
+            }
+            ('/', Some('/')) if !in_string => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) if !in_string => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            _ => bare.push(c),
+        }
+    }
+    // Comments are gone, so a comma is trailing when only whitespace separates it from `}` or `]`
+    let mut out = String::with_capacity(bare.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in bare.char_indices() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' && bare[i + 1..].trim_start().starts_with(['}', ']']) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
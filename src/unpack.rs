@@ -0,0 +1,245 @@
+//! unpack.rs
+//! Splits an aggregated bundle (expandable blocks or `--json` output) back into files.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::logging::Logger;
+
+/// A file recovered from a bundle
+#[derive(Debug)]
+pub struct UnpackedFile {
+    pub path: String,
+    pub content: String,
+    pub truncated: bool,
+    /// Earlier file with identical content (`--dedup` stubs)
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonBundle {
+    files: Vec<JsonFile>,
+}
+
+#[derive(Deserialize)]
+struct JsonFile {
+    path: Option<String>,
+    metadata: Option<JsonMetadata>,
+    content: Option<String>,
+    truncation: Option<serde_json::Value>,
+    duplicate_of: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonMetadata {
+    relative_path: String,
+}
+
+/// A parsed `expandable{}` block
+struct Block {
+    tag: String,
+    attrs: Vec<(String, String)>,
+    lines: Vec<String>,
+}
+
+/// Unpack `bundle` (or stdin for `-`) into `dest`, returning the number of files written
+pub fn run(bundle: &Path, dest: &Path, force: bool, logger: &Logger) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut text = String::new();
+    if bundle.as_os_str() == "-" {
+        io::stdin().read_to_string(&mut text)?;
+    } else {
+        text = fs::read_to_string(bundle)?;
+    }
+
+    let files = parse_bundle(&text)?;
+    let mut written = 0;
+    for file in files {
+        let Some(target) = safe_join(dest, &file.path) else {
+            logger.warn(&format!("Skipping {}: path escapes the destination", file.path));
+            continue;
+        };
+        if target.exists() && !force {
+            logger.warn(&format!("Skipping {}: already exists (use --force to overwrite)", target.display()));
+            continue;
+        }
+        if file.truncated {
+            logger.warn(&format!("{} was truncated in the bundle; writing the kept part only", file.path));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, &file.content)?;
+        logger.debug(&format!("Wrote {}", target.display()));
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Parse either bundle format into files
+pub fn parse_bundle(text: &str) -> Result<Vec<UnpackedFile>, String> {
+    let mut files = if text.trim_start().starts_with('{') {
+        parse_json(text)?
+    } else {
+        parse_expandable(text)?
+    };
+    resolve_duplicates(&mut files);
+    Ok(files)
+}
+
+/// Replace `--dedup` stubs with the content of the file they duplicate
+fn resolve_duplicates(files: &mut [UnpackedFile]) {
+    let originals: HashMap<String, (String, bool)> = files
+        .iter()
+        .filter(|f| f.duplicate_of.is_none())
+        .map(|f| (f.path.clone(), (f.content.clone(), f.truncated)))
+        .collect();
+    for file in files.iter_mut() {
+        if let Some((content, truncated)) = file.duplicate_of.as_ref().and_then(|p| originals.get(p)) {
+            file.content = content.clone();
+            file.truncated = *truncated;
+        }
+    }
+}
+
+fn parse_expandable(text: &str) -> Result<Vec<UnpackedFile>, String> {
+    let mut files = Vec::new();
+    for block in parse_blocks(text).into_iter().filter(|b| b.tag == "file") {
+        let (inner, content_lines) = split_inner_blocks(&block.lines);
+        let path = block
+            .attr("path")
+            .map(str::to_string)
+            .or_else(|| inner.iter().find_map(metadata_path));
+        let Some(path) = path else {
+            return Err("file block without a path; re-run bound with a newer version or --meta".to_string());
+        };
+        files.push(UnpackedFile {
+            path,
+            content: content_lines.join("\n"),
+            truncated: inner.iter().any(|b| b.tag == "truncated"),
+            duplicate_of: block.attr("duplicate_of").map(str::to_string),
+        });
+    }
+    Ok(files)
+}
+
+fn parse_json(text: &str) -> Result<Vec<UnpackedFile>, String> {
+    let bundle: JsonBundle = serde_json::from_str(text).map_err(|e| format!("invalid JSON bundle: {}", e))?;
+    bundle
+        .files
+        .into_iter()
+        .map(|f| {
+            let path = f
+                .path
+                .or(f.metadata.map(|m| m.relative_path))
+                .ok_or("JSON file entry without a path")?;
+            Ok(UnpackedFile {
+                path,
+                content: f.content.unwrap_or_default(),
+                truncated: f.truncation.is_some_and(|t| !t.is_null()),
+                duplicate_of: f.duplicate_of,
+            })
+        })
+        .collect()
+}
+
+impl Block {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parse top-level blocks; nested blocks stay as indented content lines
+fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    let mut in_content = false;
+
+    for line in text.lines() {
+        // Blocks are written back to back, so a closing brace may share a line with the next opening
+        let (closes, rest) = match line.strip_prefix('}') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if closes {
+            blocks.extend(current.take());
+            in_content = false;
+        }
+        if rest == "expandable{" {
+            current = Some(Block { tag: String::new(), attrs: Vec::new(), lines: Vec::new() });
+            in_content = false;
+            continue;
+        }
+        let Some(block) = current.as_mut() else { continue };
+
+        if in_content {
+            block.lines.push(line.strip_prefix("    ").unwrap_or(line.trim_start()).to_string());
+        } else if line == "  content: |" {
+            in_content = true;
+        } else if let Some((key, value)) = line.trim_start().split_once(": ") {
+            if key == "type" {
+                block.tag = value.to_string();
+            } else {
+                block.attrs.push((key.to_string(), value.to_string()));
+            }
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+/// Separate leading metadata/truncation blocks and a trailing furnace block from the file content
+fn split_inner_blocks(lines: &[String]) -> (Vec<Block>, Vec<String>) {
+    let mut inner = Vec::new();
+    let mut start = 0;
+    while lines.get(start).is_some_and(|l| l == "expandable{")
+        && lines.get(start + 1).is_some_and(|l| l == "  type: metadata" || l == "  type: truncated")
+    {
+        let Some(len) = lines[start..].iter().position(|l| l == "}") else { break };
+        inner.extend(parse_blocks(&lines[start..=start + len].join("\n")));
+        start += len + 1;
+    }
+
+    let mut content = lines[start..].to_vec();
+    // Each section ends with a blank line added after the content
+    if content.last().is_some_and(|l| l.is_empty()) {
+        content.pop();
+    }
+    if content.last().is_some_and(|l| l == "}") {
+        let furnace = (0..content.len()).rev().find(|&i| {
+            content[i] == "expandable{" && content.get(i + 1).is_some_and(|l| l == "  type: furnace")
+        });
+        if let Some(i) = furnace {
+            content.truncate(i);
+            if content.last().is_some_and(|l| l.is_empty()) {
+                content.pop();
+            }
+        }
+    }
+    (inner, content)
+}
+
+/// The path recorded in a `--meta` header block
+fn metadata_path(block: &Block) -> Option<String> {
+    if block.tag != "metadata" {
+        return None;
+    }
+    block
+        .lines
+        .iter()
+        .find_map(|l| l.strip_prefix("📄 FILE: "))
+        .map(|p| p.trim_end().to_string())
+}
+
+/// Join a bundle path onto `dest`, rejecting absolute paths and `..`
+fn safe_join(dest: &Path, path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path);
+    if relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        Some(dest.join(relative))
+    } else {
+        None
+    }
+}
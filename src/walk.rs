@@ -0,0 +1,345 @@
+//! walk.rs
+//! Builds the candidate file list, either by walking a directory or from an explicit list.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+use crate::telemetry::SkipReason;
+
+/// Ignore files read in every directory, highest precedence first; `.gitignore` only counts inside a git repository
+const IGNORE_FILES: [&str; 3] = [".boundignore", ".ignore", ".gitignore"];
+
+/// Walk `root` honoring .gitignore/.boundignore and the optional depth limit.
+/// Entries that could not be read, or were left out by ignore rules or the depth limit, are appended to `skipped`.
+pub fn walk_files(root: &Path, depth_limit: Option<usize>, skipped: &mut Vec<(PathBuf, SkipReason)>) -> Vec<PathBuf> {
+    let base = canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    // Ignore files above the root apply too, outermost first
+    let mut ancestors: Vec<&Path> = base.ancestors().skip(1).collect();
+    ancestors.reverse();
+    let mut walk = Walk {
+        root,
+        rules: ancestors.into_iter().map(Rules::read).collect(),
+        global: GitignoreBuilder::new(&base).build_global().0,
+        base: &base,
+        depth_limit,
+        files: Vec::new(),
+        skipped,
+    };
+    walk.visit(root, 0);
+    walk.files
+}
+
+/// One traversal: each directory is listed once, and what its entries are matched against is kept on a stack
+struct Walk<'a> {
+    root: &'a Path,
+    /// Canonical `root`, which the ignore files' patterns are matched under
+    base: &'a Path,
+    /// Rules of every directory from the filesystem root down to the one being listed
+    rules: Vec<Rules>,
+    /// The user's global gitignore (`core.excludesFile`)
+    global: Gitignore,
+    depth_limit: Option<usize>,
+    files: Vec<PathBuf>,
+    skipped: &'a mut Vec<(PathBuf, SkipReason)>,
+}
+
+impl Walk<'_> {
+    fn visit(&mut self, dir: &Path, depth: usize) {
+        if self.depth_limit == Some(depth) {
+            if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some()) {
+                self.skipped.push((dir.to_path_buf(), SkipReason::TooDeep));
+            }
+            return;
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            self.skipped.push((dir.to_path_buf(), SkipReason::Unreadable));
+            return;
+        };
+        self.rules.push(Rules::read(&self.absolute(dir)));
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                self.skipped.push((path, SkipReason::Unreadable));
+                continue;
+            };
+            if self.is_ignored(&path, file_type.is_dir()) {
+                if entry.file_name() != ".git" {
+                    self.skipped.push((path, SkipReason::Ignored));
+                }
+            } else if file_type.is_dir() {
+                self.visit(&path, depth + 1);
+            } else if file_type.is_file() {
+                self.files.push(path);
+            } else if is_special(file_type) {
+                self.skipped.push((path, SkipReason::Special));
+            } else {
+                // Symlinks are not followed
+                self.skipped.push((path, SkipReason::Ignored));
+            }
+        }
+        self.rules.pop();
+    }
+
+    /// Whether the rules on the stack ignore `path`. Each kind of ignore file is searched from the nearest directory
+    /// up, and the first pattern to match decides (`!pattern` keeps the entry); with none matching, hidden names are ignored.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = self.absolute(path);
+        let any_git = self.rules.iter().any(|r| r.has_git);
+        let mut found = [None; 4];
+        let mut saw_git = false;
+        for rules in self.rules.iter().rev() {
+            // Git's own files stop applying past the repository they belong to
+            let in_repo = any_git && !saw_git;
+            for (i, matcher) in rules.files.iter().chain([&rules.exclude]).enumerate() {
+                if found[i].is_none() && (i < 2 || in_repo) {
+                    found[i] = verdict(matcher.matched(&path, is_dir));
+                }
+            }
+            saw_git |= rules.has_git;
+        }
+        let global = any_git.then(|| verdict(self.global.matched(&path, is_dir))).flatten();
+        match found.into_iter().flatten().next().or(global) {
+            Some(ignored) => ignored,
+            None => path.file_name().is_some_and(|name| name.as_encoded_bytes().starts_with(b".")),
+        }
+    }
+
+    /// `path` under the canonical root
+    fn absolute(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(self.root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => self.base.join(rel),
+            Ok(_) => self.base.to_path_buf(),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+/// The ignore files of one directory, and `info/exclude` when it holds a repository
+struct Rules {
+    /// One matcher per `IGNORE_FILES` entry, empty when the file is missing
+    files: [Gitignore; 3],
+    exclude: Gitignore,
+    has_git: bool,
+}
+
+impl Rules {
+    fn read(dir: &Path) -> Rules {
+        let dot_git = dir.join(".git");
+        Rules {
+            files: IGNORE_FILES.map(|name| matcher(dir, &dir.join(name))),
+            exclude: git_dir(&dot_git).map_or_else(Gitignore::empty, |git| matcher(dir, &git.join("info/exclude"))),
+            has_git: dot_git.exists() || dir.join(".jj").exists(),
+        }
+    }
+}
+
+/// The patterns in `file`, relative to `dir`; a missing or malformed file matches nothing
+fn matcher(dir: &Path, file: &Path) -> Gitignore {
+    if !file.is_file() {
+        return Gitignore::empty();
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    builder.add(file);
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// The repository directory `.git` stands for: itself, or for a worktree the common directory of the `gitdir:` it names
+fn git_dir(dot_git: &Path) -> Option<PathBuf> {
+    if dot_git.is_dir() {
+        return Some(dot_git.to_path_buf());
+    }
+    let text = fs::read_to_string(dot_git).ok()?;
+    let gitdir = dot_git.parent()?.join(text.strip_prefix("gitdir:")?.trim());
+    match fs::read_to_string(gitdir.join("commondir")) {
+        Ok(common) => Some(gitdir.join(common.trim())),
+        Err(_) => Some(gitdir),
+    }
+}
+
+/// `Some(true)` for an ignoring pattern, `Some(false)` for a `!pattern`, `None` when nothing matched
+fn verdict<T>(matched: Match<T>) -> Option<bool> {
+    match matched {
+        Match::None => None,
+        Match::Ignore(_) => Some(true),
+        Match::Whitelist(_) => Some(false),
+    }
+}
+
+/// Whether an entry is a FIFO, socket, or device node, whose reads can block forever or never end
+pub fn is_special(file_type: fs::FileType) -> bool {
+    !file_type.is_file() && !file_type.is_dir() && !file_type.is_symlink()
+}
+
+/// The (device, inode) pair identifying a file however it is reached; `None` where the platform has no stable id
+#[cfg(unix)]
+pub fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Whether `path` resolves to a special file (see `is_special`)
+pub fn is_special_path(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| is_special(m.file_type()))
+}
+
+/// Read newline-separated paths from a file, or from stdin when `source` is `-`.
+/// Order is preserved and duplicates are dropped.
+pub fn read_file_list(source: &Path) -> io::Result<Vec<PathBuf>> {
+    let reader: Box<dyn BufRead> = if source.as_os_str() == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(fs::File::open(source)?))
+    };
+
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let entry = line.trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let path = canonicalize(Path::new(entry)).unwrap_or_else(|_| PathBuf::from(entry));
+        if seen.insert(path.clone()) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Canonical form of `path`. On Windows this avoids `\\?\` prefixes where the plain form is equivalent,
+/// since git and `/`-joined relative paths don't work with them; std still reads paths beyond MAX_PATH.
+pub fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    dunce::canonicalize(path)
+}
+
+/// Deepest directory containing every target (a file contributes its parent directory)
+pub fn common_root(targets: &[PathBuf]) -> PathBuf {
+    let mut root: Option<PathBuf> = None;
+    for target in targets {
+        let dir = if target.is_dir() {
+            target.as_path()
+        } else {
+            target.parent().unwrap_or(target)
+        };
+        root = Some(match root {
+            None => dir.to_path_buf(),
+            Some(r) => r
+                .components()
+                .zip(dir.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    root.unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_keeps_odd_file_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let root = std::env::temp_dir().join(format!("bound-walk-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub\ndir")).unwrap();
+        let names: &[&[u8]] = &[b"new\nline.rs", b"bad\xff.rs", b"sub\ndir/caf\xc3\xa9.rs", b" lead and trail .rs "];
+        for name in names {
+            fs::write(root.join(OsStr::from_bytes(name)), "fn main() {}\n").unwrap();
+        }
+
+        let mut skipped = Vec::new();
+        let files: HashSet<PathBuf> = walk_files(&root, None, &mut skipped).into_iter().collect();
+        let expected: HashSet<PathBuf> = names.iter().map(|n| root.join(OsStr::from_bytes(n))).collect();
+        assert_eq!(files, expected);
+        assert!(skipped.is_empty());
+        for file in &files {
+            assert_eq!(canonicalize(file).unwrap(), canonicalize(&root).unwrap().join(file.strip_prefix(&root).unwrap()));
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn walk_reports_what_ignore_rules_and_the_depth_limit_leave_out() {
+        let root = std::env::temp_dir().join(format!("bound-ignore-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in [".git/info", "build", "sub", "src/deep/er"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let write = |path: &str, text: &str| fs::write(root.join(path), text).unwrap();
+        write(".gitignore", "*.log\n!keep.log\nbuild/\n");
+        write(".ignore", "keep.log\n");
+        write(".boundignore", "src/deep/er/\n");
+        write(".git/info/exclude", "x.rs\n");
+        write("sub/.gitignore", "!*.log\nsecret.rs\n");
+        for file in ["a.rs", "a.log", "keep.log", "x.rs", ".env", "build/out.rs", "sub/s.log", "sub/secret.rs", "src/b.rs", "src/deep/c.rs", "src/deep/er/d.rs"] {
+            write(file, "x\n");
+        }
+        let walked = |depth_limit| {
+            let mut skipped = Vec::new();
+            let mut files = walk_files(&root, depth_limit, &mut skipped);
+            files.sort();
+            skipped.sort();
+            let rel = |p: &PathBuf| p.strip_prefix(&root).unwrap().to_string_lossy().into_owned();
+            (files.iter().map(rel).collect::<Vec<_>>(), skipped.iter().map(|(p, reason)| (rel(p), *reason)).collect::<Vec<_>>())
+        };
+
+        let (files, skipped) = walked(None);
+        assert_eq!(files, ["a.rs", "src/b.rs", "src/deep/c.rs", "sub/s.log"]);
+        let ignored: Vec<&str> = skipped.iter().filter(|(_, r)| *r == SkipReason::Ignored).map(|(p, _)| p.as_str()).collect();
+        assert_eq!(ignored, [".boundignore", ".env", ".gitignore", ".ignore", "a.log", "build", "keep.log", "src/deep/er", "sub/.gitignore", "sub/secret.rs", "x.rs"]);
+        assert_eq!(ignored.len(), skipped.len());
+
+        let (files, skipped) = walked(Some(2));
+        assert_eq!(files, ["a.rs", "src/b.rs", "sub/s.log"]);
+        assert!(skipped.contains(&("src/deep".to_string(), SkipReason::TooDeep)));
+
+        // Without a repository, .gitignore files are not honored
+        fs::remove_dir_all(root.join(".git")).unwrap();
+        assert!(walked(None).0.contains(&"a.log".to_string()));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn walk_and_canonicalize_past_max_path() {
+        let root = std::env::temp_dir().join(format!("bound-long-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let segment = "node_modules_package_with_a_rather_long_directory_name";
+        let deep = (0..8).fold(root.clone(), |dir, i| dir.join(format!("{}_{}", segment, i)));
+        fs::create_dir_all(&deep).unwrap();
+        let file = deep.join("index.js");
+        fs::write(&file, "module.exports = 1;\n").unwrap();
+        assert!(file.as_os_str().len() > 260);
+
+        let mut skipped = Vec::new();
+        let files = walk_files(&root, None, &mut skipped);
+        assert_eq!(files, vec![file.clone()]);
+        assert!(skipped.is_empty());
+
+        let canonical = canonicalize(&file).unwrap();
+        assert!(canonical.as_os_str().len() > 260);
+        assert!(!canonical.to_string_lossy().starts_with(r"\\?\"));
+        assert_eq!(canonical, canonicalize(&root).unwrap().join(file.strip_prefix(&root).unwrap()));
+        assert_eq!(fs::read_to_string(&canonical).unwrap(), "module.exports = 1;\n");
+        assert_eq!(common_root(&[canonical.clone(), canonicalize(&root).unwrap()]), canonicalize(&root).unwrap());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
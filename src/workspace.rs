@@ -0,0 +1,291 @@
+//! workspace.rs
+//! `--package` selection in Cargo, pnpm, npm, and Yarn workspaces: maps package names to member directories
+//! and follows dependencies between members.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as Json;
+use toml::{Table, Value};
+
+use crate::walk;
+
+/// Dependency tables of a Cargo manifest, also looked up under each `[target.'cfg'.*]`
+const CARGO_DEP_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Dependency fields of a package.json
+const JS_DEP_FIELDS: &[&str] = &["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"];
+
+/// Directories `**` never descends into
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", ".git"];
+
+/// A workspace member package
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    pub dir: PathBuf,
+    /// Directories this member depends on by path; only those of other members are followed
+    pub deps: Vec<PathBuf>,
+    /// Names of packages this member depends on; those of other members are followed
+    pub dep_names: Vec<String>,
+}
+
+/// A workspace: the manifests that declare it and its member packages
+pub struct Workspace {
+    pub manifests: Vec<PathBuf>,
+    pub members: Vec<Member>,
+}
+
+impl Workspace {
+    /// Read the Cargo and JavaScript workspaces declared in `root`, or `None` when there are none
+    pub fn load(root: &Path) -> Result<Option<Self>, String> {
+        let root = &walk::canonicalize(root).map_err(|e| format!("Cannot read {}: {}", root.display(), e))?;
+        let mut workspace = Workspace { manifests: Vec::new(), members: Vec::new() };
+        workspace.load_cargo(root)?;
+        workspace.load_js(root)?;
+        Ok((!workspace.manifests.is_empty()).then_some(workspace))
+    }
+
+    /// Members of a root `Cargo.toml` `[workspace]`
+    fn load_cargo(&mut self, root: &Path) -> Result<(), String> {
+        let manifest = root.join("Cargo.toml");
+        let Some(table) = read_toml(&manifest)? else {
+            return Ok(());
+        };
+        let Some(workspace) = table.get("workspace").and_then(Value::as_table) else {
+            return Ok(());
+        };
+
+        let strings = |key: &str| -> Vec<&str> {
+            workspace.get(key).and_then(Value::as_array).map_or_else(Vec::new, |a| a.iter().filter_map(Value::as_str).collect())
+        };
+        let excluded: Vec<PathBuf> = strings("exclude").iter().flat_map(|p| expand(root, p)).collect();
+        let mut dirs: Vec<PathBuf> = strings("members").iter().flat_map(|p| expand(root, p)).collect();
+        if table.contains_key("package") {
+            dirs.push(root.to_path_buf());
+        }
+        // Paths of `[workspace.dependencies]`, which members inherit with `workspace = true`
+        let shared: HashMap<&str, PathBuf> = workspace
+            .get("dependencies")
+            .and_then(Value::as_table)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, dep)| Some((name.as_str(), root.join(dep.get("path")?.as_str()?))))
+            .collect();
+
+        for dir in dirs.into_iter().filter(|d| !excluded.contains(d)) {
+            let Some(member) = read_toml(&dir.join("Cargo.toml"))? else {
+                continue;
+            };
+            let Some(name) = member.get("package").and_then(|p| p.get("name")).and_then(Value::as_str) else {
+                continue;
+            };
+            let mut tables: Vec<&Table> = CARGO_DEP_TABLES.iter().filter_map(|t| member.get(*t)?.as_table()).collect();
+            for target in member.get("target").and_then(Value::as_table).into_iter().flat_map(|t| t.values()) {
+                tables.extend(CARGO_DEP_TABLES.iter().filter_map(|t| target.get(*t)?.as_table()));
+            }
+            let deps = tables
+                .into_iter()
+                .flatten()
+                .filter_map(|(key, dep)| match dep.get("path").and_then(Value::as_str) {
+                    Some(path) => Some(dir.join(path)),
+                    None if dep.get("workspace").and_then(Value::as_bool) == Some(true) => shared.get(key.as_str()).cloned(),
+                    None => None,
+                })
+                .filter_map(|path| walk::canonicalize(&path).ok())
+                .collect();
+            self.members.push(Member { name: name.to_string(), dir, deps, dep_names: Vec::new() });
+        }
+        self.manifests.push(manifest);
+        Ok(())
+    }
+
+    /// Members listed in `pnpm-workspace.yaml` or the root package.json's `workspaces`
+    fn load_js(&mut self, root: &Path) -> Result<(), String> {
+        let pnpm = root.join("pnpm-workspace.yaml");
+        let package_json = root.join("package.json");
+        let (manifest, patterns) = match fs::read_to_string(&pnpm) {
+            Ok(text) => (pnpm, pnpm_packages(&text)),
+            Err(_) => {
+                let Some(package) = read_json(&package_json)? else {
+                    return Ok(());
+                };
+                // Either a list of globs or, in Yarn, `{ "packages": [...] }`
+                let list = match &package["workspaces"] {
+                    Json::Array(list) => list.clone(),
+                    other => other["packages"].as_array().cloned().unwrap_or_default(),
+                };
+                if list.is_empty() {
+                    return Ok(());
+                }
+                (package_json.clone(), list.iter().filter_map(|p| p.as_str().map(str::to_string)).collect())
+            }
+        };
+
+        let (excluded, included): (Vec<&String>, Vec<&String>) = patterns.iter().partition(|p| p.starts_with('!'));
+        let excluded: Vec<PathBuf> = excluded.iter().flat_map(|p| expand(root, &p[1..])).collect();
+        let mut seen = HashSet::new();
+        for dir in included.iter().flat_map(|p| expand(root, p)) {
+            if excluded.contains(&dir) || !seen.insert(dir.clone()) {
+                continue;
+            }
+            let Some(package) = read_json(&dir.join("package.json"))? else {
+                continue;
+            };
+            let Some(name) = package["name"].as_str() else {
+                continue;
+            };
+            let dep_names = JS_DEP_FIELDS
+                .iter()
+                .filter_map(|field| package[*field].as_object())
+                .flat_map(|deps| deps.keys().cloned())
+                .collect();
+            self.members.push(Member { name: name.to_string(), dir, deps: Vec::new(), dep_names });
+        }
+        if manifest != package_json && package_json.is_file() {
+            // pnpm keeps shared scripts and dependencies in the root package.json
+            self.manifests.push(package_json);
+        }
+        self.manifests.push(manifest);
+        Ok(())
+    }
+
+    /// The named packages and every member they depend on, directly or transitively
+    pub fn select(&self, names: &[String]) -> Result<Vec<&Member>, String> {
+        let by_dir: HashMap<&Path, &Member> = self.members.iter().map(|m| (m.dir.as_path(), m)).collect();
+        let mut queue = VecDeque::new();
+        for name in names {
+            let matching: Vec<&Member> = self.members.iter().filter(|m| &m.name == name).collect();
+            match matching.is_empty() {
+                false => queue.extend(matching),
+                true => {
+                    let known: BTreeSet<&str> = self.members.iter().map(|m| m.name.as_str()).collect();
+                    return Err(format!(
+                        "no package named {:?} in the workspace (members: {})",
+                        name,
+                        known.into_iter().collect::<Vec<_>>().join(", ")
+                    ));
+                }
+            }
+        }
+        let mut selected: Vec<&Member> = Vec::new();
+        while let Some(member) = queue.pop_front() {
+            if selected.iter().any(|m| m.dir == member.dir) {
+                continue;
+            }
+            selected.push(member);
+            queue.extend(member.deps.iter().filter_map(|d| by_dir.get(d.as_path()).copied()));
+            queue.extend(self.members.iter().filter(|m| member.dep_names.contains(&m.name)));
+        }
+        Ok(selected)
+    }
+}
+
+/// Parse a TOML file, or `None` if it doesn't exist
+fn read_toml(path: &Path) -> Result<Option<Table>, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => text.parse::<Table>().map(Some).map_err(|e| format!("Cannot parse {}: {}", path.display(), e)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parse a JSON file, or `None` if it doesn't exist
+fn read_json(path: &Path) -> Result<Option<Json>, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map(Some).map_err(|e| format!("Cannot parse {}: {}", path.display(), e)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Globs in the `packages:` list of a pnpm-workspace.yaml
+fn pnpm_packages(yaml: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_list = false;
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_list = trimmed.starts_with("packages:");
+        } else if let (true, Some(item)) = (in_list, trimmed.strip_prefix('-')) {
+            let item = item.split(" #").next().unwrap_or(item).trim();
+            packages.push(item.trim_matches(['\'', '"']).to_string());
+        }
+    }
+    packages
+}
+
+/// Directories matching a member pattern such as `crates/*` or `apps/**`: `*` and `?` match within one
+/// path segment and `**` matches any number of directories
+fn expand(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    for segment in pattern.split('/').filter(|s| !s.is_empty() && *s != ".") {
+        let mut next = Vec::new();
+        for dir in dirs {
+            if segment == "**" {
+                descendants(&dir, &mut next);
+                continue;
+            }
+            if !segment.contains(['*', '?']) {
+                next.push(dir.join(segment));
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut matched: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_name().to_str().is_some_and(|name| wildcard(segment, name)))
+                .map(|e| e.path())
+                .collect();
+            matched.sort();
+            next.extend(matched);
+        }
+        dirs = next;
+    }
+    dirs.into_iter().filter(|d| d.is_dir()).filter_map(|d| walk::canonicalize(&d).ok()).collect()
+}
+
+/// `dir` and every directory below it, skipping hidden, dependency, and build directories
+fn descendants(dir: &Path, out: &mut Vec<PathBuf>) {
+    out.push(dir.to_path_buf());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|e| e.file_name().to_str().is_some_and(|n| !n.starts_with('.') && !SKIPPED_DIRS.contains(&n)))
+        .map(|e| e.path())
+        .collect();
+    subdirs.sort();
+    for subdir in subdirs {
+        descendants(&subdir, out);
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters and `?` any one
+fn wildcard(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut pi, mut ni) = (0, 0);
+    // Position after the last `*` and the name position it was tried against
+    let mut star = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi + 1, ni));
+            pi += 1;
+        } else if let Some((after, tried)) = star {
+            pi = after;
+            ni = tried + 1;
+            star = Some((after, tried + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}